@@ -0,0 +1,137 @@
+use std::io::Read;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Context;
+use mf2_parser::format_source;
+use mf2_parser::parse_with_options;
+use mf2_parser::FormatOptions;
+use mf2_parser::ParseOptions;
+use mf2_parser::SpecVersion;
+
+const USAGE: &str = "\
+Usage: mf2fmt [OPTIONS] [FILES]...
+
+Formats Message Format 2 messages. Files are formatted in place. If no files
+are given, a message is read from stdin and the formatted message is written
+to stdout.
+
+Options:
+  --check                  Only check if the files are formatted
+  --indent-width <WIDTH>   Indent variants of a matcher by WIDTH spaces [default: 2]
+  --no-align-keys          Do not align the keys of variants into columns
+  --spec-version <VERSION> One of draft, ldml46, ldml47 [default: ldml47]
+  -h, --help               Print this help";
+
+struct Args {
+  check: bool,
+  format_options: FormatOptions,
+  parse_options: ParseOptions,
+  files: Vec<PathBuf>,
+}
+
+fn main() -> Result<(), anyhow::Error> {
+  let args = parse_args(std::env::args().skip(1))?;
+
+  if args.files.is_empty() {
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+    let Some(formatted) = format_text(&text, "<stdin>", &args) else {
+      std::process::exit(1);
+    };
+    if args.check && formatted != text {
+      eprintln!("<stdin> is not formatted");
+      std::process::exit(1);
+    }
+    if !args.check {
+      std::io::stdout().write_all(formatted.as_bytes())?;
+    }
+    return Ok(());
+  }
+
+  let mut failed = false;
+  for path in &args.files {
+    let text = std::fs::read_to_string(path)
+      .with_context(|| format!("failed to read {}", path.display()))?;
+    let Some(formatted) =
+      format_text(&text, &path.display().to_string(), &args)
+    else {
+      failed = true;
+      continue;
+    };
+    if formatted == text {
+      continue;
+    }
+    if args.check {
+      eprintln!("{} is not formatted", path.display());
+      failed = true;
+    } else {
+      std::fs::write(path, formatted)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+  }
+
+  if failed {
+    std::process::exit(1);
+  }
+  Ok(())
+}
+
+/// Formats a message, or prints its diagnostics and returns `None` if it has
+/// syntax errors.
+fn format_text(text: &str, name: &str, args: &Args) -> Option<String> {
+  let (message, diagnostics, info) =
+    parse_with_options(text, args.parse_options.clone());
+  if !diagnostics.is_empty() {
+    for diagnostic in diagnostics {
+      let (line, column) = info.utf8_line_col(diagnostic.span().start);
+      eprintln!("{name}:{}:{}: {diagnostic}", line + 1, column + 1);
+    }
+    return None;
+  }
+  Some(format_source(&message, &args.format_options))
+}
+
+fn parse_args(
+  mut iter: impl Iterator<Item = String>,
+) -> Result<Args, anyhow::Error> {
+  let mut args = Args {
+    check: false,
+    format_options: FormatOptions::default(),
+    parse_options: ParseOptions::default(),
+    files: vec![],
+  };
+
+  while let Some(arg) = iter.next() {
+    match arg.as_str() {
+      "-h" | "--help" => {
+        println!("{USAGE}");
+        std::process::exit(0);
+      }
+      "--check" => args.check = true,
+      "--no-align-keys" => args.format_options.align_keys = false,
+      "--indent-width" => {
+        let width = iter.next().context("--indent-width requires a value")?;
+        args.format_options.indent_width = width
+          .parse()
+          .with_context(|| format!("invalid indent width: {width}"))?;
+      }
+      "--spec-version" => {
+        args.parse_options.spec_version = match iter.next().as_deref() {
+          Some("draft") => SpecVersion::Draft,
+          Some("ldml46") => SpecVersion::Ldml46,
+          Some("ldml47") => SpecVersion::Ldml47,
+          Some(other) => bail!("unknown spec version: {other}"),
+          None => bail!("--spec-version requires a value"),
+        };
+      }
+      flag if flag.starts_with('-') => {
+        bail!("unknown option: {flag}\n\n{USAGE}")
+      }
+      file => args.files.push(PathBuf::from(file)),
+    }
+  }
+
+  Ok(args)
+}
@@ -0,0 +1,476 @@
+//! Suggests completions at a location in a message.
+//!
+//! The context of a completion is found from the tokens before the location,
+//! which are also available for messages that do not parse, like a message
+//! that is being typed. Variables are taken from the (possibly partial) AST.
+
+use mf2_parser::ast;
+use mf2_parser::ast::strip_bidi;
+use mf2_parser::functions::FunctionRegistry;
+use mf2_parser::Location;
+use mf2_parser::Span;
+use mf2_parser::Spanned;
+use mf2_parser::Token;
+use mf2_parser::TokenKind;
+
+const KEYWORDS: [&str; 3] = [".input", ".local", ".match"];
+
+const PLURAL_CATEGORIES: [&str; 6] =
+  ["zero", "one", "two", "few", "many", "other"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompletionKind {
+  Function,
+  FunctionOption,
+  Variable,
+  Keyword,
+  Markup,
+  Key,
+}
+
+#[derive(Debug)]
+pub struct Completion {
+  pub kind: CompletionKind,
+  /// The text that replaces the word at the location.
+  pub label: String,
+  pub detail: Option<String>,
+  /// Markdown documentation of the completion.
+  pub documentation: Option<String>,
+}
+
+impl Completion {
+  fn new(kind: CompletionKind, label: impl Into<String>) -> Completion {
+    Completion {
+      kind,
+      label: label.into(),
+      detail: None,
+      documentation: None,
+    }
+  }
+}
+
+/// Suggests completions at a location. Returns the span of the word that the
+/// completions replace (which may be empty) and the completions.
+pub fn completions(
+  message: &ast::Message,
+  tokens: &[Token],
+  location: Location,
+  functions: &FunctionRegistry,
+) -> Option<(Span, Vec<Completion>)> {
+  // The tokens before the location, where the last one may continue after it.
+  let end = tokens
+    .iter()
+    .position(|token| token.span.start >= location)
+    .unwrap_or(tokens.len());
+  let before = &tokens[..end];
+  let last = before.last();
+
+  let (word, rest) = match last {
+    Some(token)
+      if matches!(
+        token.kind,
+        TokenKind::Name
+          | TokenKind::Number
+          | TokenKind::Variable
+          | TokenKind::Keyword
+      ) =>
+    {
+      (token.span, &before[..before.len() - 1])
+    }
+    _ => (Span::new(location..location), before),
+  };
+  let previous = rest.last().map(|token| token.kind);
+
+  let completions = match last.map(|token| token.kind) {
+    Some(TokenKind::Variable) => variable_completions(message, rest, location),
+    Some(TokenKind::Keyword) if depth(rest) == 0 => keyword_completions(rest),
+    _ => match previous {
+      Some(TokenKind::FunctionSigil) => function_completions(functions),
+      Some(TokenKind::MarkupSigil) => markup_completions(tokens, word),
+      Some(TokenKind::Whitespace) => {
+        if let Some(function) = annotation_function(rest) {
+          option_completions(rest, &function, functions)
+        } else if is_variant_start(rest) {
+          key_completions()
+        } else {
+          return None;
+        }
+      }
+      _ => return None,
+    },
+  };
+  Some((word, completions))
+}
+
+/// The nesting depth of braces and quoted patterns after the tokens.
+fn depth(tokens: &[Token]) -> i32 {
+  tokens.iter().fold(0, |depth, token| match token.kind {
+    TokenKind::OpenBrace | TokenKind::OpenQuotedPattern => depth + 1,
+    TokenKind::CloseBrace | TokenKind::CloseQuotedPattern => depth - 1,
+    _ => depth,
+  })
+}
+
+/// Suggests the variables of the declarations before the location, or before
+/// the declaration that the location is in. Nothing is suggested for the name
+/// of a `.local` declaration, which must be a new variable.
+fn variable_completions(
+  message: &ast::Message,
+  before: &[Token],
+  location: Location,
+) -> Vec<Completion> {
+  let previous = before.iter().rev().find(|token| {
+    !matches!(token.kind, TokenKind::Whitespace | TokenKind::Bidi)
+  });
+  if previous.is_some_and(|token| {
+    token.kind == TokenKind::Keyword && token.text == ".local"
+  }) {
+    return vec![];
+  }
+
+  let ast::Message::Complex(message) = message else {
+    return vec![];
+  };
+  // The span of a declaration that is being typed is not reliable, so the
+  // start of its keyword is used instead.
+  let end = declaration_start(before).unwrap_or(location);
+  let mut completions = vec![];
+  for declaration in &message.declarations {
+    if declaration.span().end > end {
+      break;
+    }
+    let variable = match declaration {
+      ast::Declaration::InputDeclaration(input) => &input.expression.variable,
+      ast::Declaration::LocalDeclaration(local) => &local.variable,
+      ast::Declaration::ReservedStatement(_) => continue,
+    };
+    let label = format!("${}", strip_bidi(&variable.name));
+    if completions.iter().any(|c: &Completion| c.label == label) {
+      continue;
+    }
+    completions.push(Completion::new(CompletionKind::Variable, label));
+  }
+  completions
+}
+
+/// The start of the `.input` or `.local` declaration that the tokens end in.
+fn declaration_start(before: &[Token]) -> Option<Location> {
+  let mut start = None;
+  for (index, token) in before.iter().enumerate() {
+    if depth(&before[..index]) != 0 {
+      continue;
+    }
+    match token.kind {
+      TokenKind::Keyword => {
+        start =
+          matches!(token.text, ".input" | ".local").then_some(token.span.start);
+      }
+      TokenKind::OpenQuotedPattern => start = None,
+      _ => {}
+    }
+  }
+  start
+}
+
+/// Suggests the keywords that can start the next statement: declarations
+/// are only allowed before `.match`, and `.match` only once.
+fn keyword_completions(before: &[Token]) -> Vec<Completion> {
+  let has_match = before
+    .iter()
+    .any(|token| token.kind == TokenKind::Keyword && token.text == ".match");
+  if has_match {
+    return vec![];
+  }
+  KEYWORDS
+    .iter()
+    .map(|keyword| Completion::new(CompletionKind::Keyword, *keyword))
+    .collect()
+}
+
+fn function_completions(functions: &FunctionRegistry) -> Vec<Completion> {
+  let mut completions = functions
+    .iter()
+    .map(|(id, function)| {
+      let label = match &id.namespace {
+        Some(namespace) => format!("{}:{}", namespace, id.name),
+        None => id.name.to_string(),
+      };
+      Completion {
+        documentation: function.description().map(str::to_string),
+        ..Completion::new(CompletionKind::Function, label)
+      }
+    })
+    .collect::<Vec<_>>();
+  completions.sort_by(|a, b| a.label.cmp(&b.label));
+  completions
+}
+
+/// Suggests the names of the markup in the message, other than the one that
+/// is being typed.
+fn markup_completions(tokens: &[Token], word: Span) -> Vec<Completion> {
+  let mut completions: Vec<Completion> = vec![];
+  for pair in tokens.windows(2) {
+    let (sigil, name) = (&pair[0], &pair[1]);
+    if sigil.kind != TokenKind::MarkupSigil
+      || name.kind != TokenKind::Name
+      || name.span == word
+    {
+      continue;
+    }
+    let label = strip_bidi(name.text);
+    if !completions.iter().any(|c| c.label == label) {
+      completions.push(Completion::new(CompletionKind::Markup, label));
+    }
+  }
+  completions
+}
+
+/// Finds the function of the annotation that the tokens end in, if they end
+/// in an option position, like `{$x :number ` or `{$x :number a=1 `.
+fn annotation_function(before: &[Token]) -> Option<ast::Identifier<'static>> {
+  let sigil = before.iter().rposition(|token| {
+    matches!(
+      token.kind,
+      TokenKind::FunctionSigil
+        | TokenKind::OpenBrace
+        | TokenKind::CloseBrace
+        | TokenKind::OpenQuotedPattern
+        | TokenKind::CloseQuotedPattern
+        | TokenKind::At
+    )
+  })?;
+  if before[sigil].kind != TokenKind::FunctionSigil {
+    return None;
+  }
+
+  // The previous option must be complete, so the tokens cannot end in
+  // `a=` or `a =`.
+  let last = before[..before.len() - 1].iter().rev().find(|token| {
+    !matches!(token.kind, TokenKind::Whitespace | TokenKind::Bidi)
+  })?;
+  if last.kind == TokenKind::Equals {
+    return None;
+  }
+
+  match &before[sigil + 1..] {
+    [namespace, colon, name, ..]
+      if namespace.kind == TokenKind::Name
+        && colon.kind == TokenKind::Colon
+        && name.kind == TokenKind::Name =>
+    {
+      Some(ast::Identifier::new(Some(namespace.text), name.text).into_owned())
+    }
+    [name, ..] if name.kind == TokenKind::Name => {
+      Some(ast::Identifier::new(None, name.text).into_owned())
+    }
+    _ => None,
+  }
+}
+
+fn option_completions(
+  before: &[Token],
+  function_id: &ast::Identifier,
+  functions: &FunctionRegistry,
+) -> Vec<Completion> {
+  let Some(function) = functions.get(function_id) else {
+    return vec![];
+  };
+  let Some(option_names) = function.option_names() else {
+    return vec![];
+  };
+
+  // The options that are already set in the annotation.
+  let sigil = before
+    .iter()
+    .rposition(|token| token.kind == TokenKind::FunctionSigil)
+    .unwrap_or(0);
+  let used = before[sigil..]
+    .windows(2)
+    .filter(|pair| {
+      pair[0].kind == TokenKind::Name && pair[1].kind == TokenKind::Equals
+    })
+    .map(|pair| strip_bidi(pair[0].text))
+    .collect::<Vec<_>>();
+
+  option_names
+    .iter()
+    .filter(|name| !used.contains(name))
+    .map(|name| Completion {
+      documentation: function.option_description(name).map(str::to_string),
+      ..Completion::new(CompletionKind::FunctionOption, *name)
+    })
+    .collect()
+}
+
+/// Whether the tokens end at the start of a new variant line of a matcher,
+/// which follows the selectors or the previous variant.
+fn is_variant_start(before: &[Token]) -> bool {
+  let Some((whitespace, rest)) = before.split_last() else {
+    return false;
+  };
+  if !whitespace.text.contains('\n') || depth(rest) != 0 {
+    return false;
+  }
+  let has_match = rest
+    .iter()
+    .any(|token| token.kind == TokenKind::Keyword && token.text == ".match");
+  let previous = rest
+    .iter()
+    .rev()
+    .find(|token| token.kind != TokenKind::Bidi)
+    .map(|token| token.kind);
+  has_match
+    && matches!(
+      previous,
+      Some(
+        TokenKind::Variable
+          | TokenKind::CloseBrace
+          | TokenKind::CloseQuotedPattern
+      )
+    )
+}
+
+fn key_completions() -> Vec<Completion> {
+  let mut completions = PLURAL_CATEGORIES
+    .iter()
+    .map(|category| Completion {
+      detail: Some("Plural category".to_string()),
+      ..Completion::new(CompletionKind::Key, *category)
+    })
+    .collect::<Vec<_>>();
+  completions.push(Completion {
+    detail: Some("Catch-all key".to_string()),
+    ..Completion::new(CompletionKind::Key, "*")
+  });
+  completions
+}
+
+#[cfg(test)]
+mod tests {
+  use mf2_parser::functions::FunctionRegistry;
+  use mf2_parser::parse;
+  use mf2_parser::tokenize;
+
+  use super::completions;
+  use super::CompletionKind;
+
+  /// Completes at the end of the message, and returns the replaced word and
+  /// the labels of the completions.
+  fn complete(message: &str) -> Option<(String, Vec<String>)> {
+    let lines = message.split('\n').collect::<Vec<_>>();
+    let line = lines.len() as u32 - 1;
+    let col = lines.last().unwrap().encode_utf16().count() as u32;
+    complete_at(message, line, col)
+  }
+
+  fn complete_at(
+    message: &str,
+    line: u32,
+    col: u32,
+  ) -> Option<(String, Vec<String>)> {
+    let (ast, _diagnostics, info) = parse(message);
+    let tokens = tokenize(message).collect::<Vec<_>>();
+    let location = info.location_from_utf16_line_col(line, col);
+    let functions = FunctionRegistry::with_default_functions();
+    let (word, completions) = completions(&ast, &tokens, location, &functions)?;
+    let labels = completions.into_iter().map(|c| c.label).collect();
+    Some((info.slice(word).to_string(), labels))
+  }
+
+  fn labels(labels: &[&str]) -> Vec<String> {
+    labels.iter().map(|label| label.to_string()).collect()
+  }
+
+  #[test]
+  fn completes_functions() {
+    let (word, completions) = complete("Hello {$x :").unwrap();
+    assert_eq!(word, "");
+    assert!(completions.contains(&"number".to_string()));
+    assert!(completions.contains(&"string".to_string()));
+    let (word, _) = complete("Hello {$x :num").unwrap();
+    assert_eq!(word, "num");
+  }
+
+  #[test]
+  fn completes_options() {
+    let (word, completions) =
+      complete("{$x :number minimumFractionDigits=2 ").unwrap();
+    assert_eq!(word, "");
+    assert!(completions.contains(&"maximumFractionDigits".to_string()));
+    assert!(!completions.contains(&"minimumFractionDigits".to_string()));
+    let (word, _) = complete("{$x :number max").unwrap();
+    assert_eq!(word, "max");
+    assert_eq!(complete("{$x :string ").unwrap().1, labels(&[]));
+    assert!(complete("{$x :number a=").is_none());
+    assert!(complete("{$x :number} ").is_none());
+  }
+
+  #[test]
+  fn completes_variables() {
+    let message = ".input {$count :number}\n.local $label = {$count}\n{{{$";
+    let (word, completions) = complete(message).unwrap();
+    assert_eq!(word, "$");
+    assert_eq!(completions, labels(&["$count", "$label"]));
+    let (word, completions) = complete("{$").unwrap();
+    assert_eq!(word, "$");
+    assert_eq!(completions, labels(&[]));
+  }
+
+  #[test]
+  fn completes_only_variables_in_scope() {
+    let message = ".input {$x :number}\n.input {$\n.local $later = {1}\n{{}}";
+    let (word, completions) = complete_at(message, 1, 9).unwrap();
+    assert_eq!(word, "$");
+    assert_eq!(completions, labels(&["$x"]));
+    let message = ".local $a = {1} .local $b = {$";
+    assert_eq!(complete(message).unwrap().1, labels(&["$a"]));
+    let (word, completions) = complete(".local $a = {1} .local $").unwrap();
+    assert_eq!(word, "$");
+    assert_eq!(completions, labels(&[]));
+  }
+
+  #[test]
+  fn completes_keywords() {
+    let (word, completions) = complete(".").unwrap();
+    assert_eq!(word, ".");
+    assert_eq!(completions, labels(&[".input", ".local", ".match"]));
+    let (word, completions) = complete(".input {$x}\n.lo").unwrap();
+    assert_eq!(word, ".lo");
+    assert_eq!(completions.len(), 3);
+    assert_eq!(complete(".match $x\n* {{}}\n.").unwrap().1, labels(&[]));
+  }
+
+  #[test]
+  fn completes_markup() {
+    let (word, completions) = complete("{#b}bold{#i}{/").unwrap();
+    assert_eq!(word, "");
+    assert_eq!(completions, labels(&["b", "i"]));
+    let (word, completions) = complete("{#b}bold{/b").unwrap();
+    assert_eq!(word, "b");
+    assert_eq!(completions, labels(&["b"]));
+  }
+
+  #[test]
+  fn completes_keys() {
+    let keys = labels(&["zero", "one", "two", "few", "many", "other", "*"]);
+    assert_eq!(complete(".match $x\n").unwrap().1, keys);
+    assert_eq!(complete(".match $x\none {{}}\n").unwrap().1, keys);
+    let (word, completions) = complete(".match $x\none {{}}\no").unwrap();
+    assert_eq!(word, "o");
+    assert_eq!(completions, keys);
+    assert!(complete(".match ").is_none());
+    assert!(complete("Hello\n").is_none());
+  }
+
+  #[test]
+  fn describes_completions() {
+    let (ast, _diagnostics, info) = parse("{1 :number ");
+    let tokens = tokenize("{1 :number ").collect::<Vec<_>>();
+    let location = info.location_from_utf16_line_col(0, 11);
+    let functions = FunctionRegistry::with_default_functions();
+    let (_, completions) =
+      completions(&ast, &tokens, location, &functions).unwrap();
+    assert_eq!(completions[0].kind, CompletionKind::FunctionOption);
+    assert!(completions[0].documentation.is_some());
+  }
+}
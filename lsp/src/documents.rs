@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use lsp_types::TextDocumentContentChangeEvent;
+use lsp_types::Uri;
+use mf2_parser::SourceTextInfo;
+
+/// The text of an open document, as last synced by the client.
+pub struct Document {
+  pub text: String,
+  pub version: i32,
+}
+
+/// The documents that are open in the client, by their URI.
+#[derive(Default)]
+pub struct DocumentStore {
+  documents: HashMap<Uri, Document>,
+}
+
+impl DocumentStore {
+  pub fn open(&mut self, uri: Uri, text: String, version: i32) -> &Document {
+    self
+      .documents
+      .insert(uri.clone(), Document { text, version });
+    &self.documents[&uri]
+  }
+
+  /// Applies the changes to an open document in order. Changes without a
+  /// range replace the whole text. Changes with a range are ignored if the
+  /// text is too long for its positions to be converted. Returns `None` if
+  /// the document is not open.
+  pub fn change(
+    &mut self,
+    uri: &Uri,
+    version: i32,
+    changes: Vec<TextDocumentContentChangeEvent>,
+  ) -> Option<&Document> {
+    let document = self.documents.get_mut(uri)?;
+    for change in changes {
+      match change.range {
+        Some(range) => {
+          let Some(info) = SourceTextInfo::new(&document.text) else {
+            continue;
+          };
+          let start = info.byte_index_from_utf16_line_col(
+            range.start.line,
+            range.start.character,
+          );
+          let end = info
+            .byte_index_from_utf16_line_col(range.end.line, range.end.character)
+            .max(start);
+          document.text.replace_range(start..end, &change.text);
+        }
+        None => document.text = change.text,
+      }
+    }
+    document.version = version;
+    Some(document)
+  }
+
+  pub fn get(&self, uri: &Uri) -> Option<&Document> {
+    self.documents.get(uri)
+  }
+
+  pub fn close(&mut self, uri: &Uri) -> Option<Document> {
+    self.documents.remove(uri)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&Uri, &Document)> {
+    self.documents.iter()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use lsp_types::Position;
+  use lsp_types::Range;
+  use lsp_types::TextDocumentContentChangeEvent;
+  use lsp_types::Uri;
+
+  use super::DocumentStore;
+
+  fn edit(
+    (start_line, start_character): (u32, u32),
+    (end_line, end_character): (u32, u32),
+    text: &str,
+  ) -> TextDocumentContentChangeEvent {
+    TextDocumentContentChangeEvent {
+      range: Some(Range {
+        start: Position::new(start_line, start_character),
+        end: Position::new(end_line, end_character),
+      }),
+      range_length: None,
+      text: text.to_string(),
+    }
+  }
+
+  #[test]
+  fn converts_utf16_positions() {
+    let uri = "file:///a.mf2".parse::<Uri>().unwrap();
+    let mut store = DocumentStore::default();
+    store.open(uri.clone(), "a😀b\r\nc\n".to_string(), 1);
+    let document = store
+      .change(
+        &uri,
+        2,
+        vec![
+          edit((0, 3), (0, 10), "B"),
+          edit((1, 0), (1, 1), "C"),
+          edit((5, 0), (5, 0), "!"),
+        ],
+      )
+      .unwrap();
+    assert_eq!(document.text, "a😀B\r\nC\n!");
+  }
+
+  #[test]
+  fn applies_changes_in_order() {
+    let uri = "file:///a.mf2".parse::<Uri>().unwrap();
+    let mut store = DocumentStore::default();
+    store.open(uri.clone(), "Hello {$name}\n".to_string(), 1);
+    let document = store
+      .change(
+        &uri,
+        2,
+        vec![
+          edit((0, 0), (0, 5), "Hi"),
+          edit((0, 5), (0, 9), "user"),
+          edit((1, 0), (1, 0), "!"),
+        ],
+      )
+      .unwrap();
+    assert_eq!(document.text, "Hi {$user}\n!");
+    assert_eq!(document.version, 2);
+
+    let full = TextDocumentContentChangeEvent {
+      range: None,
+      range_length: None,
+      text: "Bye".to_string(),
+    };
+    assert_eq!(store.change(&uri, 3, vec![full]).unwrap().text, "Bye");
+    assert!(store.close(&uri).is_some());
+    assert!(store.change(&uri, 4, vec![]).is_none());
+  }
+}
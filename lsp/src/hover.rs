@@ -0,0 +1,339 @@
+//! The contents of hovers, in Markdown.
+
+use mf2_parser::ast;
+use mf2_parser::ast::strip_bidi;
+use mf2_parser::ast::Identifier;
+use mf2_parser::functions::FunctionRegistry;
+use mf2_parser::SourceTextInfo;
+use mf2_parser::Span;
+use mf2_parser::Spanned;
+
+use crate::lookup::Node;
+use crate::scope::Scope;
+
+/// Describes a node for a hover.
+pub fn hover_contents(
+  node: &Node,
+  message: &ast::Message,
+  info: &SourceTextInfo,
+  functions: &FunctionRegistry,
+) -> Option<String> {
+  match node {
+    Node::Function { id, .. } => Some(describe_function(id, functions)),
+    Node::FunctionOption { function, key } => {
+      Some(describe_option(function, key, functions))
+    }
+    Node::Variable(variable) => {
+      Some(describe_variable(variable, message, info))
+    }
+    Node::Number(number) => {
+      // Numbers with huge exponents are shown as they are written.
+      let value = number
+        .normalized()
+        .unwrap_or_else(|| number.raw.to_string());
+      Some(format!("Number literal with the value `{}`", value))
+    }
+  }
+}
+
+fn function_name(id: &Identifier) -> String {
+  match &id.namespace {
+    Some(namespace) => {
+      format!(":{}:{}", strip_bidi(namespace), strip_bidi(&id.name))
+    }
+    None => format!(":{}", strip_bidi(&id.name)),
+  }
+}
+
+fn describe_function(id: &Identifier, functions: &FunctionRegistry) -> String {
+  let name = function_name(id);
+  let Some(function) = functions.get(id) else {
+    return format!("`{}` is not a registered function.", name);
+  };
+
+  let mut signature = name;
+  if let Some(option_names) = function.option_names() {
+    for option_name in option_names {
+      signature.push_str(&format!(" {}=…", option_name));
+    }
+  } else {
+    signature.push_str(" …");
+  }
+
+  let mut contents = format!("```mf2\n{}\n```", signature);
+  if let Some(description) = function.description() {
+    contents.push_str("\n\n");
+    contents.push_str(description);
+  }
+  if let Some(option_names) = function.option_names() {
+    if !option_names.is_empty() {
+      contents.push_str("\n\nOptions:\n");
+    }
+    for option_name in option_names {
+      contents.push_str(&format!("\n- `{}`", option_name));
+      if let Some(description) = function.option_description(option_name) {
+        contents.push_str(": ");
+        contents.push_str(description);
+      }
+    }
+  }
+  contents
+}
+
+fn describe_option(
+  function_id: &Identifier,
+  key: &Identifier,
+  functions: &FunctionRegistry,
+) -> String {
+  let function_name = function_name(function_id);
+  let name = match &key.namespace {
+    Some(namespace) => {
+      format!("{}:{}", strip_bidi(namespace), strip_bidi(&key.name))
+    }
+    None => strip_bidi(&key.name).to_string(),
+  };
+  let Some(function) = functions.get(function_id) else {
+    return format!("Option `{}` of `{}`", name, function_name);
+  };
+
+  let is_known = key.namespace.is_none()
+    && function
+      .option_names()
+      .map_or(true, |option_names| option_names.contains(&name.as_str()));
+  if !is_known {
+    return format!("`{}` is not an option of `{}`.", name, function_name);
+  }
+
+  let mut contents = format!("Option `{}` of `{}`", name, function_name);
+  if let Some(description) = function.option_description(&name) {
+    contents.push_str("\n\n");
+    contents.push_str(description);
+  }
+  contents
+}
+
+fn describe_variable(
+  variable: &ast::Variable,
+  message: &ast::Message,
+  info: &SourceTextInfo,
+) -> String {
+  let name = strip_bidi(&variable.name);
+  let declarations = match message {
+    ast::Message::Complex(message) => message.declarations.as_slice(),
+    ast::Message::Simple(_) => &[],
+  };
+  let scope = Scope::resolve(message);
+  let Some(index) = declaration_index(declarations, &scope, variable.span)
+  else {
+    return format!(
+      "Variable `${}`, an external variable that is passed in when the \
+       message is formatted.",
+      name
+    );
+  };
+
+  let declaration = &declarations[index];
+  let (keyword, inherited_from) = match declaration {
+    ast::Declaration::LocalDeclaration(local) => {
+      let inherited_from = match &local.expression {
+        ast::Expression::VariableExpression(expression)
+          if expression.annotation.is_none() =>
+        {
+          Some(strip_bidi(&expression.variable.name))
+        }
+        _ => None,
+      };
+      (".local", inherited_from)
+    }
+    _ => (".input", None),
+  };
+  let source = info.slice(declaration.span());
+  let mut contents = format!(
+    "```mf2\n{}\n```\n\nVariable `${}`, declared by `{}`",
+    source, name, keyword
+  );
+  match resolved_annotation(declarations, &scope, index) {
+    Some(ast::Annotation::Function(function)) => {
+      contents.push_str(&format!(
+        " and annotated with `{}`",
+        function_name(&function.id)
+      ));
+      match inherited_from {
+        Some(variable) => {
+          contents.push_str(&format!(", inherited from `${}`.", variable))
+        }
+        None => contents.push('.'),
+      }
+    }
+    Some(_) => contents.push_str(" with a reserved annotation."),
+    None => contents.push_str(" without an annotation."),
+  }
+  contents
+}
+
+/// Finds the index of the declaration that an occurrence of a variable refers
+/// to, or `None` for external variables.
+fn declaration_index(
+  declarations: &[ast::Declaration],
+  scope: &Scope,
+  occurrence: Span,
+) -> Option<usize> {
+  let symbol = scope
+    .symbols
+    .iter()
+    .find(|symbol| symbol.occurrences.contains(&occurrence))?;
+  let declaration = symbol.declaration?;
+  declarations.iter().position(|candidate| {
+    declared_variable(candidate).map(|variable| variable.span)
+      == Some(declaration)
+  })
+}
+
+fn declared_variable<'d, 'a>(
+  declaration: &'d ast::Declaration<'a>,
+) -> Option<&'d ast::Variable<'a>> {
+  match declaration {
+    ast::Declaration::InputDeclaration(input) => {
+      Some(&input.expression.variable)
+    }
+    ast::Declaration::LocalDeclaration(local) => Some(&local.variable),
+    ast::Declaration::ReservedStatement(_) => None,
+  }
+}
+
+/// The annotation of the value of a declaration. A `.local` whose expression
+/// is a variable without an annotation gets the annotation of the declaration
+/// of that variable.
+fn resolved_annotation<'d, 'a>(
+  declarations: &'d [ast::Declaration<'a>],
+  scope: &Scope,
+  index: usize,
+) -> Option<&'d ast::Annotation<'a>> {
+  match &declarations[index] {
+    ast::Declaration::InputDeclaration(input) => {
+      input.expression.annotation.as_ref()
+    }
+    ast::Declaration::LocalDeclaration(local) => match &local.expression {
+      ast::Expression::VariableExpression(expression)
+        if expression.annotation.is_none() =>
+      {
+        let previous =
+          declaration_index(declarations, scope, expression.variable.span)?;
+        // A variable can only refer to an earlier declaration, but this is
+        // checked to never recurse endlessly.
+        if previous >= index {
+          return None;
+        }
+        resolved_annotation(declarations, scope, previous)
+      }
+      expression => expression_annotation(expression),
+    },
+    ast::Declaration::ReservedStatement(_) => None,
+  }
+}
+
+fn expression_annotation<'e, 'a>(
+  expression: &'e ast::Expression<'a>,
+) -> Option<&'e ast::Annotation<'a>> {
+  match expression {
+    ast::Expression::LiteralExpression(expression) => {
+      expression.annotation.as_ref()
+    }
+    ast::Expression::VariableExpression(expression) => {
+      expression.annotation.as_ref()
+    }
+    ast::Expression::AnnotationExpression(expression) => {
+      Some(&expression.annotation)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use mf2_parser::functions::FunctionRegistry;
+  use mf2_parser::parse;
+
+  use super::hover_contents;
+  use crate::lookup::node_at;
+
+  fn hover_at(message: &str, offset: u32) -> Option<String> {
+    let (ast, _diagnostics, info) = parse(message);
+    let node = node_at(&ast, info.location_from_utf16_line_col(0, offset))?;
+    let functions = FunctionRegistry::with_default_functions();
+    hover_contents(&node, &ast, &info, &functions)
+  }
+
+  #[test]
+  fn describes_functions() {
+    let contents = hover_at("{1 :number}", 5).unwrap();
+    assert!(contents.starts_with("```mf2\n:number select=… "));
+    assert!(contents.contains("- `maximumFractionDigits`: The maximum"));
+    assert_eq!(
+      hover_at("{1 :foo}", 5).unwrap(),
+      "`:foo` is not a registered function."
+    );
+  }
+
+  #[test]
+  fn describes_options() {
+    let contents = hover_at("{1 :number useGrouping=never}", 12).unwrap();
+    assert!(contents.starts_with("Option `useGrouping` of `:number`\n\n"));
+    assert_eq!(
+      hover_at("{1 :string foo=bar}", 12).unwrap(),
+      "`foo` is not an option of `:string`."
+    );
+  }
+
+  #[test]
+  fn describes_variables() {
+    let message = ".input {$n :number}\n.local $m = {$n}\n{{{$m} {$x}}}";
+    assert_eq!(
+      hover_at(message, 9).unwrap(),
+      "```mf2\n.input {$n :number}\n```\n\nVariable `$n`, declared by \
+       `.input` and annotated with `:number`."
+    );
+    let (ast, _diagnostics, info) = parse(message);
+    let node = node_at(&ast, info.location_from_utf16_line_col(2, 4)).unwrap();
+    let functions = FunctionRegistry::with_default_functions();
+    assert_eq!(
+      hover_contents(&node, &ast, &info, &functions).unwrap(),
+      "```mf2\n.local $m = {$n}\n```\n\nVariable `$m`, declared by `.local` \
+       and annotated with `:number`, inherited from `$n`."
+    );
+    let message = ".local $a = {$x} .local $b = {$a} {{{$b}}}";
+    assert!(hover_at(message, 24)
+      .unwrap()
+      .ends_with("declared by `.local` without an annotation."));
+    assert!(hover_at("{$x}", 2).unwrap().contains("external variable"));
+  }
+
+  #[test]
+  fn describes_the_declaration_before_a_variable() {
+    let message = ".input {$x :number} .local $x = {1 :string} {{{$x}}}";
+    assert!(hover_at(message, 48).unwrap().starts_with(
+      "```mf2\n.local $x = {1 :string}\n```\n\nVariable `$x`, declared by \
+       `.local` and annotated with `:string`."
+    ));
+    let message = ".local $a = {$b} .local $b = {1 :number} {{{$b}}}";
+    assert!(hover_at(message, 14).unwrap().contains("external variable"));
+    assert!(hover_at(message, 45)
+      .unwrap()
+      .contains("declared by `.local`"));
+  }
+
+  #[test]
+  fn describes_numbers() {
+    assert_eq!(
+      hover_at("{1.5e3}", 2).unwrap(),
+      "Number literal with the value `1500`"
+    );
+    assert_eq!(
+      hover_at("{12345678901234567890.1}", 2).unwrap(),
+      "Number literal with the value `12345678901234567890.1`"
+    );
+    assert_eq!(
+      hover_at("{-1e1000}", 2).unwrap(),
+      "Number literal with the value `-1e1000`"
+    );
+  }
+}
@@ -0,0 +1,154 @@
+//! Finds the AST node at a location in a message, for requests like hover
+//! that are about the node under the cursor.
+
+use mf2_parser::ast;
+use mf2_parser::ast::Identifier;
+use mf2_parser::ast::Variable;
+use mf2_parser::Location;
+use mf2_parser::Span;
+use mf2_parser::Spanned;
+use mf2_parser::Visit;
+use mf2_parser::Visitable;
+
+/// A node that a request can be about.
+#[derive(Debug)]
+pub enum Node {
+  /// The identifier of a function annotation, with its span from the `:`.
+  Function {
+    id: Identifier<'static>,
+    span: Span,
+  },
+  /// The key of an option of a function annotation.
+  FunctionOption {
+    function: Identifier<'static>,
+    key: Identifier<'static>,
+  },
+  Variable(Variable<'static>),
+  Number(ast::Number<'static>),
+}
+
+impl Spanned for Node {
+  fn span(&self) -> Span {
+    match self {
+      Node::Function { span, .. } => *span,
+      Node::FunctionOption { key, .. } => key.span(),
+      Node::Variable(variable) => variable.span(),
+      Node::Number(number) => number.span(),
+    }
+  }
+}
+
+/// Finds the node at a location. A location at the end of a node, like the
+/// cursor right after `$name`, is also considered to be in the node.
+pub fn node_at(message: &ast::Message, location: Location) -> Option<Node> {
+  let mut finder = NodeFinder {
+    location,
+    function: None,
+    node: None,
+  };
+  message.apply_visitor(&mut finder);
+  finder.node
+}
+
+pub fn contains(span: Span, location: Location) -> bool {
+  span.start <= location && location <= span.end
+}
+
+struct NodeFinder {
+  location: Location,
+  /// The function whose options are being visited.
+  function: Option<Identifier<'static>>,
+  node: Option<Node>,
+}
+
+impl NodeFinder {
+  fn found(&mut self, span: Span, node: impl FnOnce() -> Node) {
+    if self.node.is_none() && contains(span, self.location) {
+      self.node = Some(node());
+    }
+  }
+}
+
+impl Visit for NodeFinder {
+  fn visit_function(&mut self, func: &ast::Function) {
+    let span = Span::new(func.start..func.id.span().end);
+    self.found(span, || Node::Function {
+      id: func.id.clone().into_owned(),
+      span,
+    });
+
+    let outer = self.function.replace(func.id.clone().into_owned());
+    func.apply_visitor_to_children(self);
+    self.function = outer;
+  }
+
+  fn visit_markup(&mut self, markup: &ast::Markup) {
+    let outer = self.function.take();
+    markup.apply_visitor_to_children(self);
+    self.function = outer;
+  }
+
+  fn visit_fn_or_markup_option(&mut self, opt: &ast::FnOrMarkupOption) {
+    if let Some(function) = &self.function {
+      let function = function.clone();
+      self.found(opt.key.span(), || Node::FunctionOption {
+        function,
+        key: opt.key.clone().into_owned(),
+      });
+    }
+    opt.apply_visitor_to_children(self);
+  }
+
+  fn visit_variable(&mut self, var: &Variable) {
+    self.found(var.span(), || Node::Variable(var.clone().into_owned()));
+  }
+
+  fn visit_number(&mut self, num: &ast::Number) {
+    self.found(num.span(), || Node::Number(num.clone().into_owned()));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use mf2_parser::parse;
+
+  use super::node_at;
+  use super::Node;
+
+  fn node_at_offset(message: &str, offset: usize) -> Option<Node> {
+    let (ast, _diagnostics, info) = parse(message);
+    let (line, col) = (0, message[..offset].encode_utf16().count() as u32);
+    node_at(&ast, info.location_from_utf16_line_col(line, col))
+  }
+
+  #[test]
+  fn finds_nodes() {
+    let message = "{$count :number minimumFractionDigits=2} {#b a=1.5}";
+    assert!(matches!(
+      node_at_offset(message, 1),
+      Some(Node::Variable(variable)) if variable.name == "count"
+    ));
+    assert!(matches!(
+      node_at_offset(message, 7),
+      Some(Node::Variable(_))
+    ));
+    assert!(matches!(
+      node_at_offset(message, 10),
+      Some(Node::Function { id, .. }) if id.name == "number"
+    ));
+    assert!(matches!(
+      node_at_offset(message, 20),
+      Some(Node::FunctionOption { function, key })
+        if function.name == "number" && key.name == "minimumFractionDigits"
+    ));
+    assert!(matches!(
+      node_at_offset(message, 38),
+      Some(Node::Number(number)) if number.raw == "2"
+    ));
+    assert!(node_at_offset(message, 44).is_none());
+    assert!(matches!(
+      node_at_offset(message, 48),
+      Some(Node::Number(number)) if number.raw == "1.5"
+    ));
+  }
+}
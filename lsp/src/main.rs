@@ -1,23 +1,70 @@
+mod completion;
+mod documents;
+mod hover;
+mod lookup;
+mod rename;
+mod scope;
+
+use std::collections::HashMap;
+
+use completion::CompletionKind;
+use documents::DocumentStore;
 use lsp_server::Connection;
+use lsp_server::ErrorCode;
 use lsp_server::Message;
 use lsp_server::Notification;
 use lsp_server::Response;
+use lsp_server::ResponseError;
+use lsp_types::notification::DidChangeConfiguration;
 use lsp_types::notification::DidChangeTextDocument;
 use lsp_types::notification::DidCloseTextDocument;
 use lsp_types::notification::DidOpenTextDocument;
+use lsp_types::request::Completion;
+use lsp_types::request::DocumentHighlightRequest;
+use lsp_types::request::GotoDefinition;
 use lsp_types::request::HoverRequest;
+use lsp_types::request::PrepareRenameRequest;
+use lsp_types::request::References;
+use lsp_types::request::Rename;
+use lsp_types::CompletionItem;
+use lsp_types::CompletionItemKind;
+use lsp_types::CompletionOptions;
+use lsp_types::CompletionResponse;
+use lsp_types::CompletionTextEdit;
 use lsp_types::Diagnostic;
+use lsp_types::DocumentHighlight;
+use lsp_types::DocumentHighlightKind;
+use lsp_types::GotoDefinitionResponse;
+use lsp_types::Hover;
+use lsp_types::HoverContents;
+use lsp_types::HoverProviderCapability;
 use lsp_types::InitializeParams;
+use lsp_types::MarkupContent;
+use lsp_types::MarkupKind;
+use lsp_types::OneOf;
 use lsp_types::Position;
+use lsp_types::PrepareRenameResponse;
 use lsp_types::PublishDiagnosticsParams;
 use lsp_types::Range;
+use lsp_types::RenameOptions;
 use lsp_types::ServerCapabilities;
 use lsp_types::TextDocumentSyncCapability;
 use lsp_types::TextDocumentSyncKind;
+use lsp_types::TextEdit;
 use lsp_types::Uri;
-use mf2_parser::parse;
+use lsp_types::WorkspaceEdit;
+use mf2_parser::functions::FunctionRegistry;
+use mf2_parser::parse_with_options;
+use mf2_parser::tokenize_with_options;
+use mf2_parser::validate_with_functions;
 use mf2_parser::Location;
+use mf2_parser::ParseOptions;
 use mf2_parser::SourceTextInfo;
+use mf2_parser::Span;
+use mf2_parser::Spanned;
+use mf2_parser::SpecVersion;
+use scope::Scope;
+use scope::Symbol;
 
 fn main() -> Result<(), anyhow::Error> {
   eprintln!(
@@ -34,8 +81,22 @@ fn main() -> Result<(), anyhow::Error> {
 
   let capabilities = ServerCapabilities {
     text_document_sync: Some(TextDocumentSyncCapability::Kind(
-      TextDocumentSyncKind::FULL,
+      TextDocumentSyncKind::INCREMENTAL,
     )),
+    hover_provider: Some(HoverProviderCapability::Simple(true)),
+    definition_provider: Some(OneOf::Left(true)),
+    references_provider: Some(OneOf::Left(true)),
+    document_highlight_provider: Some(OneOf::Left(true)),
+    completion_provider: Some(CompletionOptions {
+      trigger_characters: Some(
+        [":", "$", ".", "#", "/", " "].map(String::from).to_vec(),
+      ),
+      ..CompletionOptions::default()
+    }),
+    rename_provider: Some(OneOf::Right(RenameOptions {
+      prepare_provider: Some(true),
+      work_done_progress_options: Default::default(),
+    })),
     ..ServerCapabilities::default()
   };
 
@@ -63,6 +124,18 @@ fn main() -> Result<(), anyhow::Error> {
   }
   eprintln!();
 
+  let mut parse_options = ParseOptions::default();
+  if let Some(spec_version) = initialize_params
+    .initialization_options
+    .as_ref()
+    .and_then(spec_version_from_config)
+  {
+    parse_options.spec_version = spec_version;
+  }
+
+  let mut documents = DocumentStore::default();
+  let functions = FunctionRegistry::with_default_functions();
+
   loop {
     match connection.receiver.recv()? {
       Message::Request(req) => {
@@ -82,8 +155,15 @@ fn main() -> Result<(), anyhow::Error> {
                       <$name as lsp_types::request::Request>::Params,
                     >(req.params)?;
                   )?
-                  let result: <$name as lsp_types::request::Request>::Result = $body;
-                  connection.sender.send(Message::Response(Response::new_ok(req.id, result)))?;
+                  let result: Result<
+                    <$name as lsp_types::request::Request>::Result,
+                    ResponseError,
+                  > = $body;
+                  let response = match result {
+                    Ok(result) => Response::new_ok(req.id, result),
+                    Err(error) => Response::new_err(req.id, error.code, error.message),
+                  };
+                  connection.sender.send(Message::Response(response))?;
                 }
               )*
               _ => {
@@ -95,8 +175,116 @@ fn main() -> Result<(), anyhow::Error> {
 
         match_request! {
           HoverRequest(params) => {
-            eprintln!("Hover request: {:#?}", params);
-            None
+            let position = params.text_document_position_params;
+            Ok(documents.get(&position.text_document.uri).and_then(|document| {
+              hover(&document.text, position.position, &parse_options, &functions)
+            }))
+          }
+          GotoDefinition(params) => {
+            let position = params.text_document_position_params;
+            let uri = position.text_document.uri;
+            Ok(documents.get(&uri).and_then(|document| {
+              let (symbol, text_info) =
+                symbol_at(&document.text, position.position, &parse_options)?;
+              let range = span_to_range(&text_info, symbol.declaration?);
+              Some(GotoDefinitionResponse::Scalar(lsp_types::Location {
+                uri: uri.clone(),
+                range,
+              }))
+            }))
+          }
+          References(params) => {
+            let position = params.text_document_position;
+            let uri = position.text_document.uri;
+            Ok(documents.get(&uri).and_then(|document| {
+              let (symbol, text_info) =
+                symbol_at(&document.text, position.position, &parse_options)?;
+              let locations = symbol
+                .occurrences
+                .iter()
+                .filter(|span| {
+                  params.context.include_declaration
+                    || Some(**span) != symbol.declaration
+                })
+                .map(|span| lsp_types::Location {
+                  uri: uri.clone(),
+                  range: span_to_range(&text_info, *span),
+                })
+                .collect();
+              Some(locations)
+            }))
+          }
+          DocumentHighlightRequest(params) => {
+            let position = params.text_document_position_params;
+            Ok(documents.get(&position.text_document.uri).and_then(|document| {
+              let (symbol, text_info) =
+                symbol_at(&document.text, position.position, &parse_options)?;
+              let highlights = symbol
+                .occurrences
+                .iter()
+                .map(|span| DocumentHighlight {
+                  range: span_to_range(&text_info, *span),
+                  kind: Some(if Some(*span) == symbol.declaration {
+                    DocumentHighlightKind::WRITE
+                  } else {
+                    DocumentHighlightKind::READ
+                  }),
+                })
+                .collect();
+              Some(highlights)
+            }))
+          }
+          Completion(params) => {
+            let position = params.text_document_position;
+            Ok(documents.get(&position.text_document.uri).and_then(|document| {
+              complete(&document.text, position.position, &parse_options, &functions)
+            }))
+          }
+          PrepareRenameRequest(params) => {
+            Ok(documents.get(&params.text_document.uri).and_then(|document| {
+              let (ast, _diagnostics, text_info) =
+                parse_with_options(&document.text, parse_options.clone());
+              let location = text_info
+                .location_from_utf16_line_col(params.position.line, params.position.character);
+              let (span, name) = rename::prepare_rename(&ast, location)?;
+              Some(PrepareRenameResponse::RangeWithPlaceholder {
+                range: span_to_range(&text_info, span),
+                placeholder: name,
+              })
+            }))
+          }
+          Rename(params) => {
+            let position = params.text_document_position;
+            let uri = position.text_document.uri;
+            match documents.get(&uri) {
+              Some(document) => {
+                let (ast, _diagnostics, text_info) =
+                  parse_with_options(&document.text, parse_options.clone());
+                let location = text_info
+                  .location_from_utf16_line_col(position.position.line, position.position.character);
+                match rename::rename(&ast, location, &params.new_name) {
+                  Ok(renaming) => Ok(renaming.map(|(spans, new_name)| {
+                    let edits = spans
+                      .into_iter()
+                      .map(|span| TextEdit {
+                        range: span_to_range(&text_info, span),
+                        new_text: new_name.clone(),
+                      })
+                      .collect();
+                    WorkspaceEdit {
+                      changes: Some(HashMap::from([(uri.clone(), edits)])),
+                      ..WorkspaceEdit::default()
+                    }
+                  })),
+                  Err(error) => Err(ResponseError {
+                    code: ErrorCode::InvalidParams as i32,
+                    message: error.to_string(),
+                    data: None,
+                  }),
+                }
+              }
+              None => Ok(None),
+            }
           }
         }
       }
@@ -125,27 +313,72 @@ fn main() -> Result<(), anyhow::Error> {
 
         match_notification! {
           DidOpenTextDocument(params) => {
-            eprintln!("Opened document: {:#?}", params);
+            eprintln!("Opened document: {}", params.text_document.uri.as_str());
 
-            validate_message(
-              &params.text_document.text,
-              params.text_document.uri,
+            let uri = params.text_document.uri;
+            let document = documents.open(
+              uri.clone(),
+              params.text_document.text,
               params.text_document.version,
+            );
+            validate_message(
+              &document.text,
+              uri,
+              document.version,
+              &parse_options,
+              &functions,
               &connection
             )?;
           }
           DidChangeTextDocument(params) => {
-            eprintln!("Changed document: {:#?}", params);
+            eprintln!("Changed document: {}", params.text_document.uri.as_str());
 
-            validate_message(
-              &params.content_changes[0].text,
-              params.text_document.uri,
+            let uri = params.text_document.uri;
+            match documents.change(
+              &uri,
               params.text_document.version,
-              &connection
-            )?;
+              params.content_changes,
+            ) {
+              Some(document) => validate_message(
+                &document.text,
+                uri,
+                document.version,
+                &parse_options,
+                &functions,
+                &connection
+              )?,
+              None => eprintln!("Changed document is not open: {}", uri.as_str()),
+            }
           }
           DidCloseTextDocument(params) => {
-            eprintln!("Closed document: {:#?}", params);
+            eprintln!("Closed document: {}", params.text_document.uri.as_str());
+
+            documents.close(&params.text_document.uri);
+            publish_diagnostics(
+              params.text_document.uri,
+              None,
+              vec![],
+              &connection,
+            )?;
+          }
+          DidChangeConfiguration(params) => {
+            eprintln!("Changed configuration: {:#?}", params);
+
+            if let Some(spec_version) =
+              params.settings.get("mf2").and_then(spec_version_from_config)
+            {
+              parse_options.spec_version = spec_version;
+              for (uri, document) in documents.iter() {
+                validate_message(
+                  &document.text,
+                  uri.clone(),
+                  document.version,
+                  &parse_options,
+                  &functions,
+                  &connection
+                )?;
+              }
+            }
           }
         }
       }
@@ -156,40 +389,147 @@ fn main() -> Result<(), anyhow::Error> {
   Ok(())
 }
 
+/// Reads the `specVersion` setting from the client configuration. Valid values
+/// are `"draft"`, `"ldml46"`, and `"ldml47"`.
+fn spec_version_from_config(config: &serde_json::Value) -> Option<SpecVersion> {
+  match config.get("specVersion")?.as_str()? {
+    "draft" => Some(SpecVersion::Draft),
+    "ldml46" => Some(SpecVersion::Ldml46),
+    "ldml47" => Some(SpecVersion::Ldml47),
+    other => {
+      eprintln!("Unknown spec version: {}", other);
+      None
+    }
+  }
+}
+
 fn validate_message(
   text: &str,
   uri: Uri,
   version: i32,
+  options: &ParseOptions,
+  functions: &FunctionRegistry,
   connection: &Connection,
 ) -> Result<(), anyhow::Error> {
-  let (_ast, diagnostics, text_info) = parse(text);
+  let (ast, diagnostics, text_info) = parse_with_options(text, options.clone());
 
   let diagnostics = diagnostics
     .into_iter()
-    .map(|diag| {
-      let span = diag.span();
+    .chain(validate_with_functions(&ast, functions))
+    .map(|diag| Diagnostic {
+      range: span_to_range(&text_info, diag.span()),
+      severity: Some(lsp_types::DiagnosticSeverity::ERROR),
+      message: diag.to_string(),
+      source: Some("mf2".to_string()),
+      ..Diagnostic::default()
+    })
+    .collect();
 
-      fn loc_to_pos(info: &SourceTextInfo, loc: Location) -> Position {
-        let (line, character) = info.utf16_line_col(loc);
-        Position { line, character }
-      }
+  publish_diagnostics(uri, Some(version), diagnostics, connection)
+}
 
-      Diagnostic {
-        range: Range {
-          start: loc_to_pos(&text_info, span.start),
-          end: loc_to_pos(&text_info, span.end),
-        },
-        severity: Some(lsp_types::DiagnosticSeverity::ERROR),
-        message: diag.to_string(),
-        source: Some("mf2".to_string()),
-        ..Diagnostic::default()
-      }
+fn hover(
+  text: &str,
+  position: Position,
+  options: &ParseOptions,
+  functions: &FunctionRegistry,
+) -> Option<Hover> {
+  let (ast, _diagnostics, text_info) =
+    parse_with_options(text, options.clone());
+  let location =
+    text_info.location_from_utf16_line_col(position.line, position.character);
+  let node = lookup::node_at(&ast, location)?;
+  let contents = hover::hover_contents(&node, &ast, &text_info, functions)?;
+
+  Some(Hover {
+    contents: HoverContents::Markup(MarkupContent {
+      kind: MarkupKind::Markdown,
+      value: contents,
+    }),
+    range: Some(span_to_range(&text_info, node.span())),
+  })
+}
+
+fn complete(
+  text: &str,
+  position: Position,
+  options: &ParseOptions,
+  functions: &FunctionRegistry,
+) -> Option<CompletionResponse> {
+  let (ast, _diagnostics, text_info) =
+    parse_with_options(text, options.clone());
+  let tokens = tokenize_with_options(text, options.clone()).collect::<Vec<_>>();
+  let location =
+    text_info.location_from_utf16_line_col(position.line, position.character);
+  let (word, completions) =
+    completion::completions(&ast, &tokens, location, functions)?;
+  let range = span_to_range(&text_info, word);
+
+  let items = completions
+    .into_iter()
+    .map(|completion| CompletionItem {
+      kind: Some(match completion.kind {
+        CompletionKind::Function => CompletionItemKind::FUNCTION,
+        CompletionKind::FunctionOption => CompletionItemKind::PROPERTY,
+        CompletionKind::Variable => CompletionItemKind::VARIABLE,
+        CompletionKind::Keyword => CompletionItemKind::KEYWORD,
+        CompletionKind::Markup => CompletionItemKind::STRUCT,
+        CompletionKind::Key => CompletionItemKind::ENUM_MEMBER,
+      }),
+      detail: completion.detail,
+      documentation: completion.documentation.map(|documentation| {
+        lsp_types::Documentation::MarkupContent(MarkupContent {
+          kind: MarkupKind::Markdown,
+          value: documentation,
+        })
+      }),
+      text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+        range,
+        new_text: completion.label.clone(),
+      })),
+      label: completion.label,
+      ..CompletionItem::default()
     })
     .collect();
+  Some(CompletionResponse::Array(items))
+}
+
+/// Finds the variable at a position, resolved to its declaration and all of
+/// its other occurrences.
+fn symbol_at<'t>(
+  text: &'t str,
+  position: Position,
+  options: &ParseOptions,
+) -> Option<(Symbol, SourceTextInfo<'t>)> {
+  let (ast, _diagnostics, text_info) =
+    parse_with_options(text, options.clone());
+  let location =
+    text_info.location_from_utf16_line_col(position.line, position.character);
+  let symbol = Scope::resolve(&ast).symbol_at(location)?.clone();
+  Some((symbol, text_info))
+}
+
+fn loc_to_pos(info: &SourceTextInfo, loc: Location) -> Position {
+  let (line, character) = info.utf16_line_col(loc);
+  Position { line, character }
+}
+
+fn span_to_range(info: &SourceTextInfo, span: Span) -> Range {
+  Range {
+    start: loc_to_pos(info, span.start),
+    end: loc_to_pos(info, span.end),
+  }
+}
 
+fn publish_diagnostics(
+  uri: Uri,
+  version: Option<i32>,
+  diagnostics: Vec<Diagnostic>,
+  connection: &Connection,
+) -> Result<(), anyhow::Error> {
   let params = PublishDiagnosticsParams {
     uri,
-    version: Some(version),
+    version,
     diagnostics,
   };
 
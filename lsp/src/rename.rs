@@ -0,0 +1,166 @@
+//! Renames a variable everywhere it occurs in a message.
+
+use std::fmt;
+
+use mf2_parser::ast;
+use mf2_parser::ast::is_valid_name;
+use mf2_parser::Location;
+use mf2_parser::Span;
+
+use crate::lookup::contains;
+use crate::scope::Scope;
+
+#[derive(Debug, PartialEq)]
+pub enum RenameError {
+  InvalidName { name: String },
+  NameInUse { name: String },
+}
+
+impl fmt::Display for RenameError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RenameError::InvalidName { name } => {
+        write!(f, "`{}` is not a valid variable name", name)
+      }
+      RenameError::NameInUse { name } => {
+        write!(f, "The variable `${}` is already used in the message", name)
+      }
+    }
+  }
+}
+
+/// Finds the variable at a location, and returns the span of its name
+/// (without the `$`) and the name itself.
+pub fn prepare_rename(
+  message: &ast::Message,
+  location: Location,
+) -> Option<(Span, String)> {
+  let scope = Scope::resolve(message);
+  let symbol = scope.symbol_at(location)?;
+  let span = symbol
+    .occurrences
+    .iter()
+    .find(|span| contains(**span, location))?;
+  Some((name_span(*span), symbol.name.clone()))
+}
+
+/// Finds the variable at a location, and returns the spans of the names of
+/// all its occurrences, and the name to replace them with, which is
+/// `new_name` without a leading `$`.
+pub fn rename(
+  message: &ast::Message,
+  location: Location,
+  new_name: &str,
+) -> Result<Option<(Vec<Span>, String)>, RenameError> {
+  let new_name = new_name.strip_prefix('$').unwrap_or(new_name);
+  if !is_valid_name(new_name) {
+    return Err(RenameError::InvalidName {
+      name: new_name.to_string(),
+    });
+  }
+
+  let scope = Scope::resolve(message);
+  let Some(symbol) = scope.symbol_at(location) else {
+    return Ok(None);
+  };
+  let is_in_use = scope
+    .symbols
+    .iter()
+    .any(|other| other.name == new_name && other.name != symbol.name);
+  if is_in_use {
+    return Err(RenameError::NameInUse {
+      name: new_name.to_string(),
+    });
+  }
+
+  Ok(Some((
+    symbol.occurrences.iter().copied().map(name_span).collect(),
+    new_name.to_string(),
+  )))
+}
+
+fn name_span(variable: Span) -> Span {
+  Span::new(variable.start + '$'..variable.end)
+}
+
+#[cfg(test)]
+mod tests {
+  use mf2_parser::parse;
+
+  use super::prepare_rename;
+  use super::rename;
+  use super::RenameError;
+
+  const MESSAGE: &str = ".input {$count :number}
+.local $label = {$count :integer u:x=$count}
+{{{$label} of {$count} {#b a=$count /} {$total}}}";
+
+  fn rename_at(
+    line: u32,
+    col: u32,
+    new_name: &str,
+  ) -> Result<String, RenameError> {
+    let (ast, _diagnostics, info) = parse(MESSAGE);
+    let location = info.location_from_utf16_line_col(line, col);
+    let (mut spans, new_name) = rename(&ast, location, new_name)?.unwrap();
+    spans.reverse();
+    let mut text = MESSAGE.to_string();
+    for span in spans {
+      let start = span.start.inner_byte_index_for_test() as usize;
+      let end = span.end.inner_byte_index_for_test() as usize;
+      text.replace_range(start..end, &new_name);
+    }
+    Ok(text)
+  }
+
+  #[test]
+  fn renames_all_occurrences() {
+    assert_eq!(
+      rename_at(2, 18, "itemCount").unwrap(),
+      ".input {$itemCount :number}
+.local $label = {$itemCount :integer u:x=$itemCount}
+{{{$label} of {$itemCount} {#b a=$itemCount /} {$total}}}"
+    );
+    assert_eq!(
+      rename_at(1, 8, "$name").unwrap(),
+      ".input {$count :number}
+.local $name = {$count :integer u:x=$count}
+{{{$name} of {$count} {#b a=$count /} {$total}}}"
+    );
+  }
+
+  #[test]
+  fn refuses_invalid_names() {
+    assert_eq!(
+      rename_at(2, 18, "1st"),
+      Err(RenameError::InvalidName {
+        name: "1st".to_string()
+      })
+    );
+    assert!(rename_at(2, 18, "item count").is_err());
+    assert!(rename_at(2, 18, "").is_err());
+    assert_eq!(
+      rename_at(2, 18, "$$count"),
+      Err(RenameError::InvalidName {
+        name: "$count".to_string()
+      })
+    );
+    assert_eq!(
+      rename_at(2, 18, "total"),
+      Err(RenameError::NameInUse {
+        name: "total".to_string()
+      })
+    );
+  }
+
+  #[test]
+  fn prepares_renames() {
+    let (ast, _diagnostics, info) = parse(MESSAGE);
+    let location = info.location_from_utf16_line_col(2, 4);
+    let (span, name) = prepare_rename(&ast, location).unwrap();
+    assert_eq!(info.slice(span), "label");
+    assert_eq!(name, "label");
+    let location = info.location_from_utf16_line_col(2, 12);
+    assert!(prepare_rename(&ast, location).is_none());
+  }
+}
@@ -0,0 +1,164 @@
+//! Resolves the variables of a message to their declarations.
+//!
+//! A variable refers to the latest `.input` or `.local` declaration of its
+//! name before it. Variables without such a declaration are external
+//! variables, which are passed in when the message is formatted.
+
+use std::collections::HashMap;
+
+use mf2_parser::ast;
+use mf2_parser::ast::strip_bidi;
+use mf2_parser::ast::Variable;
+use mf2_parser::Location;
+use mf2_parser::Span;
+use mf2_parser::Visit;
+use mf2_parser::Visitable;
+
+use crate::lookup::contains;
+
+/// A declared or external variable, and the places it is used.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+  /// The name of the variable, without its `$`.
+  pub name: String,
+  /// The variable of the declaration, or `None` for an external variable.
+  pub declaration: Option<Span>,
+  /// The spans of all occurrences of the variable, including the declaration,
+  /// in source order.
+  pub occurrences: Vec<Span>,
+}
+
+/// The variables of a message, after resolving them to their declarations.
+#[derive(Debug, Default)]
+pub struct Scope {
+  pub symbols: Vec<Symbol>,
+}
+
+impl Scope {
+  pub fn resolve(message: &ast::Message) -> Scope {
+    let mut resolver = Resolver::default();
+    message.apply_visitor(&mut resolver);
+    resolver.scope
+  }
+
+  /// Finds the symbol with an occurrence at a location.
+  pub fn symbol_at(&self, location: Location) -> Option<&Symbol> {
+    self.symbols.iter().find(|symbol| {
+      symbol
+        .occurrences
+        .iter()
+        .any(|span| contains(*span, location))
+    })
+  }
+}
+
+#[derive(Default)]
+struct Resolver {
+  scope: Scope,
+  /// The symbol each name refers to at the current point of the message.
+  bindings: HashMap<String, usize>,
+}
+
+impl Resolver {
+  fn declare(&mut self, variable: &Variable) {
+    let name = strip_bidi(&variable.name).to_string();
+    self.bindings.insert(name.clone(), self.scope.symbols.len());
+    self.scope.symbols.push(Symbol {
+      name,
+      declaration: Some(variable.span),
+      occurrences: vec![variable.span],
+    });
+  }
+}
+
+impl Visit for Resolver {
+  fn visit_input_declaration(&mut self, decl: &ast::InputDeclaration) {
+    let expression = &decl.expression;
+    self.declare(&expression.variable);
+    if let Some(annotation) = &expression.annotation {
+      annotation.apply_visitor(self);
+    }
+    for attribute in &expression.attributes {
+      attribute.apply_visitor(self);
+    }
+  }
+
+  fn visit_local_declaration(&mut self, decl: &ast::LocalDeclaration) {
+    decl.expression.apply_visitor(self);
+    self.declare(&decl.variable);
+  }
+
+  fn visit_variable(&mut self, var: &Variable) {
+    let name = strip_bidi(&var.name);
+    let index = match self.bindings.get(name) {
+      Some(index) => *index,
+      None => {
+        let index = self.scope.symbols.len();
+        self.bindings.insert(name.to_string(), index);
+        self.scope.symbols.push(Symbol {
+          name: name.to_string(),
+          declaration: None,
+          occurrences: vec![],
+        });
+        index
+      }
+    };
+    self.scope.symbols[index].occurrences.push(var.span);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use mf2_parser::parse;
+  use mf2_parser::Span;
+
+  use super::Scope;
+
+  fn offsets(span: Span) -> (u32, u32) {
+    (
+      span.start.inner_byte_index_for_test(),
+      span.end.inner_byte_index_for_test(),
+    )
+  }
+
+  #[test]
+  fn resolves_declarations() {
+    let message = ".input {$n :number minimumFractionDigits=$d}
+.local $m = {$n}
+.local $n2 = {$m :string u:id=$n}
+.match $m
+* {{{$n} {#b a=$n2 /} {$x} {$m}}}";
+    let (ast, _diagnostics, _info) = parse(message);
+    let scope = Scope::resolve(&ast);
+    let symbols = scope
+      .symbols
+      .iter()
+      .map(|symbol| {
+        let occurrences = symbol
+          .occurrences
+          .iter()
+          .map(|span| offsets(*span))
+          .collect::<Vec<_>>();
+        (symbol.declaration.map(offsets), occurrences)
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      symbols,
+      vec![
+        (Some((8, 10)), vec![(8, 10), (58, 60), (92, 94), (111, 113)]),
+        (None, vec![(41, 43)]),
+        (
+          Some((52, 54)),
+          vec![(52, 54), (76, 78), (103, 105), (134, 136)]
+        ),
+        (Some((69, 72)), vec![(69, 72), (121, 124)]),
+        (None, vec![(129, 131)]),
+      ]
+    );
+
+    let location = scope.symbols[0].occurrences[2].end;
+    let symbol = scope.symbol_at(location).unwrap();
+    assert_eq!(symbol.name, "n");
+    assert_eq!(symbol.declaration.map(offsets), Some((8, 10)));
+  }
+}
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mf2_parser::parse_cst;
+use mf2_parser::parse_with_options;
+use mf2_parser::validate;
+use mf2_parser::ParseOptions;
+use mf2_parser::SpecVersion;
+
+fuzz_target!(|message: &str| {
+  for spec_version in
+    [SpecVersion::Draft, SpecVersion::Ldml46, SpecVersion::Ldml47]
+  {
+    let options = ParseOptions::default().with_spec_version(spec_version);
+    let (ast, _, _) = parse_with_options(message, options.clone());
+    let _ = validate(&ast);
+    let (cst, _, _) = parse_cst(message, options);
+    assert_eq!(cst.to_string(), message);
+  }
+});
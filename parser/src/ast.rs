@@ -1,5 +1,11 @@
+use std::borrow::Cow;
 use std::fmt::Debug;
 
+use unicode_normalization::is_nfc_quick;
+use unicode_normalization::IsNormalized;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::chars;
 use crate::util::LengthShort;
 use crate::util::Location;
 use crate::util::Span;
@@ -15,6 +21,7 @@ macro_rules! ast_enum {
     }
   } => {
     #[derive(Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub enum $name<$lifetime> {
       $( $item ( $item$(<$item_lifetime>)? ), )*
     }
@@ -35,6 +42,14 @@ macro_rules! ast_enum {
       }
     }
 
+    impl $name<'_> {
+      pub fn into_owned(self) -> $name<'static> {
+        match self {
+          $( $name::$item(item) => $name::$item(item.into_owned()), )*
+        }
+      }
+    }
+
     impl crate::visitor::Visitable for $name<'_> {
       fn apply_visitor<V: crate::visitor::Visit + ?Sized>(&self, visitor: &mut V) {
         visitor.$visit_method(self);
@@ -49,7 +64,23 @@ macro_rules! ast_enum {
   };
 }
 
+/// Names in the AST (of variables, identifiers, and unquoted literals) keep the
+/// bidi marks and isolates that may surround them in the source text. This
+/// returns the name without them, which is the value the name represents.
+pub fn strip_bidi(name: &str) -> &str {
+  name.trim_matches(|c| matches!(c, chars::bidi!()))
+}
+
+/// Whether the text is a valid name, like the name of a variable without its
+/// `$`. Bidi marks and isolates are not part of a name.
+pub fn is_valid_name(name: &str) -> bool {
+  let mut name_chars = name.chars();
+  matches!(name_chars.next(), Some(chars::name_start!()))
+    && name_chars.all(|c| matches!(c, chars::name!()))
+}
+
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Message<'a> {
   Simple(Pattern<'a>),
   Complex(ComplexMessage<'a>),
@@ -73,6 +104,17 @@ impl Spanned for Message<'_> {
   }
 }
 
+impl Message<'_> {
+  /// Converts the message into one that owns all of its text, so that it no
+  /// longer borrows from the source text it was parsed from.
+  pub fn into_owned(self) -> Message<'static> {
+    match self {
+      Message::Simple(pattern) => Message::Simple(pattern.into_owned()),
+      Message::Complex(complex) => Message::Complex(complex.into_owned()),
+    }
+  }
+}
+
 impl Visitable for Message<'_> {
   fn apply_visitor<V: Visit + ?Sized>(&self, visitor: &mut V) {
     match self {
@@ -90,6 +132,7 @@ impl Visitable for Message<'_> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pattern<'a> {
   pub parts: Vec<PatternPart<'a>>,
 }
@@ -117,6 +160,18 @@ impl Visitable for Pattern<'_> {
   }
 }
 
+impl Pattern<'_> {
+  pub fn into_owned(self) -> Pattern<'static> {
+    Pattern {
+      parts: self
+        .parts
+        .into_iter()
+        .map(PatternPart::into_owned)
+        .collect(),
+    }
+  }
+}
+
 ast_enum! {
   #[visit(visit_pattern_part)]
   pub enum PatternPart<'a> {
@@ -128,14 +183,15 @@ ast_enum! {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text<'a> {
   pub start: Location,
-  pub content: &'a str,
+  pub content: Cow<'a, str>,
 }
 
 impl Spanned for Text<'_> {
   fn span(&self) -> Span {
-    Span::new(self.start..self.start + self.content)
+    Span::new(self.start..self.start + &*self.content)
   }
 }
 
@@ -147,7 +203,17 @@ impl Visitable for Text<'_> {
   fn apply_visitor_to_children<V: Visit + ?Sized>(&self, _visitor: &mut V) {}
 }
 
+impl Text<'_> {
+  pub fn into_owned(self) -> Text<'static> {
+    Text {
+      start: self.start,
+      content: Cow::Owned(self.content.into_owned()),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Escape {
   pub start: Location,
   pub escaped_char: char,
@@ -167,6 +233,14 @@ impl Visitable for Escape {
   fn apply_visitor_to_children<V: Visit + ?Sized>(&self, _visitor: &mut V) {}
 }
 
+impl Escape {
+  /// This node does not borrow any text, so this returns the node itself. It
+  /// exists so that all nodes can be converted in the same way.
+  pub fn into_owned(self) -> Escape {
+    self
+  }
+}
+
 ast_enum! {
   #[visit(visit_expression)]
   pub enum Expression<'a> {
@@ -177,6 +251,7 @@ ast_enum! {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LiteralExpression<'a> {
   pub span: Span,
   pub literal: Literal<'a>,
@@ -206,7 +281,23 @@ impl Visitable for LiteralExpression<'_> {
   }
 }
 
+impl LiteralExpression<'_> {
+  pub fn into_owned(self) -> LiteralExpression<'static> {
+    LiteralExpression {
+      span: self.span,
+      literal: self.literal.into_owned(),
+      annotation: self.annotation.map(Annotation::into_owned),
+      attributes: self
+        .attributes
+        .into_iter()
+        .map(Attribute::into_owned)
+        .collect(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VariableExpression<'a> {
   pub span: Span,
   pub variable: Variable<'a>,
@@ -236,10 +327,26 @@ impl Visitable for VariableExpression<'_> {
   }
 }
 
+impl VariableExpression<'_> {
+  pub fn into_owned(self) -> VariableExpression<'static> {
+    VariableExpression {
+      span: self.span,
+      variable: self.variable.into_owned(),
+      annotation: self.annotation.map(Annotation::into_owned),
+      attributes: self
+        .attributes
+        .into_iter()
+        .map(Attribute::into_owned)
+        .collect(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Variable<'a> {
   pub span: Span,
-  pub name: &'a str,
+  pub name: Cow<'a, str>,
 }
 
 impl Spanned for Variable<'_> {
@@ -256,7 +363,17 @@ impl Visitable for Variable<'_> {
   fn apply_visitor_to_children<V: Visit + ?Sized>(&self, _visitor: &mut V) {}
 }
 
+impl Variable<'_> {
+  pub fn into_owned(self) -> Variable<'static> {
+    Variable {
+      span: self.span,
+      name: Cow::Owned(self.name.into_owned()),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AnnotationExpression<'a> {
   pub span: Span,
   pub annotation: Annotation<'a>,
@@ -282,6 +399,20 @@ impl Visitable for AnnotationExpression<'_> {
   }
 }
 
+impl AnnotationExpression<'_> {
+  pub fn into_owned(self) -> AnnotationExpression<'static> {
+    AnnotationExpression {
+      span: self.span,
+      annotation: self.annotation.into_owned(),
+      attributes: self
+        .attributes
+        .into_iter()
+        .map(Attribute::into_owned)
+        .collect(),
+    }
+  }
+}
+
 ast_enum! {
   #[visit(visit_annotation)]
   pub enum Annotation<'a> {
@@ -292,19 +423,32 @@ ast_enum! {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier<'a> {
   pub start: Location,
-  pub namespace: Option<&'a str>,
-  pub name: &'a str,
+  pub namespace: Option<Cow<'a, str>>,
+  pub name: Cow<'a, str>,
+}
+
+impl<'a> Identifier<'a> {
+  /// Creates an identifier that is not part of a message, like the name of a
+  /// function in a [`FunctionRegistry`](crate::functions::FunctionRegistry).
+  pub fn new(namespace: Option<&'a str>, name: &'a str) -> Self {
+    Identifier {
+      start: Location::dummy(),
+      namespace: namespace.map(Cow::Borrowed),
+      name: Cow::Borrowed(name),
+    }
+  }
 }
 
 impl Spanned for Identifier<'_> {
   fn span(&self) -> Span {
     let mut end = self.start;
-    if let Some(namespace) = self.namespace {
-      end = end + namespace + ':';
+    if let Some(namespace) = &self.namespace {
+      end = end + &**namespace + ':';
     }
-    end = end + self.name;
+    end = end + &*self.name;
 
     Span::new(self.start..end)
   }
@@ -318,7 +462,20 @@ impl Visitable for Identifier<'_> {
   fn apply_visitor_to_children<V: Visit + ?Sized>(&self, _visitor: &mut V) {}
 }
 
+impl Identifier<'_> {
+  pub fn into_owned(self) -> Identifier<'static> {
+    Identifier {
+      start: self.start,
+      namespace: self
+        .namespace
+        .map(|namespace| Cow::Owned(namespace.into_owned())),
+      name: Cow::Owned(self.name.into_owned()),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Function<'a> {
   pub start: Location,
   pub id: Identifier<'a>,
@@ -349,7 +506,22 @@ impl Visitable for Function<'_> {
   }
 }
 
+impl Function<'_> {
+  pub fn into_owned(self) -> Function<'static> {
+    Function {
+      start: self.start,
+      id: self.id.into_owned(),
+      options: self
+        .options
+        .into_iter()
+        .map(FnOrMarkupOption::into_owned)
+        .collect(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FnOrMarkupOption<'a> {
   pub key: Identifier<'a>,
   pub value: LiteralOrVariable<'a>,
@@ -374,7 +546,17 @@ impl Visitable for FnOrMarkupOption<'_> {
   }
 }
 
+impl FnOrMarkupOption<'_> {
+  pub fn into_owned(self) -> FnOrMarkupOption<'static> {
+    FnOrMarkupOption {
+      key: self.key.into_owned(),
+      value: self.value.into_owned(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Attribute<'a> {
   pub span: Span,
   pub key: Identifier<'a>,
@@ -400,6 +582,16 @@ impl Visitable for Attribute<'_> {
   }
 }
 
+impl Attribute<'_> {
+  pub fn into_owned(self) -> Attribute<'static> {
+    Attribute {
+      span: self.span,
+      key: self.key.into_owned(),
+      value: self.value.map(LiteralOrVariable::into_owned),
+    }
+  }
+}
+
 ast_enum! {
   #[visit(visit_literal_or_variable)]
   pub enum LiteralOrVariable<'a> {
@@ -409,6 +601,7 @@ ast_enum! {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrivateUseAnnotation<'a> {
   pub start: Location,
   pub sigil: char,
@@ -438,7 +631,22 @@ impl Visitable for PrivateUseAnnotation<'_> {
   }
 }
 
+impl PrivateUseAnnotation<'_> {
+  pub fn into_owned(self) -> PrivateUseAnnotation<'static> {
+    PrivateUseAnnotation {
+      start: self.start,
+      sigil: self.sigil,
+      body: self
+        .body
+        .into_iter()
+        .map(ReservedBodyPart::into_owned)
+        .collect(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReservedAnnotation<'a> {
   pub start: Location,
   pub sigil: char,
@@ -468,6 +676,20 @@ impl Visitable for ReservedAnnotation<'_> {
   }
 }
 
+impl ReservedAnnotation<'_> {
+  pub fn into_owned(self) -> ReservedAnnotation<'static> {
+    ReservedAnnotation {
+      start: self.start,
+      sigil: self.sigil,
+      body: self
+        .body
+        .into_iter()
+        .map(ReservedBodyPart::into_owned)
+        .collect(),
+    }
+  }
+}
+
 ast_enum! {
   #[visit(visit_reserved_body_part)]
   pub enum ReservedBodyPart<'a> {
@@ -486,7 +708,46 @@ ast_enum! {
   }
 }
 
+impl Literal<'_> {
+  /// The value the literal represents: the contents of a quoted literal with
+  /// its escapes resolved, an unquoted literal without the bidi marks around
+  /// it, or the source text of a number.
+  pub fn value(&self) -> Cow<'_, str> {
+    match self {
+      Literal::Quoted(quoted) => match quoted.parts.as_slice() {
+        [] => Cow::Borrowed(""),
+        [QuotedPart::Text(text)] => Cow::Borrowed(&text.content),
+        parts => {
+          let mut value = String::new();
+          for part in parts {
+            match part {
+              QuotedPart::Text(text) => value.push_str(&text.content),
+              QuotedPart::Escape(escape) => value.push(escape.escaped_char),
+            }
+          }
+          Cow::Owned(value)
+        }
+      },
+      Literal::Text(text) => Cow::Borrowed(strip_bidi(&text.content)),
+      Literal::Number(number) => Cow::Borrowed(&number.raw),
+    }
+  }
+
+  /// The value of the literal in Unicode Normalization Form C. Variant keys
+  /// are compared by this value, so that keys with different encodings of
+  /// the same text (like `é` as one or two code points) are the same.
+  pub fn normalized_value(&self) -> Cow<'_, str> {
+    let value = self.value();
+    if is_nfc_quick(value.chars()) == IsNormalized::Yes {
+      value
+    } else {
+      Cow::Owned(value.nfc().collect())
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quoted<'a> {
   pub span: Span,
   pub parts: Vec<QuotedPart<'a>>,
@@ -510,6 +771,15 @@ impl Visitable for Quoted<'_> {
   }
 }
 
+impl Quoted<'_> {
+  pub fn into_owned(self) -> Quoted<'static> {
+    Quoted {
+      span: self.span,
+      parts: self.parts.into_iter().map(QuotedPart::into_owned).collect(),
+    }
+  }
+}
+
 ast_enum! {
   #[visit(visit_quoted_part)]
   pub enum QuotedPart<'a> {
@@ -519,6 +789,7 @@ ast_enum! {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExponentSign {
   Plus,
   Minus,
@@ -526,9 +797,10 @@ pub enum ExponentSign {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Number<'a> {
   pub start: Location,
-  pub raw: &'a str,
+  pub raw: Cow<'a, str>,
   pub is_negative: bool,
   pub integral_len: LengthShort,
   pub fractional_len: Option<LengthShort>,
@@ -537,7 +809,7 @@ pub struct Number<'a> {
 
 impl Spanned for Number<'_> {
   fn span(&self) -> Span {
-    Span::new(self.start..self.start + self.raw)
+    Span::new(self.start..self.start + &*self.raw)
   }
 }
 
@@ -549,8 +821,64 @@ impl Visitable for Number<'_> {
   fn apply_visitor_to_children<V: Visit + ?Sized>(&self, _visitor: &mut V) {}
 }
 
+impl Number<'_> {
+  pub fn into_owned(self) -> Number<'static> {
+    Number {
+      start: self.start,
+      raw: Cow::Owned(self.raw.into_owned()),
+      is_negative: self.is_negative,
+      integral_len: self.integral_len,
+      fractional_len: self.fractional_len,
+      exponent_len: self.exponent_len,
+    }
+  }
+}
+
 impl<'a> Number<'a> {
-  fn slice(&self, span: Span) -> &'a str {
+  /// Creates a number from its raw source text, which must consist of an
+  /// optional `-`, digits, an optional fractional part, and an optional
+  /// exponent. Any of the digit sequences may be empty.
+  pub(crate) fn new(start: Location, raw: &'a str) -> Number<'a> {
+    fn digits(s: &str) -> &str {
+      let len = s.bytes().take_while(u8::is_ascii_digit).count();
+      &s[..len]
+    }
+
+    let is_negative = raw.starts_with('-');
+    let mut rest = if is_negative { &raw[1..] } else { raw };
+
+    let integral_part = digits(rest);
+    rest = &rest[integral_part.len()..];
+
+    let fractional_part = rest.strip_prefix('.').map(|after_dot| {
+      let fractional_part = digits(after_dot);
+      rest = &after_dot[fractional_part.len()..];
+      fractional_part
+    });
+
+    let exponent_part = rest.strip_prefix(['e', 'E']).map(|after_e| {
+      let (sign, after_sign) = if let Some(s) = after_e.strip_prefix('-') {
+        (ExponentSign::Minus, s)
+      } else if let Some(s) = after_e.strip_prefix('+') {
+        (ExponentSign::Plus, s)
+      } else {
+        (ExponentSign::None, after_e)
+      };
+      (sign, digits(after_sign))
+    });
+
+    Number {
+      start,
+      raw: Cow::Borrowed(raw),
+      is_negative,
+      integral_len: LengthShort::new_from_str(integral_part),
+      fractional_len: fractional_part.map(LengthShort::new_from_str),
+      exponent_len: exponent_part
+        .map(|(sign, digits)| (sign, LengthShort::new_from_str(digits))),
+    }
+  }
+
+  fn slice(&self, span: Span) -> &str {
     &self.raw[span.start.inner() as usize..span.end.inner() as usize]
   }
 
@@ -570,7 +898,7 @@ impl<'a> Number<'a> {
     Span::new(self.integral_start()..self.integral_end())
   }
 
-  pub fn integral_part(&self) -> &'a str {
+  pub fn integral_part(&self) -> &str {
     self.slice(self.integral_span())
   }
 
@@ -582,7 +910,7 @@ impl<'a> Number<'a> {
     })
   }
 
-  pub fn fractional_part(&self) -> Option<&'a str> {
+  pub fn fractional_part(&self) -> Option<&str> {
     self.fractional_span().map(|span| self.slice(span))
   }
 
@@ -606,7 +934,14 @@ impl<'a> Number<'a> {
     })
   }
 
-  pub fn exponent_part(&self) -> Option<(ExponentSign, &'a str)> {
+  /// The value of the number as a decimal without an exponent, like `1500`
+  /// for `1.5e3`. Returns `None` if the number is not valid, or if its
+  /// exponent is too large for the value to be written out.
+  pub fn normalized(&self) -> Option<String> {
+    crate::functions::normalize_number(&self.raw)
+  }
+
+  pub fn exponent_part(&self) -> Option<(ExponentSign, &str)> {
     self
       .exponent_span()
       .map(|span| (self.exponent_len.as_ref().unwrap().0, self.slice(span)))
@@ -614,6 +949,7 @@ impl<'a> Number<'a> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Markup<'a> {
   pub span: Span,
   pub kind: MarkupKind,
@@ -622,7 +958,8 @@ pub struct Markup<'a> {
   pub attributes: Vec<Attribute<'a>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MarkupKind {
   Open,
   Standalone,
@@ -651,7 +988,28 @@ impl Visitable for Markup<'_> {
   }
 }
 
+impl Markup<'_> {
+  pub fn into_owned(self) -> Markup<'static> {
+    Markup {
+      span: self.span,
+      kind: self.kind,
+      id: self.id.into_owned(),
+      options: self
+        .options
+        .into_iter()
+        .map(FnOrMarkupOption::into_owned)
+        .collect(),
+      attributes: self
+        .attributes
+        .into_iter()
+        .map(Attribute::into_owned)
+        .collect(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComplexMessage<'a> {
   pub declarations: Vec<Declaration<'a>>,
   pub body: ComplexMessageBody<'a>,
@@ -686,6 +1044,19 @@ impl Visitable for ComplexMessage<'_> {
   }
 }
 
+impl ComplexMessage<'_> {
+  pub fn into_owned(self) -> ComplexMessage<'static> {
+    ComplexMessage {
+      declarations: self
+        .declarations
+        .into_iter()
+        .map(Declaration::into_owned)
+        .collect(),
+      body: self.body.into_owned(),
+    }
+  }
+}
+
 ast_enum! {
   #[visit(visit_declaration)]
   pub enum Declaration<'a> {
@@ -696,6 +1067,7 @@ ast_enum! {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InputDeclaration<'a> {
   pub start: Location,
   pub expression: VariableExpression<'a>,
@@ -719,7 +1091,17 @@ impl Visitable for InputDeclaration<'_> {
   }
 }
 
+impl InputDeclaration<'_> {
+  pub fn into_owned(self) -> InputDeclaration<'static> {
+    InputDeclaration {
+      start: self.start,
+      expression: self.expression.into_owned(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalDeclaration<'a> {
   pub start: Location,
   pub variable: Variable<'a>,
@@ -745,10 +1127,21 @@ impl Visitable for LocalDeclaration<'_> {
   }
 }
 
+impl LocalDeclaration<'_> {
+  pub fn into_owned(self) -> LocalDeclaration<'static> {
+    LocalDeclaration {
+      start: self.start,
+      variable: self.variable.into_owned(),
+      expression: self.expression.into_owned(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReservedStatement<'a> {
   pub start: Location,
-  pub name: &'a str,
+  pub name: Cow<'a, str>,
   pub body: Vec<ReservedBodyPart<'a>>,
   pub expressions: Vec<Expression<'a>>,
 }
@@ -765,7 +1158,7 @@ impl Spanned for ReservedStatement<'_> {
           .body
           .last()
           .map(|last| last.span().end)
-          .unwrap_or_else(|| start + '.' + self.name)
+          .unwrap_or_else(|| start + '.' + &*self.name)
       });
     Span::new(start..end)
   }
@@ -786,6 +1179,25 @@ impl Visitable for ReservedStatement<'_> {
   }
 }
 
+impl ReservedStatement<'_> {
+  pub fn into_owned(self) -> ReservedStatement<'static> {
+    ReservedStatement {
+      start: self.start,
+      name: Cow::Owned(self.name.into_owned()),
+      body: self
+        .body
+        .into_iter()
+        .map(ReservedBodyPart::into_owned)
+        .collect(),
+      expressions: self
+        .expressions
+        .into_iter()
+        .map(Expression::into_owned)
+        .collect(),
+    }
+  }
+}
+
 ast_enum! {
   #[visit(visit_complex_message_body)]
   pub enum ComplexMessageBody<'a> {
@@ -795,6 +1207,7 @@ ast_enum! {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QuotedPattern<'a> {
   pub span: Span,
   pub pattern: Pattern<'a>,
@@ -816,10 +1229,20 @@ impl Visitable for QuotedPattern<'_> {
   }
 }
 
+impl QuotedPattern<'_> {
+  pub fn into_owned(self) -> QuotedPattern<'static> {
+    QuotedPattern {
+      span: self.span,
+      pattern: self.pattern.into_owned(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matcher<'a> {
   pub start: Location,
-  pub selectors: Vec<Expression<'a>>,
+  pub selectors: Vec<Selector<'a>>,
   pub variants: Vec<Variant<'a>>,
 }
 
@@ -856,7 +1279,22 @@ impl Visitable for Matcher<'_> {
   }
 }
 
+impl Matcher<'_> {
+  pub fn into_owned(self) -> Matcher<'static> {
+    Matcher {
+      start: self.start,
+      selectors: self
+        .selectors
+        .into_iter()
+        .map(Selector::into_owned)
+        .collect(),
+      variants: self.variants.into_iter().map(Variant::into_owned).collect(),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Variant<'a> {
   pub keys: Vec<Key<'a>>,
   pub pattern: QuotedPattern<'a>,
@@ -887,6 +1325,23 @@ impl Visitable for Variant<'_> {
   }
 }
 
+impl Variant<'_> {
+  pub fn into_owned(self) -> Variant<'static> {
+    Variant {
+      keys: self.keys.into_iter().map(Key::into_owned).collect(),
+      pattern: self.pattern.into_owned(),
+    }
+  }
+}
+
+ast_enum! {
+  #[visit(visit_selector)]
+  pub enum Selector<'a> {
+    Variable<'a>,
+    Expression<'a>,
+  }
+}
+
 ast_enum! {
   #[visit(visit_key)]
   pub enum Key<'a> {
@@ -896,6 +1351,7 @@ ast_enum! {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Star {
   pub start: Location,
 }
@@ -913,3 +1369,11 @@ impl Visitable for Star {
 
   fn apply_visitor_to_children<V: Visit + ?Sized>(&self, _visitor: &mut V) {}
 }
+
+impl Star {
+  /// This node does not borrow any text, so this returns the node itself. It
+  /// exists so that all nodes can be converted in the same way.
+  pub fn into_owned(self) -> Star {
+    self
+  }
+}
@@ -0,0 +1,499 @@
+//! A lossless concrete syntax tree (CST) for Message Format 2 messages.
+//!
+//! Unlike the [`ast`](crate::ast), the CST keeps every character of the source
+//! text, including whitespace, bidi marks, and invalid contents that the parser
+//! recovered from. Concatenating the text of all tokens in the tree gives back
+//! the source text exactly.
+//!
+//! The tree is split into two layers, in the style of rowan and Roslyn. The
+//! green tree ([`GreenNode`] and [`GreenToken`]) is immutable, only stores the
+//! kind and the text of each element, and can be shared between trees. The red
+//! tree ([`SyntaxNode`] and [`SyntaxToken`]) is a cursor into the green tree,
+//! which is created on demand and knows the location and the parent of each
+//! element.
+
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::util::Location;
+use crate::util::Span;
+use crate::util::Spanned;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+  // Tokens
+  /// Spaces, tabs, and newlines.
+  Whitespace,
+  /// Bidi marks and isolates outside of names.
+  Bidi,
+  /// Text in a pattern, a quoted literal, or a reserved body.
+  Text,
+  /// A `\` followed by the escaped character.
+  Escape,
+  /// A name, including the bidi marks that surround it.
+  Name,
+  Number,
+  /// A `.` followed by a keyword, like `.input`.
+  Keyword,
+  OpenBrace,
+  CloseBrace,
+  DoubleOpenBrace,
+  DoubleCloseBrace,
+  Dollar,
+  Colon,
+  Equals,
+  At,
+  Hash,
+  Slash,
+  Pipe,
+  Star,
+  /// The sigil of a reserved or private-use annotation.
+  Sigil,
+  /// Invalid contents that the parser recovered from. This is used both for
+  /// tokens, and for nodes wrapping invalid contents that have structure (like
+  /// a quoted literal in an invalid position).
+  Error,
+
+  // Nodes
+  Root,
+  Pattern,
+  LiteralExpression,
+  VariableExpression,
+  AnnotationExpression,
+  Variable,
+  Function,
+  Identifier,
+  Option,
+  Attribute,
+  PrivateUseAnnotation,
+  ReservedAnnotation,
+  Quoted,
+  Markup,
+  ComplexMessage,
+  InputDeclaration,
+  LocalDeclaration,
+  ReservedStatement,
+  QuotedPattern,
+  Matcher,
+  Variant,
+}
+
+impl SyntaxKind {
+  /// Whether this is a token that only separates other tokens.
+  pub fn is_trivia(self) -> bool {
+    matches!(self, SyntaxKind::Whitespace | SyntaxKind::Bidi)
+  }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct GreenNode {
+  kind: SyntaxKind,
+  len: u32,
+  children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+  pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+    let len = children.iter().map(GreenElement::len).sum();
+    GreenNode {
+      kind,
+      len,
+      children,
+    }
+  }
+
+  pub fn kind(&self) -> SyntaxKind {
+    self.kind
+  }
+
+  /// The length of the text of the node in bytes.
+  pub fn len(&self) -> u32 {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn children(&self) -> &[GreenElement] {
+    &self.children
+  }
+}
+
+impl Debug for GreenNode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("GreenNode")
+      .field("kind", &self.kind)
+      .field("len", &self.len)
+      .finish()
+  }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct GreenToken {
+  kind: SyntaxKind,
+  text: Box<str>,
+}
+
+impl GreenToken {
+  pub fn new(kind: SyntaxKind, text: &str) -> Self {
+    GreenToken {
+      kind,
+      text: text.into(),
+    }
+  }
+
+  pub fn kind(&self) -> SyntaxKind {
+    self.kind
+  }
+
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+}
+
+impl Debug for GreenToken {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?} {:?}", self.kind, self.text)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+  Node(Arc<GreenNode>),
+  Token(Arc<GreenToken>),
+}
+
+impl GreenElement {
+  pub fn kind(&self) -> SyntaxKind {
+    match self {
+      GreenElement::Node(node) => node.kind,
+      GreenElement::Token(token) => token.kind,
+    }
+  }
+
+  pub fn len(&self) -> u32 {
+    match self {
+      GreenElement::Node(node) => node.len,
+      GreenElement::Token(token) => token.text.len() as u32,
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+struct NodeData {
+  green: Arc<GreenNode>,
+  parent: Option<SyntaxNode>,
+  offset: u32,
+}
+
+/// A node in the red tree.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+impl SyntaxNode {
+  pub fn new_root(green: Arc<GreenNode>) -> Self {
+    SyntaxNode(Rc::new(NodeData {
+      green,
+      parent: None,
+      offset: 0,
+    }))
+  }
+
+  pub fn kind(&self) -> SyntaxKind {
+    self.0.green.kind
+  }
+
+  pub fn green(&self) -> &Arc<GreenNode> {
+    &self.0.green
+  }
+
+  pub fn parent(&self) -> Option<&SyntaxNode> {
+    self.0.parent.as_ref()
+  }
+
+  /// The ancestors of this node, starting with the node itself.
+  pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+    std::iter::successors(Some(self.clone()), |node| node.parent().cloned())
+  }
+
+  /// The text of the node, which is the concatenated text of all tokens in
+  /// it.
+  pub fn text(&self) -> String {
+    let mut text = String::with_capacity(self.0.green.len as usize);
+    for token in self.tokens() {
+      text.push_str(token.text());
+    }
+    text
+  }
+
+  pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+    let node = self.clone();
+    let mut offset = self.0.offset;
+    self
+      .0
+      .green
+      .children
+      .iter()
+      .enumerate()
+      .map(move |(index, child)| {
+        let child_offset = offset;
+        offset += child.len();
+        match child {
+          GreenElement::Node(green) => {
+            SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+              green: green.clone(),
+              parent: Some(node.clone()),
+              offset: child_offset,
+            })))
+          }
+          GreenElement::Token(_) => SyntaxElement::Token(SyntaxToken {
+            parent: node.clone(),
+            index,
+            offset: child_offset,
+          }),
+        }
+      })
+      // Collect so that the iterator does not borrow `self`.
+      .collect::<Vec<_>>()
+      .into_iter()
+  }
+
+  pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+    self
+      .children_with_tokens()
+      .filter_map(|element| element.into_node())
+  }
+
+  /// All nodes and tokens in this node (including the node itself), in
+  /// preorder.
+  pub fn descendants_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+    let mut stack = vec![SyntaxElement::Node(self.clone())];
+    std::iter::from_fn(move || {
+      let element = stack.pop()?;
+      if let SyntaxElement::Node(node) = &element {
+        let mut children = node.children_with_tokens().collect::<Vec<_>>();
+        children.reverse();
+        stack.extend(children);
+      }
+      Some(element)
+    })
+  }
+
+  /// All nodes in this node (including the node itself), in preorder.
+  pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+    self
+      .descendants_with_tokens()
+      .filter_map(|element| element.into_node())
+  }
+
+  /// All tokens in this node, in source order.
+  pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+    self
+      .descendants_with_tokens()
+      .filter_map(|element| element.into_token())
+  }
+
+  /// The token that contains the given location. If the location is at the
+  /// boundary between two tokens, the token to the right is returned, unless
+  /// the location is at the end of the node.
+  pub fn token_at(&self, loc: Location) -> Option<SyntaxToken> {
+    let span = self.span();
+    if loc < span.start || loc > span.end {
+      return None;
+    }
+    let mut last = None;
+    for token in self.tokens() {
+      let token_span = token.span();
+      if token_span.start <= loc && loc < token_span.end {
+        return Some(token);
+      }
+      if token_span.start <= loc {
+        last = Some(token);
+      }
+    }
+    last
+  }
+}
+
+impl Spanned for SyntaxNode {
+  fn span(&self) -> Span {
+    let start = Location::new(self.0.offset);
+    Span::new(start..Location::new(self.0.offset + self.0.green.len))
+  }
+}
+
+impl PartialEq for SyntaxNode {
+  fn eq(&self, other: &Self) -> bool {
+    Arc::ptr_eq(&self.0.green, &other.0.green)
+      && self.0.offset == other.0.offset
+  }
+}
+
+impl Eq for SyntaxNode {}
+
+impl Display for SyntaxNode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    for token in self.tokens() {
+      f.write_str(token.text())?;
+    }
+    Ok(())
+  }
+}
+
+impl Debug for SyntaxNode {
+  /// Prints the tree, with one element per line, indented by depth.
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let root_depth = self.ancestors().count();
+    for element in self.descendants_with_tokens() {
+      let depth = match &element {
+        SyntaxElement::Node(node) => node.ancestors().count() - root_depth,
+        SyntaxElement::Token(token) => {
+          token.parent().ancestors().count() + 1 - root_depth
+        }
+      };
+      write!(f, "{:indent$}", "", indent = depth * 2)?;
+      match element {
+        SyntaxElement::Node(node) => {
+          writeln!(f, "{:?}{:?}", node.kind(), node.span())?
+        }
+        SyntaxElement::Token(token) => writeln!(
+          f,
+          "{:?}{:?} {:?}",
+          token.kind(),
+          token.span(),
+          token.text()
+        )?,
+      }
+    }
+    Ok(())
+  }
+}
+
+/// A token in the red tree.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+  parent: SyntaxNode,
+  index: usize,
+  offset: u32,
+}
+
+impl SyntaxToken {
+  fn green(&self) -> &GreenToken {
+    match &self.parent.0.green.children[self.index] {
+      GreenElement::Token(token) => token,
+      GreenElement::Node(_) => unreachable!(),
+    }
+  }
+
+  pub fn kind(&self) -> SyntaxKind {
+    self.green().kind
+  }
+
+  pub fn text(&self) -> &str {
+    &self.green().text
+  }
+
+  pub fn parent(&self) -> &SyntaxNode {
+    &self.parent
+  }
+}
+
+impl Spanned for SyntaxToken {
+  fn span(&self) -> Span {
+    let start = Location::new(self.offset);
+    let end = Location::new(self.offset + self.green().text.len() as u32);
+    Span::new(start..end)
+  }
+}
+
+impl Debug for SyntaxToken {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?}{:?} {:?}", self.kind(), self.span(), self.text())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+  Node(SyntaxNode),
+  Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+  pub fn kind(&self) -> SyntaxKind {
+    match self {
+      SyntaxElement::Node(node) => node.kind(),
+      SyntaxElement::Token(token) => token.kind(),
+    }
+  }
+
+  pub fn into_node(self) -> Option<SyntaxNode> {
+    match self {
+      SyntaxElement::Node(node) => Some(node),
+      SyntaxElement::Token(_) => None,
+    }
+  }
+
+  pub fn into_token(self) -> Option<SyntaxToken> {
+    match self {
+      SyntaxElement::Node(_) => None,
+      SyntaxElement::Token(token) => Some(token),
+    }
+  }
+}
+
+impl Spanned for SyntaxElement {
+  fn span(&self) -> Span {
+    match self {
+      SyntaxElement::Node(node) => node.span(),
+      SyntaxElement::Token(token) => token.span(),
+    }
+  }
+}
+
+/// An event emitted by the parser, from which the green tree is built.
+#[derive(Debug)]
+pub(crate) enum Event {
+  StartNode { kind: SyntaxKind },
+  FinishNode,
+  Token { kind: SyntaxKind, len: u32 },
+}
+
+/// Builds a green tree from the events emitted by the parser. The events must
+/// contain exactly one root node, and the tokens must cover the source text.
+pub(crate) fn build_tree(source: &str, events: Vec<Event>) -> Arc<GreenNode> {
+  let mut stack: Vec<(SyntaxKind, Vec<GreenElement>)> = vec![];
+  let mut offset = 0;
+  let mut root = None;
+
+  for event in events {
+    match event {
+      Event::StartNode { kind } => stack.push((kind, vec![])),
+      Event::FinishNode => {
+        let (kind, children) = stack.pop().unwrap();
+        let node = Arc::new(GreenNode::new(kind, children));
+        match stack.last_mut() {
+          Some((_, siblings)) => siblings.push(GreenElement::Node(node)),
+          None => root = Some(node),
+        }
+      }
+      Event::Token { kind, len } => {
+        let end = offset + len as usize;
+        let token = Arc::new(GreenToken::new(kind, &source[offset..end]));
+        offset = end;
+        stack.last_mut().unwrap().1.push(GreenElement::Token(token));
+      }
+    }
+  }
+
+  debug_assert!(stack.is_empty());
+  debug_assert_eq!(offset, source.len());
+  root.unwrap()
+}
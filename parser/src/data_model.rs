@@ -0,0 +1,953 @@
+//! The data model of Message Format 2, as defined by the `message.json` schema
+//! of the specification.
+//!
+//! Unlike the AST, the data model does not describe the syntax of a message,
+//! only its meaning: escapes are resolved, names do not contain bidi marks,
+//! and options and attributes are maps. This is the format other Message
+//! Format 2 implementations use to exchange messages, and with the `serde`
+//! feature these types serialize to and from the JSON representation of the
+//! schema.
+//!
+//! The data model follows LDML 47. Syntax from earlier drafts of the
+//! specification that has no representation in it, like reserved annotations
+//! or expression selectors, can not be converted.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::ast;
+use crate::ast::is_valid_name;
+use crate::ast::strip_bidi;
+use crate::printer::is_number;
+use crate::util::Location;
+use crate::util::Span;
+use crate::util::Spanned;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum Message {
+  #[cfg_attr(feature = "serde", serde(rename = "message"))]
+  Pattern(PatternMessage),
+  #[cfg_attr(feature = "serde", serde(rename = "select"))]
+  Select(SelectMessage),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternMessage {
+  pub declarations: Vec<Declaration>,
+  pub pattern: Pattern,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectMessage {
+  pub declarations: Vec<Declaration>,
+  pub selectors: Vec<VariableRef>,
+  pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum Declaration {
+  #[cfg_attr(feature = "serde", serde(rename = "input"))]
+  Input(InputDeclaration),
+  #[cfg_attr(feature = "serde", serde(rename = "local"))]
+  Local(LocalDeclaration),
+}
+
+/// An `.input` declaration. The argument of `value` is the variable named
+/// `name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputDeclaration {
+  pub name: String,
+  pub value: Expression,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalDeclaration {
+  pub name: String,
+  pub value: Expression,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variant {
+  pub keys: Vec<Key>,
+  pub value: Pattern,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "KeyRepr", into = "KeyRepr"))]
+pub enum Key {
+  Literal(Literal),
+  CatchAll,
+}
+
+pub type Pattern = Vec<PatternPart>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged, from = "PatternPartRepr"))]
+pub enum PatternPart {
+  Text(String),
+  Markup(Markup),
+  Expression(Expression),
+}
+
+/// An expression. At least one of `arg` and `function` is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename = "expression"))]
+pub struct Expression {
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub arg: Option<Value>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub function: Option<FunctionRef>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      default,
+      skip_serializing_if = "BTreeMap::is_empty",
+      with = "attributes_serde"
+    )
+  )]
+  pub attributes: Attributes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged, from = "ValueRepr"))]
+pub enum Value {
+  Literal(Literal),
+  Variable(VariableRef),
+}
+
+/// A literal, with its escapes resolved. Number literals are represented by
+/// their source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename = "literal"))]
+pub struct Literal {
+  pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename = "variable"))]
+pub struct VariableRef {
+  pub name: String,
+}
+
+/// A function annotation. Namespaced names are written as `namespace:name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename = "function"))]
+pub struct FunctionRef {
+  pub name: String,
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "BTreeMap::is_empty")
+  )]
+  pub options: Options,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename = "markup"))]
+pub struct Markup {
+  pub kind: MarkupKind,
+  pub name: String,
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "BTreeMap::is_empty")
+  )]
+  pub options: Options,
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      default,
+      skip_serializing_if = "BTreeMap::is_empty",
+      with = "attributes_serde"
+    )
+  )]
+  pub attributes: Attributes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MarkupKind {
+  Open,
+  Standalone,
+  Close,
+}
+
+pub type Options = BTreeMap<String, Value>;
+
+/// Attributes by name. Attributes without a value are `None`, and are
+/// serialized as `true`.
+pub type Attributes = BTreeMap<String, Option<Literal>>;
+
+/// The `type` tags of keys are checked when deserializing them, which is not
+/// the case for the tags of structs.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+enum KeyRepr {
+  #[serde(rename = "literal")]
+  Literal { value: String },
+  #[serde(rename = "*")]
+  CatchAll,
+}
+
+#[cfg(feature = "serde")]
+impl From<KeyRepr> for Key {
+  fn from(key: KeyRepr) -> Self {
+    match key {
+      KeyRepr::Literal { value } => Key::Literal(Literal { value }),
+      KeyRepr::CatchAll => Key::CatchAll,
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+impl From<Key> for KeyRepr {
+  fn from(key: Key) -> Self {
+    match key {
+      Key::Literal(Literal { value }) => KeyRepr::Literal { value },
+      Key::CatchAll => KeyRepr::CatchAll,
+    }
+  }
+}
+
+/// Pattern parts are deserialized by their `type` tag, so that an object is
+/// not taken for an expression just because all fields of expressions are
+/// optional.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum PatternPartRepr {
+  Text(String),
+  Placeholder(PlaceholderRepr),
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(tag = "type")]
+enum PlaceholderRepr {
+  #[serde(rename = "expression")]
+  Expression(Expression),
+  #[serde(rename = "markup")]
+  Markup(Markup),
+}
+
+#[cfg(feature = "serde")]
+impl From<PatternPartRepr> for PatternPart {
+  fn from(part: PatternPartRepr) -> Self {
+    match part {
+      PatternPartRepr::Text(text) => PatternPart::Text(text),
+      PatternPartRepr::Placeholder(PlaceholderRepr::Expression(expression)) => {
+        PatternPart::Expression(expression)
+      }
+      PatternPartRepr::Placeholder(PlaceholderRepr::Markup(markup)) => {
+        PatternPart::Markup(markup)
+      }
+    }
+  }
+}
+
+/// Values are deserialized by their `type` tag, like pattern parts.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(tag = "type")]
+enum ValueRepr {
+  #[serde(rename = "literal")]
+  Literal(Literal),
+  #[serde(rename = "variable")]
+  Variable(VariableRef),
+}
+
+#[cfg(feature = "serde")]
+impl From<ValueRepr> for Value {
+  fn from(value: ValueRepr) -> Self {
+    match value {
+      ValueRepr::Literal(literal) => Value::Literal(literal),
+      ValueRepr::Variable(variable) => Value::Variable(variable),
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+mod attributes_serde {
+  use std::borrow::Cow;
+  use std::collections::BTreeMap;
+
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+
+  use super::Attributes;
+  use super::Literal;
+
+  #[derive(Serialize, Deserialize)]
+  #[serde(untagged)]
+  enum AttributeValue<'a> {
+    Literal(Cow<'a, Literal>),
+    True(bool),
+  }
+
+  pub fn serialize<S: Serializer>(
+    attributes: &Attributes,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(attributes.iter().map(|(name, value)| {
+      let value = match value {
+        Some(literal) => AttributeValue::Literal(Cow::Borrowed(literal)),
+        None => AttributeValue::True(true),
+      };
+      (name, value)
+    }))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Attributes, D::Error> {
+    let attributes =
+      BTreeMap::<String, AttributeValue>::deserialize(deserializer)?;
+    Ok(
+      attributes
+        .into_iter()
+        .map(|(name, value)| match value {
+          AttributeValue::Literal(literal) => {
+            (name, Some(literal.into_owned()))
+          }
+          AttributeValue::True(_) => (name, None),
+        })
+        .collect(),
+    )
+  }
+}
+
+/// The reason an AST can not be converted to the data model.
+#[derive(Debug, Clone)]
+pub enum ConversionError {
+  /// Reserved and private-use annotations, reserved statements, and variables
+  /// as attribute values are not part of the data model.
+  UnsupportedSyntax { span: Span },
+  /// Only variables can be selectors in the data model.
+  SelectorNotVariable { span: Span },
+  /// Options are a map in the data model, so their names must be unique.
+  DuplicateOptionName { name: String, span: Span },
+  /// Attributes are a map in the data model, so their names must be unique.
+  DuplicateAttribute { name: String, span: Span },
+}
+
+impl ConversionError {
+  pub fn span(&self) -> Span {
+    match self {
+      ConversionError::UnsupportedSyntax { span }
+      | ConversionError::SelectorNotVariable { span }
+      | ConversionError::DuplicateOptionName { span, .. }
+      | ConversionError::DuplicateAttribute { span, .. } => *span,
+    }
+  }
+}
+
+impl fmt::Display for ConversionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ConversionError::UnsupportedSyntax { .. } => {
+        write!(f, "This syntax is not supported by the data model.")
+      }
+      ConversionError::SelectorNotVariable { .. } => {
+        write!(f, "Selectors must be variables in the data model.")
+      }
+      ConversionError::DuplicateOptionName { name, .. } => {
+        write!(f, "Option '{name}' is specified more than once.")
+      }
+      ConversionError::DuplicateAttribute { name, .. } => {
+        write!(f, "Attribute '{name}' is specified more than once.")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// The reason a message of the data model can not be converted to an AST or
+/// to source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerationError {
+  /// A variable, function, markup, option, or attribute name that is not a
+  /// valid name (or `namespace:name` identifier) in the syntax.
+  InvalidName { name: String },
+  /// An expression with neither an argument nor a function.
+  EmptyExpression,
+}
+
+impl fmt::Display for GenerationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GenerationError::InvalidName { name } => {
+        write!(f, "'{name}' is not a valid name.")
+      }
+      GenerationError::EmptyExpression => {
+        write!(f, "An expression must have an argument or a function.")
+      }
+    }
+  }
+}
+
+impl std::error::Error for GenerationError {}
+
+impl Message {
+  /// Converts the AST of a message to the data model.
+  ///
+  /// Messages with diagnostics should not be converted, because the AST of a
+  /// message with syntax errors may not contain all of the source text.
+  pub fn from_ast(message: &ast::Message) -> Result<Message, ConversionError> {
+    match message {
+      ast::Message::Simple(pattern) => Ok(Message::Pattern(PatternMessage {
+        declarations: vec![],
+        pattern: from_pattern(pattern)?,
+      })),
+      ast::Message::Complex(complex) => {
+        let declarations = complex
+          .declarations
+          .iter()
+          .map(from_declaration)
+          .collect::<Result<_, _>>()?;
+        match &complex.body {
+          ast::ComplexMessageBody::QuotedPattern(quoted) => {
+            Ok(Message::Pattern(PatternMessage {
+              declarations,
+              pattern: from_pattern(&quoted.pattern)?,
+            }))
+          }
+          ast::ComplexMessageBody::Matcher(matcher) => {
+            let selectors = matcher
+              .selectors
+              .iter()
+              .map(|selector| match selector {
+                ast::Selector::Variable(variable) => {
+                  Ok(from_variable(variable))
+                }
+                ast::Selector::Expression(expression) => {
+                  Err(ConversionError::SelectorNotVariable {
+                    span: expression.span(),
+                  })
+                }
+              })
+              .collect::<Result<_, _>>()?;
+            let variants = matcher
+              .variants
+              .iter()
+              .map(|variant| {
+                Ok(Variant {
+                  keys: variant.keys.iter().map(from_key).collect(),
+                  value: from_pattern(&variant.pattern.pattern)?,
+                })
+              })
+              .collect::<Result<_, _>>()?;
+            Ok(Message::Select(SelectMessage {
+              declarations,
+              selectors,
+              variants,
+            }))
+          }
+        }
+      }
+    }
+  }
+
+  /// Converts the message to an AST. The nodes of the AST have dummy
+  /// locations, because there is no source text they could point into.
+  ///
+  /// Names that can not be written in the syntax, and expressions with
+  /// neither an argument nor a function, are an error.
+  pub fn to_ast(&self) -> Result<ast::Message<'static>, GenerationError> {
+    let (declarations, body) = match self {
+      Message::Pattern(message) => {
+        let pattern = to_pattern(&message.pattern)?;
+        if message.declarations.is_empty() {
+          return Ok(ast::Message::Simple(pattern));
+        }
+        (
+          &message.declarations,
+          ast::ComplexMessageBody::QuotedPattern(to_quoted_pattern(pattern)),
+        )
+      }
+      Message::Select(message) => (
+        &message.declarations,
+        ast::ComplexMessageBody::Matcher(ast::Matcher {
+          start: Location::dummy(),
+          selectors: message
+            .selectors
+            .iter()
+            .map(|selector| {
+              Ok(ast::Selector::Variable(to_variable(&selector.name)?))
+            })
+            .collect::<Result<_, _>>()?,
+          variants: message
+            .variants
+            .iter()
+            .map(|variant| {
+              Ok(ast::Variant {
+                keys: variant.keys.iter().map(to_key).collect(),
+                pattern: to_quoted_pattern(to_pattern(&variant.value)?),
+              })
+            })
+            .collect::<Result<_, _>>()?,
+        }),
+      ),
+    };
+    Ok(ast::Message::Complex(ast::ComplexMessage {
+      declarations: declarations
+        .iter()
+        .map(to_declaration)
+        .collect::<Result<_, _>>()?,
+      body,
+    }))
+  }
+
+  /// Generates Message Format 2 source text for the message, using
+  /// [`print`](crate::print).
+  pub fn to_source(&self) -> Result<String, GenerationError> {
+    Ok(crate::print(&self.to_ast()?))
+  }
+}
+
+fn from_pattern(pattern: &ast::Pattern) -> Result<Pattern, ConversionError> {
+  let mut parts = vec![];
+  let mut text = String::new();
+  for part in &pattern.parts {
+    match part {
+      ast::PatternPart::Text(part) => text.push_str(&part.content),
+      ast::PatternPart::Escape(escape) => text.push(escape.escaped_char),
+      ast::PatternPart::Expression(expression) => {
+        if !text.is_empty() {
+          parts.push(PatternPart::Text(std::mem::take(&mut text)));
+        }
+        parts.push(PatternPart::Expression(from_expression(expression)?));
+      }
+      ast::PatternPart::Markup(markup) => {
+        if !text.is_empty() {
+          parts.push(PatternPart::Text(std::mem::take(&mut text)));
+        }
+        parts.push(PatternPart::Markup(from_markup(markup)?));
+      }
+    }
+  }
+  if !text.is_empty() {
+    parts.push(PatternPart::Text(text));
+  }
+  Ok(parts)
+}
+
+fn from_declaration(
+  declaration: &ast::Declaration,
+) -> Result<Declaration, ConversionError> {
+  match declaration {
+    ast::Declaration::InputDeclaration(input) => {
+      Ok(Declaration::Input(InputDeclaration {
+        name: strip_bidi(&input.expression.variable.name).to_string(),
+        value: from_expression_parts(
+          Some(Value::Variable(from_variable(&input.expression.variable))),
+          input.expression.annotation.as_ref(),
+          &input.expression.attributes,
+        )?,
+      }))
+    }
+    ast::Declaration::LocalDeclaration(local) => {
+      Ok(Declaration::Local(LocalDeclaration {
+        name: strip_bidi(&local.variable.name).to_string(),
+        value: from_expression(&local.expression)?,
+      }))
+    }
+    ast::Declaration::ReservedStatement(statement) => {
+      Err(ConversionError::UnsupportedSyntax {
+        span: statement.span(),
+      })
+    }
+  }
+}
+
+fn from_expression(
+  expression: &ast::Expression,
+) -> Result<Expression, ConversionError> {
+  match expression {
+    ast::Expression::LiteralExpression(expression) => from_expression_parts(
+      Some(Value::Literal(from_literal(&expression.literal))),
+      expression.annotation.as_ref(),
+      &expression.attributes,
+    ),
+    ast::Expression::VariableExpression(expression) => from_expression_parts(
+      Some(Value::Variable(from_variable(&expression.variable))),
+      expression.annotation.as_ref(),
+      &expression.attributes,
+    ),
+    ast::Expression::AnnotationExpression(expression) => from_expression_parts(
+      None,
+      Some(&expression.annotation),
+      &expression.attributes,
+    ),
+  }
+}
+
+fn from_expression_parts(
+  arg: Option<Value>,
+  annotation: Option<&ast::Annotation>,
+  attributes: &[ast::Attribute],
+) -> Result<Expression, ConversionError> {
+  let function = match annotation {
+    Some(ast::Annotation::Function(function)) => Some(FunctionRef {
+      name: from_identifier(&function.id),
+      options: from_options(&function.options)?,
+    }),
+    Some(annotation) => {
+      return Err(ConversionError::UnsupportedSyntax {
+        span: annotation.span(),
+      })
+    }
+    None => None,
+  };
+  Ok(Expression {
+    arg,
+    function,
+    attributes: from_attributes(attributes)?,
+  })
+}
+
+fn from_markup(markup: &ast::Markup) -> Result<Markup, ConversionError> {
+  Ok(Markup {
+    kind: match markup.kind {
+      ast::MarkupKind::Open => MarkupKind::Open,
+      ast::MarkupKind::Standalone => MarkupKind::Standalone,
+      ast::MarkupKind::Close => MarkupKind::Close,
+    },
+    name: from_identifier(&markup.id),
+    options: from_options(&markup.options)?,
+    attributes: from_attributes(&markup.attributes)?,
+  })
+}
+
+fn from_options(
+  options: &[ast::FnOrMarkupOption],
+) -> Result<Options, ConversionError> {
+  let mut map = Options::new();
+  for option in options {
+    let name = from_identifier(&option.key);
+    let value = match &option.value {
+      ast::LiteralOrVariable::Literal(literal) => {
+        Value::Literal(from_literal(literal))
+      }
+      ast::LiteralOrVariable::Variable(variable) => {
+        Value::Variable(from_variable(variable))
+      }
+    };
+    if map.contains_key(&name) {
+      return Err(ConversionError::DuplicateOptionName {
+        name,
+        span: option.key.span(),
+      });
+    }
+    map.insert(name, value);
+  }
+  Ok(map)
+}
+
+fn from_attributes(
+  attributes: &[ast::Attribute],
+) -> Result<Attributes, ConversionError> {
+  let mut map = Attributes::new();
+  for attribute in attributes {
+    let name = from_identifier(&attribute.key);
+    let value = match &attribute.value {
+      Some(ast::LiteralOrVariable::Literal(literal)) => {
+        Some(from_literal(literal))
+      }
+      Some(ast::LiteralOrVariable::Variable(variable)) => {
+        return Err(ConversionError::UnsupportedSyntax {
+          span: variable.span(),
+        })
+      }
+      None => None,
+    };
+    if map.contains_key(&name) {
+      return Err(ConversionError::DuplicateAttribute {
+        name,
+        span: attribute.key.span(),
+      });
+    }
+    map.insert(name, value);
+  }
+  Ok(map)
+}
+
+fn from_key(key: &ast::Key) -> Key {
+  match key {
+    ast::Key::Literal(literal) => Key::Literal(from_literal(literal)),
+    ast::Key::Star(_) => Key::CatchAll,
+  }
+}
+
+fn from_literal(literal: &ast::Literal) -> Literal {
+  Literal {
+    value: literal.value().into_owned(),
+  }
+}
+
+fn from_variable(variable: &ast::Variable) -> VariableRef {
+  VariableRef {
+    name: strip_bidi(&variable.name).to_string(),
+  }
+}
+
+fn from_identifier(identifier: &ast::Identifier) -> String {
+  match &identifier.namespace {
+    Some(namespace) => {
+      format!("{}:{}", strip_bidi(namespace), strip_bidi(&identifier.name))
+    }
+    None => strip_bidi(&identifier.name).to_string(),
+  }
+}
+
+fn dummy_span() -> Span {
+  Span::new(Location::dummy()..Location::dummy())
+}
+
+fn to_pattern(
+  pattern: &Pattern,
+) -> Result<ast::Pattern<'static>, GenerationError> {
+  let parts = pattern
+    .iter()
+    .map(|part| {
+      Ok(match part {
+        PatternPart::Text(text) => ast::PatternPart::Text(to_text(text)),
+        PatternPart::Expression(expression) => {
+          ast::PatternPart::Expression(to_expression(expression)?)
+        }
+        PatternPart::Markup(markup) => {
+          ast::PatternPart::Markup(to_markup(markup)?)
+        }
+      })
+    })
+    .collect::<Result<_, _>>()?;
+  Ok(ast::Pattern { parts })
+}
+
+fn to_quoted_pattern(
+  pattern: ast::Pattern<'static>,
+) -> ast::QuotedPattern<'static> {
+  ast::QuotedPattern {
+    span: dummy_span(),
+    pattern,
+  }
+}
+
+fn to_declaration(
+  declaration: &Declaration,
+) -> Result<ast::Declaration<'static>, GenerationError> {
+  Ok(match declaration {
+    Declaration::Input(input) => {
+      ast::Declaration::InputDeclaration(ast::InputDeclaration {
+        start: Location::dummy(),
+        expression: ast::VariableExpression {
+          span: dummy_span(),
+          variable: to_variable(&input.name)?,
+          annotation: input
+            .value
+            .function
+            .as_ref()
+            .map(to_function)
+            .transpose()?,
+          attributes: to_attributes(&input.value.attributes)?,
+        },
+      })
+    }
+    Declaration::Local(local) => {
+      ast::Declaration::LocalDeclaration(ast::LocalDeclaration {
+        start: Location::dummy(),
+        variable: to_variable(&local.name)?,
+        expression: to_expression(&local.value)?,
+      })
+    }
+  })
+}
+
+fn to_expression(
+  expression: &Expression,
+) -> Result<ast::Expression<'static>, GenerationError> {
+  let annotation = expression.function.as_ref().map(to_function).transpose()?;
+  let attributes = to_attributes(&expression.attributes)?;
+  Ok(match (&expression.arg, annotation) {
+    (Some(Value::Variable(variable)), annotation) => {
+      ast::Expression::VariableExpression(ast::VariableExpression {
+        span: dummy_span(),
+        variable: to_variable(&variable.name)?,
+        annotation,
+        attributes,
+      })
+    }
+    (None, Some(annotation)) => {
+      ast::Expression::AnnotationExpression(ast::AnnotationExpression {
+        span: dummy_span(),
+        annotation,
+        attributes,
+      })
+    }
+    (Some(Value::Literal(literal)), annotation) => {
+      ast::Expression::LiteralExpression(ast::LiteralExpression {
+        span: dummy_span(),
+        literal: to_literal(&literal.value),
+        annotation,
+        attributes,
+      })
+    }
+    (None, None) => return Err(GenerationError::EmptyExpression),
+  })
+}
+
+fn to_function(
+  function: &FunctionRef,
+) -> Result<ast::Annotation<'static>, GenerationError> {
+  Ok(ast::Annotation::Function(ast::Function {
+    start: Location::dummy(),
+    id: to_identifier(&function.name)?,
+    options: to_options(&function.options)?,
+  }))
+}
+
+fn to_markup(markup: &Markup) -> Result<ast::Markup<'static>, GenerationError> {
+  Ok(ast::Markup {
+    span: dummy_span(),
+    kind: match markup.kind {
+      MarkupKind::Open => ast::MarkupKind::Open,
+      MarkupKind::Standalone => ast::MarkupKind::Standalone,
+      MarkupKind::Close => ast::MarkupKind::Close,
+    },
+    id: to_identifier(&markup.name)?,
+    options: to_options(&markup.options)?,
+    attributes: to_attributes(&markup.attributes)?,
+  })
+}
+
+fn to_options(
+  options: &Options,
+) -> Result<Vec<ast::FnOrMarkupOption<'static>>, GenerationError> {
+  options
+    .iter()
+    .map(|(name, value)| {
+      Ok(ast::FnOrMarkupOption {
+        key: to_identifier(name)?,
+        value: match value {
+          Value::Literal(literal) => {
+            ast::LiteralOrVariable::Literal(to_literal(&literal.value))
+          }
+          Value::Variable(variable) => {
+            ast::LiteralOrVariable::Variable(to_variable(&variable.name)?)
+          }
+        },
+      })
+    })
+    .collect()
+}
+
+fn to_attributes(
+  attributes: &Attributes,
+) -> Result<Vec<ast::Attribute<'static>>, GenerationError> {
+  attributes
+    .iter()
+    .map(|(name, value)| {
+      Ok(ast::Attribute {
+        span: dummy_span(),
+        key: to_identifier(name)?,
+        value: value.as_ref().map(|literal| {
+          ast::LiteralOrVariable::Literal(to_literal(&literal.value))
+        }),
+      })
+    })
+    .collect()
+}
+
+fn to_key(key: &Key) -> ast::Key<'static> {
+  match key {
+    Key::Literal(literal) => ast::Key::Literal(to_literal(&literal.value)),
+    Key::CatchAll => ast::Key::Star(ast::Star {
+      start: Location::dummy(),
+    }),
+  }
+}
+
+/// Converts a literal value to the literal the printer prints most simply:
+/// a number if it is a valid number literal, unquoted text if it is a valid
+/// name, and otherwise quoted text. Bidi marks are part of the value, so
+/// values with bidi marks are quoted.
+fn to_literal(value: &str) -> ast::Literal<'static> {
+  if is_number(value) {
+    ast::Literal::Number(
+      ast::Number::new(Location::dummy(), value).into_owned(),
+    )
+  } else if is_valid_name(value) {
+    ast::Literal::Text(to_text(value))
+  } else {
+    ast::Literal::Quoted(ast::Quoted {
+      span: dummy_span(),
+      parts: vec![ast::QuotedPart::Text(to_text(value))],
+    })
+  }
+}
+
+fn to_text(text: &str) -> ast::Text<'static> {
+  ast::Text {
+    start: Location::dummy(),
+    content: Cow::Owned(text.to_string()),
+  }
+}
+
+fn to_variable(name: &str) -> Result<ast::Variable<'static>, GenerationError> {
+  if !is_valid_name(name) {
+    return Err(GenerationError::InvalidName {
+      name: name.to_string(),
+    });
+  }
+  Ok(ast::Variable {
+    span: dummy_span(),
+    name: Cow::Owned(name.to_string()),
+  })
+}
+
+/// Converts a function, markup, option, or attribute name, which is written
+/// as `namespace:name` if it has a namespace.
+fn to_identifier(
+  identifier: &str,
+) -> Result<ast::Identifier<'static>, GenerationError> {
+  let (namespace, name) = match identifier.split_once(':') {
+    Some((namespace, name)) => (Some(namespace), name),
+    None => (None, identifier),
+  };
+  if !namespace.map_or(true, is_valid_name) || !is_valid_name(name) {
+    return Err(GenerationError::InvalidName {
+      name: identifier.to_string(),
+    });
+  }
+  Ok(ast::Identifier {
+    start: Location::dummy(),
+    namespace: namespace.map(|namespace| Cow::Owned(namespace.to_string())),
+    name: Cow::Owned(name.to_string()),
+  })
+}
@@ -1,7 +1,5 @@
 use std::fmt;
 
-use crate::ast::Attribute;
-use crate::ast::FnOrMarkupOption;
 use crate::ast::Identifier;
 use crate::ast::Number;
 use crate::Location;
@@ -28,6 +26,27 @@ macro_rules! diagnostics {
           $(Self::$variant { $($field,)* } => $span,)*
         }
       }
+
+      /// The name of the variant of the diagnostic, like
+      /// `"PlaceholderMissingClosingBrace"`. This is stable, and can be used
+      /// to identify the kind of a diagnostic.
+      pub fn kind(&self) -> &'static str {
+        match self {
+          $(Self::$variant { .. } => stringify!($variant),)*
+        }
+      }
+    }
+
+    impl $name<'_> {
+      /// Converts the diagnostic into one that no longer borrows from the
+      /// source text it was reported for.
+      pub fn into_owned(self) -> $name<'static> {
+        match self {
+          $(Self::$variant { $($field),* } => $name::$variant {
+            $($field: IntoOwned::into_owned($field)),*
+          },)*
+        }
+      }
     }
 
     #[allow(unused_variables)]
@@ -41,6 +60,62 @@ macro_rules! diagnostics {
   };
 }
 
+/// Diagnostics are serialized as their kind, span, and message, like
+/// `{"kind":"VariableMissingName","span":{"start":1,"end":2},"message":"..."}`.
+/// The payload of the diagnostic is not serialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Diagnostic<'_> {
+  fn serialize<S: serde::Serializer>(
+    &self,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct as _;
+
+    let mut state = serializer.serialize_struct("Diagnostic", 3)?;
+    state.serialize_field("kind", self.kind())?;
+    state.serialize_field("span", &self.span())?;
+    state.serialize_field("message", &self.to_string())?;
+    state.end()
+  }
+}
+
+/// Converts the payload of a diagnostic into a `'static` value.
+trait IntoOwned {
+  type Owned: 'static;
+
+  fn into_owned(self) -> Self::Owned;
+}
+
+macro_rules! impl_into_owned_for_copy {
+  ($($ty:ty),*) => {
+    $(impl IntoOwned for $ty {
+      type Owned = $ty;
+
+      fn into_owned(self) -> Self::Owned {
+        self
+      }
+    })*
+  };
+}
+
+impl_into_owned_for_copy!(Span, Option<Span>, Location, char);
+
+impl IntoOwned for Number<'_> {
+  type Owned = Number<'static>;
+
+  fn into_owned(self) -> Self::Owned {
+    Number::into_owned(self)
+  }
+}
+
+impl IntoOwned for Identifier<'_> {
+  type Owned = Identifier<'static>;
+
+  fn into_owned(self) -> Self::Owned {
+    Identifier::into_owned(self)
+  }
+}
+
 diagnostics! {
   pub enum Diagnostic<'a> {
     NumberMissingIntegralPart { number: Number<'a> } => {
@@ -83,9 +158,9 @@ diagnostics! {
       message: ("Markup has invalid spaces between self-closing tag and closing brace (at {:?})", space),
       span: *space,
     },
-    MarkupOptionAfterAttribute { previous_attribute: Attribute<'a>, option: FnOrMarkupOption<'a> } => {
-      message: ("Markup has option after attribute (at {:?})", option.span()),
-      span: option.span(),
+    MarkupOptionAfterAttribute { previous_attribute: Span, option: Span } => {
+      message: ("Markup has option after attribute (at {:?})", option),
+      span: *option,
     },
     FunctionMissingIdentifier { span: Span } => {
       message: ("Function is missing an identifier (at {:?})", span),
@@ -159,6 +234,18 @@ diagnostics! {
       message: ("Attribute is missing a value (at {:?})", span),
       span: *span,
     },
+    AttributeValueIsVariable { span: Span } => {
+      message: ("Attribute value must be a literal, not a variable (at {:?})", span),
+      span: *span,
+    },
+    ReservedAnnotationNotAllowed { span: Span } => {
+      message: ("Reserved annotations are not allowed (at {:?})", span),
+      span: *span,
+    },
+    PrivateUseAnnotationNotAllowed { span: Span } => {
+      message: ("Private use annotations are not allowed (at {:?})", span),
+      span: *span,
+    },
     ComplexMessageNotYetSupported { span: Span } => {
       message: ("Complex messages are not yet supported (at {:?})", *span),
       span: *span,
@@ -207,6 +294,42 @@ diagnostics! {
       message: ("Matcher key cannot be a variable (at {:?})", span),
       span: *span,
     },
+    MatcherSelectorNotVariable { span: Span } => {
+      message: ("Matcher selector must be a variable (at {:?})", span),
+      span: *span,
+    },
+    MatcherSelectorNotExpression { span: Span } => {
+      message: ("Matcher selector must be an expression wrapped in braces (at {:?})", span),
+      span: *span,
+    },
+    MatcherMissingSelectors { span: Span } => {
+      message: ("Matcher must have at least one selector (at {:?})", span),
+      span: *span,
+    },
+    VariantMissingKeys { span: Span } => {
+      message: ("Variant must have at least one key (at {:?})", span),
+      span: *span,
+    },
+    MatcherInvalidKey { span: Span } => {
+      message: ("Matcher key must be a literal or '*' (at {:?})", span),
+      span: *span,
+    },
+    VariantMissingPattern { span: Span } => {
+      message: ("Variant is missing a quoted pattern (at {:?})", span),
+      span: *span,
+    },
+    VariantPatternNotQuoted { span: Span } => {
+      message: ("Variant pattern must be quoted (at {:?})", span),
+      span: *span,
+    },
+    MissingSpaceBeforeSelector { span: Span } => {
+      message: ("Selector is missing a leading space (at {:?})", span),
+      span: *span,
+    },
+    ReservedStatementNotAllowed { span: Span } => {
+      message: ("Unknown statement, only '.input', '.local', and '.match' are allowed (at {:?})", span),
+      span: *span,
+    },
     ReservedStatementMissingSpaceBeforeBody { span: Span } => {
       message: ("Reserved statement must be followed by a space before the body (at {:?})", span),
       span: *span,
@@ -215,6 +338,14 @@ diagnostics! {
       message: ("Reserved statement is missing a mandatory expression (at {:?})", span),
       span: *span,
     },
+    InputDeclarationMissingExpression { span: Span } => {
+      message: ("'.input' keyword must be followed by a variable expression (at {:?})", span),
+      span: *span,
+    },
+    InputDeclarationNotVariable { span: Span } => {
+      message: ("Input declaration must be a variable expression (at {:?})", span),
+      span: *span,
+    },
     LocalDeclarationValueNotWrappedInBraces { span: Span } => {
       message: ("Value of a local declaration must be wrapped in braces (at {:?})", span),
       span: *span,
@@ -227,6 +358,38 @@ diagnostics! {
       message: ("Local declaration is missing an expression (at {:?})", span),
       span: *span,
     },
+    LocalDeclarationMissingVariable { span: Span } => {
+      message: ("Local declaration is missing a variable (at {:?})", span),
+      span: *span,
+    },
+    VariantKeyMismatch { keys: Span } => {
+      message: ("Variant must have exactly one key for each selector (at {:?})", keys),
+      span: *keys,
+    },
+    MissingFallbackVariant { span: Span } => {
+      message: ("Matcher must have a fallback variant with only '*' keys (at {:?})", span),
+      span: *span,
+    },
+    MissingSelectorAnnotation { selector: Span } => {
+      message: ("Selector must reference a declaration with a function annotation (at {:?})", selector),
+      span: *selector,
+    },
+    DuplicateDeclaration { variable: Span, previous: Span } => {
+      message: ("Variable is already declared or used before this declaration (at {:?})", variable),
+      span: *variable,
+    },
+    DuplicateOptionName { key: Span, previous: Span } => {
+      message: ("Option is specified more than once (at {:?})", key),
+      span: *key,
+    },
+    DuplicateVariant { keys: Span, previous: Span } => {
+      message: ("Variant has the same keys as a previous variant (at {:?})", keys),
+      span: *keys,
+    },
+    UnsupportedOption { key: Span } => {
+      message: ("Option is not supported by the function (at {:?})", key),
+      span: *key,
+    },
   }
 }
 
@@ -0,0 +1,785 @@
+//! Formatting of messages with arguments, following the formatting rules of
+//! the specification.
+//!
+//! Formatting never fails. Parts of a message that can not be resolved are
+//! replaced by their fallback representation (like `{$name}`), and the errors
+//! are collected and returned next to the formatted message.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::strip_bidi;
+use crate::ast::Annotation;
+use crate::ast::ComplexMessage;
+use crate::ast::ComplexMessageBody;
+use crate::ast::Declaration;
+use crate::ast::Expression;
+use crate::ast::FnOrMarkupOption;
+use crate::ast::Identifier;
+use crate::ast::Key;
+use crate::ast::LiteralOrVariable;
+use crate::ast::Markup;
+use crate::ast::MarkupKind;
+use crate::ast::Matcher;
+use crate::ast::Message;
+use crate::ast::Pattern;
+use crate::ast::PatternPart;
+use crate::ast::Selector;
+use crate::ast::Variable;
+use crate::ast::Variant;
+use crate::functions::Function;
+use crate::functions::FunctionError;
+use crate::functions::FunctionOptions;
+use crate::functions::FunctionRegistry;
+use crate::util::Span;
+use crate::util::Spanned;
+
+/// A value that can be passed to a message as an argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  String(String),
+  Number(f64),
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Value::String(string) => f.write_str(string),
+      Value::Number(number) => write!(f, "{number}"),
+    }
+  }
+}
+
+impl From<String> for Value {
+  fn from(value: String) -> Self {
+    Value::String(value)
+  }
+}
+
+impl From<&str> for Value {
+  fn from(value: &str) -> Self {
+    Value::String(value.to_string())
+  }
+}
+
+impl From<f64> for Value {
+  fn from(value: f64) -> Self {
+    Value::Number(value)
+  }
+}
+
+impl From<i32> for Value {
+  fn from(value: i32) -> Self {
+    Value::Number(value.into())
+  }
+}
+
+impl From<u32> for Value {
+  fn from(value: u32) -> Self {
+    Value::Number(value.into())
+  }
+}
+
+/// The arguments of a message, by the name of their variable (without the
+/// `$`).
+pub type Arguments = HashMap<String, Value>;
+
+/// An error that occurred while formatting a message. The part of the message
+/// that caused it is replaced by its fallback representation.
+#[derive(Debug, Clone)]
+pub enum FormatError {
+  /// A variable is not declared, and is not one of the arguments.
+  UnresolvedVariable { name: String, span: Span },
+  /// An annotation references a function that is not in the
+  /// [`FunctionRegistry`].
+  UnknownFunction { name: String, span: Span },
+  /// The operand of a function is missing or not supported by it.
+  BadOperand { span: Span },
+  /// The value of an option is not supported by the function.
+  BadOption { name: String, span: Span },
+  /// Reserved and private-use annotations (from the LDML 45 draft) can not be
+  /// formatted.
+  UnsupportedExpression { span: Span },
+  /// Reserved statements (from the LDML 45 draft) can not be formatted. They
+  /// are ignored.
+  UnsupportedStatement { span: Span },
+  /// A selector can not be used for selection, so only the catch-all keys of
+  /// the variants match it.
+  BadSelector { span: Span },
+  /// No variant matches the selectors, so nothing is formatted. This only
+  /// happens for matchers without a fallback variant, which is reported by
+  /// [`validate`](crate::validate).
+  NoFallback { span: Span },
+}
+
+impl FormatError {
+  pub fn span(&self) -> Span {
+    match self {
+      FormatError::UnresolvedVariable { span, .. }
+      | FormatError::UnknownFunction { span, .. }
+      | FormatError::BadOperand { span }
+      | FormatError::BadOption { span, .. }
+      | FormatError::UnsupportedExpression { span }
+      | FormatError::UnsupportedStatement { span }
+      | FormatError::BadSelector { span }
+      | FormatError::NoFallback { span } => *span,
+    }
+  }
+}
+
+impl fmt::Display for FormatError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FormatError::UnresolvedVariable { name, span } => {
+        write!(f, "Variable '${name}' is not defined (at {span:?})")
+      }
+      FormatError::UnknownFunction { name, span } => {
+        write!(f, "Function ':{name}' is not defined (at {span:?})")
+      }
+      FormatError::BadOperand { span } => {
+        write!(f, "Operand is not supported by the function (at {span:?})")
+      }
+      FormatError::BadOption { name, span } => {
+        write!(f, "Value of option '{name}' is not supported (at {span:?})")
+      }
+      FormatError::UnsupportedExpression { span } => {
+        write!(f, "Reserved annotations are not supported (at {span:?})")
+      }
+      FormatError::UnsupportedStatement { span } => {
+        write!(f, "Reserved statements are not supported (at {span:?})")
+      }
+      FormatError::BadSelector { span } => {
+        write!(f, "Selector can not be used for selection (at {span:?})")
+      }
+      FormatError::NoFallback { span } => {
+        write!(f, "No variant matches the selectors (at {span:?})")
+      }
+    }
+  }
+}
+
+impl std::error::Error for FormatError {}
+
+/// A part of a formatted message, as returned by [`format_to_parts`]. These
+/// mirror the formatted parts of the specification.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormattedPart {
+  /// Text of the pattern, with its escapes resolved.
+  Text { value: String },
+  /// A placeholder that was formatted. The source is the fallback
+  /// representation of its expression, like `$name` or `|literal|`.
+  Expression { source: String, value: String },
+  /// Markup, with the resolved values of its options by name. Markup does not
+  /// have a formatted value.
+  Markup {
+    kind: MarkupKind,
+    name: String,
+    options: BTreeMap<String, Value>,
+  },
+  /// A placeholder that could not be formatted. It is formatted to a string
+  /// as its source (the fallback representation of its expression) inside of
+  /// braces, like `{$name}`.
+  Fallback { source: String },
+}
+
+/// Formats a message with the given arguments to a string, with the default
+/// functions of the specification.
+///
+/// The message is formatted for the locale, which is a BCP 47 language tag
+/// like `"en-US"`. Messages with diagnostics (from parsing or from
+/// [`validate`](crate::validate)) should not be formatted, because parts of
+/// them may be missing.
+pub fn format_message(
+  message: &Message,
+  locale: &str,
+  arguments: &Arguments,
+) -> (String, Vec<FormatError>) {
+  format_message_with_functions(
+    message,
+    locale,
+    arguments,
+    &FunctionRegistry::with_default_functions(),
+  )
+}
+
+/// Formats a message with the given arguments to a string, looking up the
+/// functions of its annotations in the registry.
+pub fn format_message_with_functions(
+  message: &Message,
+  locale: &str,
+  arguments: &Arguments,
+  functions: &FunctionRegistry,
+) -> (String, Vec<FormatError>) {
+  let (parts, errors) =
+    format_to_parts_with_functions(message, locale, arguments, functions);
+  let mut out = String::new();
+  for part in parts {
+    match part {
+      FormattedPart::Text { value } => out.push_str(&value),
+      FormattedPart::Expression { value, .. } => out.push_str(&value),
+      // Markup does not produce any output when formatting to a string.
+      FormattedPart::Markup { .. } => {}
+      FormattedPart::Fallback { source } => {
+        out.push('{');
+        out.push_str(&source);
+        out.push('}');
+      }
+    }
+  }
+  (out, errors)
+}
+
+/// Formats a message with the given arguments to a list of parts, so that
+/// placeholders and markup can be told apart from the text around them, for
+/// example to render markup as elements. The default functions of the
+/// specification are available.
+///
+/// Consecutive text is merged into a single part.
+pub fn format_to_parts(
+  message: &Message,
+  locale: &str,
+  arguments: &Arguments,
+) -> (Vec<FormattedPart>, Vec<FormatError>) {
+  format_to_parts_with_functions(
+    message,
+    locale,
+    arguments,
+    &FunctionRegistry::with_default_functions(),
+  )
+}
+
+/// Formats a message with the given arguments to a list of parts, looking up
+/// the functions of its annotations in the registry.
+pub fn format_to_parts_with_functions(
+  message: &Message,
+  locale: &str,
+  arguments: &Arguments,
+  functions: &FunctionRegistry,
+) -> (Vec<FormattedPart>, Vec<FormatError>) {
+  let mut formatter = Formatter {
+    locale,
+    arguments,
+    functions,
+    locals: HashMap::new(),
+    local_fallbacks: HashMap::new(),
+    errors: vec![],
+  };
+  let pattern = match message {
+    Message::Simple(pattern) => Some(pattern),
+    Message::Complex(complex) => formatter.resolve_complex_message(complex),
+  };
+  let parts = match pattern {
+    Some(pattern) => formatter.format_pattern(pattern),
+    None => vec![],
+  };
+  (parts, formatter.errors)
+}
+
+/// The resolved value of an expression or a variable.
+#[derive(Clone)]
+enum Resolved<'f> {
+  Value(ResolvedValue<'f>),
+  /// The value could not be resolved, so the fallback representation of the
+  /// expression is formatted instead.
+  Fallback,
+}
+
+#[derive(Clone)]
+struct ResolvedValue<'f> {
+  /// The operand of the expression, which is `None` for expressions that only
+  /// have an annotation.
+  operand: Option<Value>,
+  /// The function that formats and selects the value. Values without one are
+  /// formatted as their operand, and do not support selection.
+  function: Option<AnnotatedFunction<'f>>,
+}
+
+#[derive(Clone)]
+struct AnnotatedFunction<'f> {
+  name: String,
+  function: &'f dyn Function,
+  options: FunctionOptions,
+  /// The span of the annotation, which errors of the function are reported
+  /// at.
+  span: Span,
+}
+
+struct Formatter<'f> {
+  locale: &'f str,
+  arguments: &'f Arguments,
+  functions: &'f FunctionRegistry,
+  /// The values of the declared variables, by name.
+  locals: HashMap<String, Resolved<'f>>,
+  /// The fallback representations of the expressions of the `.local`
+  /// declarations, by name.
+  local_fallbacks: HashMap<String, String>,
+  errors: Vec<FormatError>,
+}
+
+impl<'f> Formatter<'f> {
+  /// Resolves the declarations of the message, and returns the pattern that
+  /// should be formatted.
+  fn resolve_complex_message<'m>(
+    &mut self,
+    message: &'m ComplexMessage,
+  ) -> Option<&'m Pattern<'m>> {
+    for declaration in &message.declarations {
+      match declaration {
+        Declaration::InputDeclaration(input) => {
+          let expression = &input.expression;
+          let operand = self.resolve_variable(&expression.variable);
+          let value = self
+            .resolve_annotation(Some(operand), expression.annotation.as_ref());
+          let name = strip_bidi(&expression.variable.name).to_string();
+          self.locals.insert(name, value);
+        }
+        Declaration::LocalDeclaration(local) => {
+          let value = self.resolve_expression(&local.expression);
+          let fallback = self.fallback(&local.expression);
+          let name = strip_bidi(&local.variable.name).to_string();
+          self.locals.insert(name.clone(), value);
+          self.local_fallbacks.insert(name, fallback);
+        }
+        Declaration::ReservedStatement(statement) => {
+          self.errors.push(FormatError::UnsupportedStatement {
+            span: statement.span(),
+          });
+        }
+      }
+    }
+
+    match &message.body {
+      ComplexMessageBody::QuotedPattern(quoted) => Some(&quoted.pattern),
+      ComplexMessageBody::Matcher(matcher) => self.select(matcher),
+    }
+  }
+
+  /// Selects the variant of a matcher to format, following the pattern
+  /// selection algorithm of the specification.
+  fn select<'m>(&mut self, matcher: &'m Matcher) -> Option<&'m Pattern<'m>> {
+    let mut preferences = Vec::with_capacity(matcher.selectors.len());
+    for (i, selector) in matcher.selectors.iter().enumerate() {
+      let value = match selector {
+        Selector::Variable(variable) => self.resolve_variable(variable),
+        Selector::Expression(expression) => self.resolve_expression(expression),
+      };
+
+      // The unique literal keys of the variants for this selector, in
+      // Unicode Normalization Form C.
+      let mut keys = Vec::<String>::new();
+      for variant in &matcher.variants {
+        if variant.keys.len() != matcher.selectors.len() {
+          continue;
+        }
+        if let Key::Literal(literal) = &variant.keys[i] {
+          let key = literal.normalized_value();
+          if !keys.iter().any(|previous| *previous == key) {
+            keys.push(key.into_owned());
+          }
+        }
+      }
+
+      // A selector that does not support selection only matches catch-all
+      // keys.
+      let matches = match value {
+        Resolved::Value(ResolvedValue {
+          operand,
+          function: Some(function),
+        }) => {
+          let key_refs = keys.iter().map(String::as_str).collect::<Vec<_>>();
+          let result = function.function.select(
+            operand.as_ref(),
+            &function.options,
+            self.locale,
+            &key_refs,
+          );
+          match result {
+            Ok(matches) => matches
+              .iter()
+              .filter_map(|i| keys.get(*i).cloned())
+              .collect(),
+            Err(FunctionError::NotSelectable) => {
+              self.errors.push(FormatError::BadSelector {
+                span: selector.span(),
+              });
+              vec![]
+            }
+            Err(error) => {
+              self.errors.push(function_error(error, function.span));
+              vec![]
+            }
+          }
+        }
+        _ => {
+          self.errors.push(FormatError::BadSelector {
+            span: selector.span(),
+          });
+          vec![]
+        }
+      };
+      preferences.push(matches);
+    }
+
+    match select_variant(&matcher.variants, &preferences) {
+      Some(variant) => Some(&variant.pattern.pattern),
+      None => {
+        self.errors.push(FormatError::NoFallback {
+          span: Span::new(matcher.start..matcher.start + ".match"),
+        });
+        None
+      }
+    }
+  }
+
+  fn resolve_expression(&mut self, expression: &Expression) -> Resolved<'f> {
+    match expression {
+      Expression::LiteralExpression(expression) => {
+        let operand = Resolved::Value(ResolvedValue {
+          operand: Some(Value::String(expression.literal.value().into_owned())),
+          function: None,
+        });
+        self.resolve_annotation(Some(operand), expression.annotation.as_ref())
+      }
+      Expression::VariableExpression(expression) => {
+        let operand = self.resolve_variable(&expression.variable);
+        self.resolve_annotation(Some(operand), expression.annotation.as_ref())
+      }
+      Expression::AnnotationExpression(expression) => {
+        self.resolve_annotation(None, Some(&expression.annotation))
+      }
+    }
+  }
+
+  /// Applies the annotation of an expression to its resolved operand.
+  ///
+  /// An operand that was annotated with the same function keeps the options
+  /// of that annotation, unless they are set again. For example, in
+  /// `.local $y = {$x :number minimumFractionDigits=2}`, `{$y :number}` is
+  /// formatted with two fraction digits.
+  fn resolve_annotation(
+    &mut self,
+    operand: Option<Resolved<'f>>,
+    annotation: Option<&Annotation>,
+  ) -> Resolved<'f> {
+    let annotation = match annotation {
+      None => return operand.unwrap_or(Resolved::Fallback),
+      Some(Annotation::Function(function)) => function,
+      Some(annotation) => {
+        self.errors.push(FormatError::UnsupportedExpression {
+          span: annotation.span(),
+        });
+        return Resolved::Fallback;
+      }
+    };
+
+    let name = identifier_name(&annotation.id);
+    let Some(function) = self.functions.get(&annotation.id) else {
+      self.errors.push(FormatError::UnknownFunction {
+        name,
+        span: annotation.span(),
+      });
+      return Resolved::Fallback;
+    };
+
+    let (operand, mut options) = match operand {
+      None => (None, FunctionOptions::default()),
+      Some(Resolved::Fallback) => return Resolved::Fallback,
+      Some(Resolved::Value(value)) => match value.function {
+        Some(previous) if previous.name == name => {
+          (value.operand, previous.options)
+        }
+        _ => (value.operand, FunctionOptions::default()),
+      },
+    };
+    options.merge(&FunctionOptions::new(
+      self.resolve_options(&annotation.options),
+    ));
+
+    Resolved::Value(ResolvedValue {
+      operand,
+      function: Some(AnnotatedFunction {
+        name,
+        function,
+        options,
+        span: annotation.span(),
+      }),
+    })
+  }
+
+  fn resolve_variable(&mut self, variable: &Variable) -> Resolved<'f> {
+    let name = strip_bidi(&variable.name);
+    if let Some(value) = self.locals.get(name) {
+      return value.clone();
+    }
+    if let Some(value) = self.arguments.get(name) {
+      return Resolved::Value(ResolvedValue {
+        operand: Some(value.clone()),
+        function: None,
+      });
+    }
+    self.errors.push(FormatError::UnresolvedVariable {
+      name: name.to_string(),
+      span: variable.span(),
+    });
+    Resolved::Fallback
+  }
+
+  /// Resolves the options of an annotation or markup by name. Options whose
+  /// value can not be resolved are left out. A variable is resolved to its
+  /// operand, without the function it was annotated with.
+  fn resolve_options(
+    &mut self,
+    options: &[FnOrMarkupOption],
+  ) -> BTreeMap<String, Value> {
+    let mut resolved = BTreeMap::new();
+    for option in options {
+      let value = match &option.value {
+        LiteralOrVariable::Literal(literal) => {
+          Some(Value::String(literal.value().into_owned()))
+        }
+        LiteralOrVariable::Variable(variable) => {
+          match self.resolve_variable(variable) {
+            Resolved::Value(value) => value.operand,
+            Resolved::Fallback => None,
+          }
+        }
+      };
+      if let Some(value) = value {
+        resolved.insert(identifier_name(&option.key), value);
+      }
+    }
+    resolved
+  }
+
+  /// Formats a resolved value with its function, or returns `None` if the
+  /// function fails. Values without a function are formatted with `:string`
+  /// if it is registered, and errors are reported at the span of the
+  /// placeholder.
+  fn format_value(
+    &mut self,
+    value: &ResolvedValue,
+    placeholder: Span,
+  ) -> Option<String> {
+    let (function, options, span) = match &value.function {
+      Some(function) => (function.function, &function.options, function.span),
+      None => match self.functions.get(&Identifier::new(None, "string")) {
+        Some(function) => (function, &FunctionOptions::default(), placeholder),
+        None => return value.operand.as_ref().map(Value::to_string),
+      },
+    };
+    match function.format(value.operand.as_ref(), options, self.locale) {
+      Ok(formatted) => Some(formatted),
+      Err(error) => {
+        self.errors.push(function_error(error, span));
+        None
+      }
+    }
+  }
+
+  fn format_pattern(&mut self, pattern: &Pattern) -> Vec<FormattedPart> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    for part in &pattern.parts {
+      let part = match part {
+        PatternPart::Text(part) => {
+          text.push_str(&part.content);
+          continue;
+        }
+        PatternPart::Escape(escape) => {
+          text.push(escape.escaped_char);
+          continue;
+        }
+        PatternPart::Expression(expression) => {
+          let source = self.fallback(expression);
+          let value = match self.resolve_expression(expression) {
+            Resolved::Value(value) => {
+              self.format_value(&value, expression.span())
+            }
+            Resolved::Fallback => None,
+          };
+          match value {
+            Some(value) => FormattedPart::Expression { source, value },
+            None => FormattedPart::Fallback { source },
+          }
+        }
+        PatternPart::Markup(markup) => self.format_markup(markup),
+      };
+      if !text.is_empty() {
+        parts.push(FormattedPart::Text {
+          value: std::mem::take(&mut text),
+        });
+      }
+      parts.push(part);
+    }
+    if !text.is_empty() {
+      parts.push(FormattedPart::Text { value: text });
+    }
+    parts
+  }
+
+  fn format_markup(&mut self, markup: &Markup) -> FormattedPart {
+    FormattedPart::Markup {
+      kind: markup.kind,
+      name: identifier_name(&markup.id),
+      options: self.resolve_options(&markup.options),
+    }
+  }
+
+  /// The fallback representation of an expression: its operand as a variable
+  /// or a quoted literal, or otherwise its annotation. A variable that refers
+  /// to a `.local` declaration is represented by the fallback of the
+  /// declaration's expression.
+  fn fallback(&self, expression: &Expression) -> String {
+    match expression {
+      Expression::LiteralExpression(expression) => {
+        let mut fallback = String::from("|");
+        for c in expression.literal.value().chars() {
+          if matches!(c, '\\' | '|') {
+            fallback.push('\\');
+          }
+          fallback.push(c);
+        }
+        fallback.push('|');
+        fallback
+      }
+      Expression::VariableExpression(expression) => {
+        let name = strip_bidi(&expression.variable.name);
+        match self.local_fallbacks.get(name) {
+          Some(fallback) => fallback.clone(),
+          None => format!("${}", name),
+        }
+      }
+      Expression::AnnotationExpression(expression) => {
+        match &expression.annotation {
+          Annotation::Function(function) => {
+            format!(":{}", identifier_name(&function.id))
+          }
+          Annotation::PrivateUseAnnotation(annotation) => {
+            annotation.sigil.to_string()
+          }
+          Annotation::ReservedAnnotation(annotation) => {
+            annotation.sigil.to_string()
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Converts an error of a function to a format error at the span of its
+/// annotation.
+fn function_error(error: FunctionError, span: Span) -> FormatError {
+  match error {
+    FunctionError::BadOperand => FormatError::BadOperand { span },
+    FunctionError::BadOption { name } => FormatError::BadOption { name, span },
+    FunctionError::NotSelectable => FormatError::BadSelector { span },
+  }
+}
+
+/// Selects the best variant for the keys that match each selector, which are
+/// ordered from the most to the least preferred key.
+///
+/// Variants with a key that does not match are filtered out. The remaining
+/// variants are sorted by the preference of their key for the first selector,
+/// then by that for the second selector, and so on, where catch-all keys come
+/// after all matching keys. The first variant is the best match.
+fn select_variant<'v, 'a>(
+  variants: &'v [Variant<'a>],
+  preferences: &[Vec<String>],
+) -> Option<&'v Variant<'a>> {
+  variants
+    .iter()
+    .filter_map(|variant| {
+      if variant.keys.len() != preferences.len() {
+        return None;
+      }
+      let ranks = variant
+        .keys
+        .iter()
+        .zip(preferences)
+        .map(|(key, matches)| match key {
+          Key::Literal(literal) => {
+            let value = literal.normalized_value();
+            matches.iter().position(|key| *key == value)
+          }
+          Key::Star(_) => Some(matches.len()),
+        })
+        .collect::<Option<Vec<_>>>()?;
+      Some((variant, ranks))
+    })
+    .min_by(|(_, a), (_, b)| a.cmp(b))
+    .map(|(variant, _)| variant)
+}
+
+/// The name of a function or option, with its namespace.
+fn identifier_name(id: &Identifier) -> String {
+  match &id.namespace {
+    Some(namespace) => {
+      format!("{}:{}", strip_bidi(namespace), strip_bidi(&id.name))
+    }
+    None => strip_bidi(&id.name).to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::select_variant;
+  use crate::ast::ComplexMessageBody;
+  use crate::ast::Message;
+  use crate::ast::PatternPart;
+  use crate::parse;
+
+  /// Selects a variant of the matcher in `source`, returning the text of its
+  /// pattern.
+  fn select(source: &str, preferences: &[&[&str]]) -> Option<String> {
+    let (message, diagnostics, _info) = parse(source);
+    assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+    let Message::Complex(complex) = message else {
+      panic!("expected a complex message");
+    };
+    let ComplexMessageBody::Matcher(matcher) = complex.body else {
+      panic!("expected a matcher");
+    };
+    let preferences = preferences
+      .iter()
+      .map(|keys| keys.iter().map(|key| key.to_string()).collect())
+      .collect::<Vec<_>>();
+    let variant = select_variant(&matcher.variants, &preferences)?;
+    match variant.pattern.pattern.parts.as_slice() {
+      [PatternPart::Text(text)] => Some(text.content.to_string()),
+      parts => panic!("unexpected pattern {parts:?}"),
+    }
+  }
+
+  #[test]
+  fn selects_the_most_preferred_key() {
+    let source =
+      ".local $x = {1 :f}\n.match $x\none {{one}}\n1 {{exact}}\n* {{other}}";
+    assert_eq!(select(source, &[&["1", "one"]]).unwrap(), "exact");
+    assert_eq!(select(source, &[&["one", "1"]]).unwrap(), "one");
+    assert_eq!(select(source, &[&["one"]]).unwrap(), "one");
+    assert_eq!(select(source, &[&["two"]]).unwrap(), "other");
+    assert_eq!(select(source, &[&[]]).unwrap(), "other");
+  }
+
+  #[test]
+  fn sorts_by_the_first_selector_first() {
+    let source = ".local $x = {1 :f}\n.local $y = {1 :f}\n.match $x $y\n* b {{*b}}\na * {{a*}}\n* * {{**}}";
+    assert_eq!(select(source, &[&["a"], &["b"]]).unwrap(), "a*");
+    assert_eq!(select(source, &[&[], &["b"]]).unwrap(), "*b");
+    assert_eq!(select(source, &[&[], &[]]).unwrap(), "**");
+  }
+
+  #[test]
+  fn matches_quoted_keys() {
+    let source = ".local $x = {1 :f}\n.match $x\n|a b| {{quoted}}\n* {{other}}";
+    assert_eq!(select(source, &[&["a b"]]).unwrap(), "quoted");
+  }
+
+  #[test]
+  fn selects_nothing_without_fallback() {
+    let source = ".local $x = {1 :f}\n.match $x\na {{a}}";
+    assert_eq!(select(source, &[&["b"]]), None);
+  }
+}
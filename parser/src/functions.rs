@@ -0,0 +1,302 @@
+//! Functions that can be used in the annotations of a message, like
+//! `{$count :number}`.
+//!
+//! A function formats its operand to a string, and may support selection, in
+//! which case it decides which variant keys match its operand. Functions are
+//! looked up by their name in a [`FunctionRegistry`] when formatting a
+//! message.
+//!
+//! The default functions of the specification are registered by
+//! [`FunctionRegistry::with_default_functions`]. The date and time functions
+//! of the specification's extended registry require the `datetime` feature.
+
+#[cfg(feature = "datetime")]
+mod datetime;
+#[cfg(feature = "datetime")]
+mod datetime_symbols;
+mod number;
+mod number_symbols_data;
+mod plural;
+mod plural_rules_data;
+mod string;
+#[cfg(feature = "datetime")]
+mod time_zone_data;
+#[cfg(feature = "datetime")]
+mod time_zones;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::strip_bidi;
+use crate::ast::Identifier;
+use crate::format::Value;
+
+pub(crate) use number::normalize_number;
+
+/// A function that can be registered in a [`FunctionRegistry`].
+pub trait Function {
+  /// Formats the operand of an expression with the resolved options of its
+  /// annotation for the locale, which is a BCP 47 language tag like `"en-US"`.
+  /// The operand is `None` for expressions without one, like `{:now}`.
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+  ) -> Result<String, FunctionError>;
+
+  /// Returns the indices of the variant keys that match the operand, ordered
+  /// from the most to the least preferred key. Catch-all keys are not passed
+  /// to the function, and the other keys are in Unicode Normalization Form
+  /// C.
+  ///
+  /// Functions that do not support selection do not implement this, in which
+  /// case the selector is reported as a bad selector.
+  fn select(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+    keys: &[&str],
+  ) -> Result<Vec<usize>, FunctionError> {
+    let _ = (operand, options, locale, keys);
+    Err(FunctionError::NotSelectable)
+  }
+
+  /// The names of the options that the function supports, which are used by
+  /// [`validate_with_functions`](crate::validate_with_functions) to report
+  /// unsupported options. `None` (the default) means that any option is
+  /// supported.
+  fn option_names(&self) -> Option<&[&str]> {
+    None
+  }
+
+  /// A short description of the function in Markdown, which is shown by
+  /// tools like the language server.
+  fn description(&self) -> Option<&str> {
+    None
+  }
+
+  /// A short description of an option of the function in Markdown.
+  fn option_description(&self, name: &str) -> Option<&str> {
+    let _ = name;
+    None
+  }
+}
+
+/// An error returned by a [`Function`]. The formatter reports it with the
+/// span of the annotation, and formats the fallback representation of the
+/// expression instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FunctionError {
+  /// The operand is missing, or is not a value that the function supports.
+  BadOperand,
+  /// The value of an option is not supported by the function.
+  BadOption { name: String },
+  /// The function does not support selection.
+  NotSelectable,
+}
+
+impl fmt::Display for FunctionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FunctionError::BadOperand => f.write_str("Operand is not supported"),
+      FunctionError::BadOption { name } => {
+        write!(f, "Value of option '{name}' is not supported")
+      }
+      FunctionError::NotSelectable => {
+        f.write_str("Function does not support selection")
+      }
+    }
+  }
+}
+
+impl std::error::Error for FunctionError {}
+
+/// The resolved options of an annotation, by name. Option names with a
+/// namespace are prefixed by it, like `u:locale`.
+///
+/// Options whose value could not be resolved are left out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionOptions(BTreeMap<String, Value>);
+
+impl FunctionOptions {
+  pub fn new(options: BTreeMap<String, Value>) -> Self {
+    FunctionOptions(options)
+  }
+
+  pub fn get(&self, name: &str) -> Option<&Value> {
+    self.0.get(name)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+    self.0.iter().map(|(name, value)| (name.as_str(), value))
+  }
+
+  /// Sets the options of `other`, replacing options with the same name. This
+  /// is used when an annotated variable is annotated again with the same
+  /// function, like `{$x :number minimumFractionDigits=2}`.
+  pub fn merge(&mut self, other: &FunctionOptions) {
+    for (name, value) in &other.0 {
+      self.0.insert(name.clone(), value.clone());
+    }
+  }
+
+  /// Gets an option that must be a string.
+  pub fn get_string(&self, name: &str) -> Result<Option<&str>, FunctionError> {
+    match self.0.get(name) {
+      None => Ok(None),
+      Some(Value::String(value)) => Ok(Some(value)),
+      Some(Value::Number(_)) => Err(bad_option(name)),
+    }
+  }
+
+  /// Gets an option that must be one of the given strings, like the
+  /// `select` option of `:number`.
+  pub fn get_choice<'c>(
+    &self,
+    name: &str,
+    choices: &[&'c str],
+  ) -> Result<Option<&'c str>, FunctionError> {
+    match self.get_string(name)? {
+      None => Ok(None),
+      Some(value) => match choices.iter().find(|choice| **choice == value) {
+        Some(choice) => Ok(Some(choice)),
+        None => Err(bad_option(name)),
+      },
+    }
+  }
+
+  /// Gets an option that must be a non-negative integer, given as a number or
+  /// as a string of digits.
+  pub fn get_integer(&self, name: &str) -> Result<Option<u32>, FunctionError> {
+    match self.0.get(name) {
+      None => Ok(None),
+      Some(Value::String(value)) => value
+        .parse::<u32>()
+        .ok()
+        .filter(|_| value.bytes().all(|b| b.is_ascii_digit()))
+        .map(Some)
+        .ok_or_else(|| bad_option(name)),
+      Some(Value::Number(value)) => {
+        if value.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(value) {
+          Ok(Some(*value as u32))
+        } else {
+          Err(bad_option(name))
+        }
+      }
+    }
+  }
+
+  /// Gets an option that must be `true` or `false`.
+  pub fn get_bool(&self, name: &str) -> Result<Option<bool>, FunctionError> {
+    Ok(
+      self
+        .get_choice(name, &["true", "false"])?
+        .map(|value| value == "true"),
+    )
+  }
+}
+
+fn bad_option(name: &str) -> FunctionError {
+  FunctionError::BadOption {
+    name: name.to_string(),
+  }
+}
+
+/// The functions that can be used when formatting a message, by their
+/// identifier (namespace and name).
+#[derive(Default)]
+pub struct FunctionRegistry {
+  functions: HashMap<(Option<String>, String), Box<dyn Function>>,
+}
+
+impl FunctionRegistry {
+  /// Creates a registry without any functions.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a registry with the default functions of the specification:
+  /// `:string`, `:number`, and `:integer`. With the `datetime` feature, it
+  /// also has `:datetime`, `:date`, and `:time`.
+  pub fn with_default_functions() -> Self {
+    let mut registry = Self::new();
+    registry.register(&Identifier::new(None, "string"), string::StringFunction);
+    registry.register(&Identifier::new(None, "number"), number::NumberFunction);
+    registry
+      .register(&Identifier::new(None, "integer"), number::IntegerFunction);
+    #[cfg(feature = "datetime")]
+    {
+      registry.register(
+        &Identifier::new(None, "datetime"),
+        datetime::DateTimeFunction,
+      );
+      registry.register(&Identifier::new(None, "date"), datetime::DateFunction);
+      registry.register(&Identifier::new(None, "time"), datetime::TimeFunction);
+    }
+    registry
+  }
+
+  /// Registers a function, replacing any function with the same identifier.
+  pub fn register(
+    &mut self,
+    id: &Identifier,
+    function: impl Function + 'static,
+  ) {
+    self.functions.insert(key(id), Box::new(function));
+  }
+
+  pub fn get(&self, id: &Identifier) -> Option<&dyn Function> {
+    self.functions.get(&key(id)).map(|function| &**function)
+  }
+
+  /// The registered functions with their identifiers, in no particular
+  /// order.
+  pub fn iter(&self) -> impl Iterator<Item = (Identifier<'_>, &dyn Function)> {
+    self.functions.iter().map(|((namespace, name), function)| {
+      (Identifier::new(namespace.as_deref(), name), &**function)
+    })
+  }
+}
+
+impl fmt::Debug for FunctionRegistry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_set().entries(self.functions.keys()).finish()
+  }
+}
+
+fn key(id: &Identifier) -> (Option<String>, String) {
+  (
+    id.namespace
+      .as_deref()
+      .map(|namespace| strip_bidi(namespace).to_string()),
+    strip_bidi(&id.name).to_string(),
+  )
+}
+
+/// The parts of a BCP 47 language tag that the default functions use.
+struct Locale {
+  /// The lowercase language subtag, like `en`.
+  language: String,
+  /// The uppercase region subtag, like `US`.
+  region: Option<String>,
+}
+
+impl Locale {
+  /// Parses the language and region of a language tag. Underscores are
+  /// accepted as separators, like in `en_US`.
+  fn parse(locale: &str) -> Locale {
+    let mut subtags = locale.split(['-', '_']);
+    let language = subtags.next().unwrap_or_default().to_ascii_lowercase();
+    let region = subtags
+      .take_while(|subtag| subtag.len() > 1)
+      .find(|subtag| {
+        (subtag.len() == 2 && subtag.bytes().all(|b| b.is_ascii_alphabetic()))
+          || (subtag.len() == 3 && subtag.bytes().all(|b| b.is_ascii_digit()))
+      })
+      .map(|region| region.to_ascii_uppercase());
+    Locale { language, region }
+  }
+}
@@ -0,0 +1,874 @@
+//! The `:datetime`, `:date`, and `:time` functions, which format dates and
+//! times given as ISO 8601 strings, like `2024-05-01` or
+//! `2024-05-01T14:30:00+02:00`.
+//!
+//! The `timeZone` option can be an IANA time zone like `Europe/Berlin` (see
+//! [`time_zones`](super::time_zones) for the bundled data), an offset like
+//! `+02:00`, or `input`, which keeps the offset of the operand. Operands
+//! without an offset are local times: they are formatted as they are, without
+//! a time zone name, or taken to be in the `timeZone` if it is set.
+
+use super::bad_option;
+use super::datetime_symbols::DateTimeSymbols;
+use super::time_zones::TimeZone;
+use super::Function;
+use super::FunctionError;
+use super::FunctionOptions;
+use super::Locale;
+use crate::format::Value;
+
+const STYLES: &[&str] = &["full", "long", "medium", "short"];
+const NUMERIC: &[&str] = &["numeric", "2-digit"];
+
+/// The `:datetime` function. The `dateStyle` and `timeStyle` options take
+/// precedence over the field options, like `month`. Without any of these
+/// options, the year, month, day, hour, and minute are formatted as numbers.
+pub(super) struct DateTimeFunction;
+
+impl Function for DateTimeFunction {
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+  ) -> Result<String, FunctionError> {
+    let symbols = DateTimeSymbols::for_locale(&Locale::parse(locale));
+    let date_style = style_option(options, "dateStyle")?;
+    let time_style = style_option(options, "timeStyle")?;
+    let pattern = if date_style.is_some() || time_style.is_some() {
+      style_pattern(symbols, date_style, time_style)
+    } else {
+      field_pattern(symbols, options)?
+    };
+    format_operand(operand, options, symbols, pattern)
+  }
+
+  fn option_names(&self) -> Option<&[&str]> {
+    Some(&[
+      "dateStyle",
+      "timeStyle",
+      "weekday",
+      "year",
+      "month",
+      "day",
+      "hour",
+      "minute",
+      "second",
+      "fractionalSecondDigits",
+      "timeZoneName",
+      "hourCycle",
+      "timeZone",
+    ])
+  }
+
+  fn description(&self) -> Option<&str> {
+    Some(
+      "Formats an ISO 8601 date and time, with a date and time style or with \
+       the fields of its options.",
+    )
+  }
+
+  fn option_description(&self, name: &str) -> Option<&str> {
+    option_description(name)
+  }
+}
+
+/// The `:date` function, which formats the date in the `style` of its option
+/// (`medium` by default).
+pub(super) struct DateFunction;
+
+impl Function for DateFunction {
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+  ) -> Result<String, FunctionError> {
+    let symbols = DateTimeSymbols::for_locale(&Locale::parse(locale));
+    let style = style_option(options, "style")?.unwrap_or(2);
+    let pattern = style_pattern(symbols, Some(style), None);
+    format_operand(operand, options, symbols, pattern)
+  }
+
+  fn option_names(&self) -> Option<&[&str]> {
+    Some(&["style", "timeZone"])
+  }
+
+  fn description(&self) -> Option<&str> {
+    Some("Formats the date of an ISO 8601 date or date and time.")
+  }
+
+  fn option_description(&self, name: &str) -> Option<&str> {
+    match name {
+      "style" => Some(
+        "The date style: `full`, `long`, `medium` (the default), or `short`.",
+      ),
+      _ => option_description(name),
+    }
+  }
+}
+
+/// The `:time` function, which formats the time in the `style` of its option
+/// (`short` by default).
+pub(super) struct TimeFunction;
+
+impl Function for TimeFunction {
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+  ) -> Result<String, FunctionError> {
+    let symbols = DateTimeSymbols::for_locale(&Locale::parse(locale));
+    let style = style_option(options, "style")?.unwrap_or(3);
+    let pattern = style_pattern(symbols, None, Some(style));
+    format_operand(operand, options, symbols, pattern)
+  }
+
+  fn option_names(&self) -> Option<&[&str]> {
+    Some(&["style", "hourCycle", "timeZone"])
+  }
+
+  fn description(&self) -> Option<&str> {
+    Some("Formats the time of an ISO 8601 date and time.")
+  }
+
+  fn option_description(&self, name: &str) -> Option<&str> {
+    match name {
+      "style" => Some(
+        "The time style: `full`, `long`, `medium`, or `short` (the default).",
+      ),
+      _ => option_description(name),
+    }
+  }
+}
+
+fn option_description(name: &str) -> Option<&'static str> {
+  Some(match name {
+    "dateStyle" => "The date style: `full`, `long`, `medium`, or `short`.",
+    "timeStyle" => "The time style: `full`, `long`, `medium`, or `short`.",
+    "weekday" => "The weekday: `long`, `short`, or `narrow`.",
+    "year" => "The year: `numeric` or `2-digit`.",
+    "month" => "The month: `numeric`, `2-digit`, `long`, `short`, or `narrow`.",
+    "day" => "The day: `numeric` or `2-digit`.",
+    "hour" => "The hour: `numeric` or `2-digit`.",
+    "minute" => "The minute: `numeric` or `2-digit`.",
+    "second" => "The second: `numeric` or `2-digit`.",
+    "fractionalSecondDigits" => {
+      "The number of fraction digits of the second, from `1` to `3`."
+    }
+    "timeZoneName" => "The time zone name: `long` or `short`.",
+    "hourCycle" => "The hour cycle: `h11`, `h12`, `h23`, or `h24`.",
+    "timeZone" => {
+      "The time zone to convert to: an IANA time zone like `Europe/Berlin`, \
+       `UTC`, an offset like `+02:00`, or `input` (the offset of the operand, \
+       which is the default)."
+    }
+    _ => return None,
+  })
+}
+
+/// Gets a style option, as the index of the style in [`STYLES`].
+fn style_option(
+  options: &FunctionOptions,
+  name: &str,
+) -> Result<Option<usize>, FunctionError> {
+  Ok(
+    options
+      .get_choice(name, STYLES)?
+      .and_then(|style| STYLES.iter().position(|s| *s == style)),
+  )
+}
+
+/// The pattern for a date style and a time style, given as indices into
+/// [`STYLES`].
+fn style_pattern(
+  symbols: &DateTimeSymbols,
+  date_style: Option<usize>,
+  time_style: Option<usize>,
+) -> Pattern {
+  let date =
+    date_style.map(|style| Pattern::parse(symbols.date_patterns[style]));
+  let time =
+    time_style.map(|style| Pattern::parse(symbols.time_patterns[style]));
+  match (date, time) {
+    (Some(date), Some(time)) => {
+      let glue = if date_style.unwrap() < 2 { 0 } else { 1 };
+      Pattern::combine(symbols.date_time_patterns[glue], date, time)
+    }
+    (Some(pattern), None) | (None, Some(pattern)) => pattern,
+    (None, None) => Pattern::default(),
+  }
+}
+
+/// Builds a pattern from the field options, by removing the fields that are
+/// not used from the date and time patterns of the locale.
+fn field_pattern(
+  symbols: &DateTimeSymbols,
+  options: &FunctionOptions,
+) -> Result<Pattern, FunctionError> {
+  let weekday = options.get_choice("weekday", &["long", "short", "narrow"])?;
+  let mut year = options.get_choice("year", NUMERIC)?;
+  let mut month = options
+    .get_choice("month", &["numeric", "2-digit", "long", "short", "narrow"])?;
+  let mut day = options.get_choice("day", NUMERIC)?;
+  let mut hour = options.get_choice("hour", NUMERIC)?;
+  let mut minute = options.get_choice("minute", NUMERIC)?;
+  let second = options.get_choice("second", NUMERIC)?;
+  let fractional_second_digits =
+    match options.get_integer("fractionalSecondDigits")? {
+      Some(digits @ 1..=3) => Some(digits as usize),
+      Some(_) => return Err(bad_option("fractionalSecondDigits")),
+      None => None,
+    };
+  let time_zone_name =
+    options.get_choice("timeZoneName", &["long", "short"])?;
+
+  let mut has_date =
+    weekday.is_some() || year.is_some() || month.is_some() || day.is_some();
+  let mut has_time = hour.is_some()
+    || minute.is_some()
+    || second.is_some()
+    || fractional_second_digits.is_some();
+  if !has_date && !has_time {
+    (has_date, has_time) = (true, true);
+    year = Some("numeric");
+    month = Some("numeric");
+    day = Some("numeric");
+    hour = Some("numeric");
+    minute = Some("numeric");
+  }
+
+  let width = |option: Option<&str>| match option {
+    Some("2-digit") => 2,
+    Some("short") => 3,
+    Some("long") => 4,
+    Some("narrow") => 5,
+    _ => 1,
+  };
+
+  let textual_month = matches!(month, Some("long" | "short" | "narrow"));
+  let date = has_date.then(|| {
+    let source = if textual_month {
+      symbols.date_patterns[0]
+    } else {
+      symbols.date_patterns[3]
+    };
+    let mut date = Pattern::parse(source);
+    date.retain_fields(|symbol| match symbol {
+      'E' => weekday.is_some(),
+      'y' => year.is_some(),
+      'M' | 'L' => month.is_some(),
+      'd' => day.is_some(),
+      _ => false,
+    });
+    if weekday.is_some() && !date.has_field('E') {
+      date.0.insert(0, Token::Field('E', 1));
+      date.0.insert(1, Token::Literal(", ".to_string()));
+    }
+    date.map_fields(|symbol, _| match symbol {
+      'E' => width(weekday).max(3),
+      'y' => width(year),
+      'M' | 'L' => width(month),
+      'd' => width(day),
+      _ => 1,
+    });
+    date
+  });
+
+  let time = has_time.then(|| {
+    let mut time = Pattern::parse(symbols.time_patterns[2]);
+    time.retain_fields(|symbol| match symbol {
+      'h' | 'H' | 'K' | 'k' | 'a' => hour.is_some(),
+      'm' => minute.is_some(),
+      's' => second.is_some() || fractional_second_digits.is_some(),
+      _ => false,
+    });
+    time.map_fields(|symbol, _| match symbol {
+      'h' | 'H' | 'K' | 'k' => width(hour),
+      'm' if hour.is_none() => width(minute),
+      'm' | 's' => 2,
+      _ => 1,
+    });
+    if let Some(digits) = fractional_second_digits {
+      if let Some(i) = time.position('s') {
+        time.0.insert(i + 1, Token::Literal(".".to_string()));
+        time.0.insert(i + 2, Token::Field('S', digits));
+      }
+    }
+    time
+  });
+
+  let mut pattern = match (date, time) {
+    (Some(date), Some(time)) => {
+      let glue = if month == Some("long") { 0 } else { 1 };
+      Pattern::combine(symbols.date_time_patterns[glue], date, time)
+    }
+    (Some(pattern), None) | (None, Some(pattern)) => pattern,
+    (None, None) => Pattern::default(),
+  };
+  if let Some(name) = time_zone_name {
+    pattern.push_literal(" ");
+    pattern.0.push(Token::Field('z', width(Some(name))));
+  }
+  Ok(pattern)
+}
+
+/// Formats the operand with a pattern, after applying the `hourCycle` and
+/// `timeZone` options.
+fn format_operand(
+  operand: Option<&Value>,
+  options: &FunctionOptions,
+  symbols: &DateTimeSymbols,
+  mut pattern: Pattern,
+) -> Result<String, FunctionError> {
+  let mut date_time = match operand {
+    Some(Value::String(string)) => {
+      DateTime::parse(string).ok_or(FunctionError::BadOperand)?
+    }
+    _ => return Err(FunctionError::BadOperand),
+  };
+
+  match options.get_string("timeZone")? {
+    None | Some("input") => {}
+    Some(time_zone) => {
+      let time_zone =
+        parse_time_zone(time_zone).ok_or_else(|| bad_option("timeZone"))?;
+      date_time = date_time.in_time_zone(&time_zone);
+    }
+  }
+  if date_time.offset.is_none() {
+    pattern.retain_fields(|symbol| symbol != 'z');
+  }
+
+  let hour_cycle =
+    options.get_choice("hourCycle", &["h11", "h12", "h23", "h24"])?;
+  if let Some(hour_cycle) = hour_cycle {
+    pattern.set_hour_cycle(hour_cycle);
+  }
+
+  Ok(pattern.format(&date_time, symbols))
+}
+
+/// Parses a time zone: an IANA time zone like `Europe/Berlin` or `UTC`, or
+/// an offset like `+02:00`.
+fn parse_time_zone(time_zone: &str) -> Option<TimeZone> {
+  TimeZone::for_id(time_zone).or_else(|| {
+    let (offset, rest) = parse_offset(time_zone)?;
+    rest.is_empty().then(|| TimeZone::fixed(offset))
+  })
+}
+
+/// Parses an offset from UTC like `+02:00`, `-0530`, or `Z`, in minutes.
+fn parse_offset(s: &str) -> Option<(i32, &str)> {
+  if let Some(rest) = s.strip_prefix(['Z', 'z']) {
+    return Some((0, rest));
+  }
+  let (sign, rest) = match s.as_bytes().first()? {
+    b'+' => (1, &s[1..]),
+    b'-' => (-1, &s[1..]),
+    _ => return None,
+  };
+  let (hours, rest) = digits(rest, 2)?;
+  let rest = rest.strip_prefix(':').unwrap_or(rest);
+  let (minutes, rest) = digits(rest, 2)?;
+  if hours > 23 || minutes > 59 {
+    return None;
+  }
+  Some((sign * (hours * 60 + minutes) as i32, rest))
+}
+
+/// Parses exactly `len` ASCII digits at the start of `s`.
+pub(super) fn digits(s: &str, len: usize) -> Option<(u32, &str)> {
+  let digits = s.get(..len)?;
+  if !digits.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  Some((digits.parse().ok()?, &s[len..]))
+}
+
+/// A date and time in the proleptic Gregorian calendar.
+#[derive(Debug, Clone, PartialEq)]
+struct DateTime {
+  year: i64,
+  month: u32,
+  day: u32,
+  hour: u32,
+  minute: u32,
+  second: u32,
+  /// The digits of the fraction of the second.
+  fraction: String,
+  /// The offset from UTC in minutes, or `None` for a local (floating) time.
+  offset: Option<i32>,
+}
+
+impl DateTime {
+  /// Parses an ISO 8601 date (`2024-05-01`) or date and time
+  /// (`2024-05-01T14:30`, with optional seconds, fraction of a second, and
+  /// offset).
+  fn parse(s: &str) -> Option<DateTime> {
+    let (year, rest) = digits(s, 4)?;
+    let (month, rest) = digits(rest.strip_prefix('-')?, 2)?;
+    let (day, rest) = digits(rest.strip_prefix('-')?, 2)?;
+    let year = year as i64;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month)
+    {
+      return None;
+    }
+    let mut date_time = DateTime {
+      year,
+      month,
+      day,
+      hour: 0,
+      minute: 0,
+      second: 0,
+      fraction: String::new(),
+      offset: None,
+    };
+    let Some(rest) = rest.strip_prefix(['T', 't']) else {
+      return rest.is_empty().then_some(date_time);
+    };
+
+    let (hour, rest) = digits(rest, 2)?;
+    let (minute, mut rest) = digits(rest.strip_prefix(':')?, 2)?;
+    if hour > 23 || minute > 59 {
+      return None;
+    }
+    date_time.hour = hour;
+    date_time.minute = minute;
+    if let Some(after_colon) = rest.strip_prefix(':') {
+      let (second, after_second) = digits(after_colon, 2)?;
+      if second > 59 {
+        return None;
+      }
+      date_time.second = second;
+      rest = after_second;
+      if let Some(after_dot) = rest.strip_prefix('.') {
+        let len = after_dot.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+          return None;
+        }
+        date_time.fraction = after_dot[..len].to_string();
+        rest = &after_dot[len..];
+      }
+    }
+    if !rest.is_empty() {
+      let (offset, rest) = parse_offset(rest)?;
+      if !rest.is_empty() {
+        return None;
+      }
+      date_time.offset = Some(offset);
+    }
+    Some(date_time)
+  }
+
+  /// Converts the date and time to a time zone. Local times are taken to be
+  /// in that time zone already.
+  fn in_time_zone(self, time_zone: &TimeZone) -> DateTime {
+    let Some(current) = self.offset else {
+      let offset = time_zone.offset_at_local(self.local_minutes());
+      return DateTime {
+        offset: Some(offset),
+        ..self
+      }
+      .in_time_zone(time_zone);
+    };
+    let offset = time_zone.offset_at(self.local_minutes() - current as i64);
+    self.with_offset(offset)
+  }
+
+  /// Converts the date and time to another offset from UTC. Local times are
+  /// taken to be in that offset already.
+  fn with_offset(self, offset: i32) -> DateTime {
+    let Some(current) = self.offset else {
+      return DateTime {
+        offset: Some(offset),
+        ..self
+      };
+    };
+    let minutes = self.local_minutes() + (offset - current) as i64;
+    let (year, month, day) = civil_from_days(minutes.div_euclid(24 * 60));
+    let minute_of_day = minutes.rem_euclid(24 * 60) as u32;
+    DateTime {
+      year,
+      month,
+      day,
+      hour: minute_of_day / 60,
+      minute: minute_of_day % 60,
+      offset: Some(offset),
+      ..self
+    }
+  }
+
+  /// The minutes since 1970-01-01T00:00 in the offset of the date and time.
+  fn local_minutes(&self) -> i64 {
+    days_from_civil(self.year, self.month, self.day) * 24 * 60
+      + (self.hour * 60 + self.minute) as i64
+  }
+
+  /// The day of the week, from 0 (Sunday) to 6 (Saturday).
+  fn weekday(&self) -> usize {
+    // 1970-01-01 was a Thursday.
+    (days_from_civil(self.year, self.month, self.day) + 4).rem_euclid(7)
+      as usize
+  }
+}
+
+pub(super) fn is_leap_year(year: i64) -> bool {
+  year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+pub(super) fn days_in_month(year: i64, month: u32) -> u32 {
+  match month {
+    2 if is_leap_year(year) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+/// The number of days since 1970-01-01, following
+/// <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+pub(super) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year.rem_euclid(400);
+  let month = month as i64;
+  let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2)
+    / 5
+    + day as i64
+    - 1;
+  let day_of_era =
+    year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146097 + day_of_era - 719468
+}
+
+/// The inverse of [`days_from_civil`].
+pub(super) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let days = days + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days.rem_euclid(146097);
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+    - day_of_era / 146096)
+    / 365;
+  let day_of_year =
+    day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  } as u32;
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  /// A date field symbol, repeated the given number of times, like `MMM`.
+  Field(char, usize),
+  Literal(String),
+}
+
+/// A CLDR date pattern, like `MMM d, y`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Pattern(Vec<Token>);
+
+impl Pattern {
+  /// Parses a pattern. Letters are field symbols, and text in single quotes
+  /// is literal, where `''` is a single quote.
+  fn parse(source: &str) -> Pattern {
+    let mut pattern = Pattern::default();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+      if c.is_ascii_alphabetic() {
+        let mut count = 1;
+        while chars.next_if_eq(&c).is_some() {
+          count += 1;
+        }
+        pattern.0.push(Token::Field(c, count));
+      } else if c == '\'' {
+        if chars.next_if_eq(&'\'').is_some() {
+          pattern.push_literal("'");
+          continue;
+        }
+        let mut literal = String::new();
+        while let Some(c) = chars.next() {
+          if c != '\'' {
+            literal.push(c);
+          } else if chars.next_if_eq(&'\'').is_some() {
+            literal.push('\'');
+          } else {
+            break;
+          }
+        }
+        pattern.push_literal(&literal);
+      } else {
+        pattern.push_literal(c.encode_utf8(&mut [0; 4]));
+      }
+    }
+    pattern
+  }
+
+  /// Combines a date and a time pattern with a pattern like `{1}, {0}`,
+  /// where `{1}` is the date and `{0}` is the time.
+  fn combine(glue: &str, date: Pattern, time: Pattern) -> Pattern {
+    let mut pattern = Pattern::default();
+    let mut rest = glue;
+    while !rest.is_empty() {
+      let (text, placeholder, after) = match rest.find('{') {
+        Some(i) if rest[i..].starts_with("{0}") => {
+          (&rest[..i], Some(&time), &rest[i + 3..])
+        }
+        Some(i) if rest[i..].starts_with("{1}") => {
+          (&rest[..i], Some(&date), &rest[i + 3..])
+        }
+        _ => (rest, None, ""),
+      };
+      pattern.extend(Pattern::parse(text));
+      if let Some(placeholder) = placeholder {
+        pattern.extend(placeholder.clone());
+      }
+      rest = after;
+    }
+    pattern
+  }
+
+  fn extend(&mut self, other: Pattern) {
+    for token in other.0 {
+      match token {
+        Token::Literal(literal) => self.push_literal(&literal),
+        field => self.0.push(field),
+      }
+    }
+  }
+
+  fn push_literal(&mut self, text: &str) {
+    match self.0.last_mut() {
+      Some(Token::Literal(literal)) => literal.push_str(text),
+      _ => self.0.push(Token::Literal(text.to_string())),
+    }
+  }
+
+  fn position(&self, symbol: char) -> Option<usize> {
+    self
+      .0
+      .iter()
+      .position(|token| matches!(token, Token::Field(c, _) if *c == symbol))
+  }
+
+  fn has_field(&self, symbol: char) -> bool {
+    self.position(symbol).is_some()
+  }
+
+  /// Removes the fields for which `keep` returns false, together with the
+  /// literal text that separates them from the other fields. Time fields are
+  /// preceded by their separator, and date fields are followed by it. For
+  /// example, removing the day from `MMMM d, y` gives `MMMM y`, and removing
+  /// the seconds from `h:mm:ss a` gives `h:mm a`.
+  fn retain_fields(&mut self, keep: impl Fn(char) -> bool) {
+    let is_field = |token: &Token| matches!(token, Token::Field(..));
+    let is_literal = |token: &Token| matches!(token, Token::Literal(_));
+    while let Some((i, symbol)) =
+      self
+        .0
+        .iter()
+        .enumerate()
+        .find_map(|(i, token)| match token {
+          Token::Field(symbol, _) if !keep(*symbol) => Some((i, *symbol)),
+          _ => None,
+        })
+    {
+      let has_field_before = self.0[..i].iter().any(is_field);
+      let has_field_after = self.0[i + 1..].iter().any(is_field);
+      let is_time = matches!(symbol, 'h' | 'H' | 'K' | 'k' | 'm' | 's' | 'S');
+      let literal_before = has_field_before && is_literal(&self.0[i - 1]);
+      let literal_after = has_field_after && is_literal(&self.0[i + 1]);
+      let range = match (has_field_before, has_field_after) {
+        // The first field also takes the literals before it.
+        (false, true) => 0..i + 1 + literal_after as usize,
+        // The last field also takes the literals after it.
+        (true, false) => i - literal_before as usize..self.0.len(),
+        (true, true) if is_time && literal_before => i - 1..i + 1,
+        (true, true) => i..i + 1 + literal_after as usize,
+        (false, false) => 0..self.0.len(),
+      };
+      self.0.drain(range);
+    }
+  }
+
+  /// Sets the width of each field to the result of `width`.
+  fn map_fields(&mut self, width: impl Fn(char, usize) -> usize) {
+    for token in &mut self.0 {
+      if let Token::Field(symbol, count) = token {
+        *count = width(*symbol, *count);
+      }
+    }
+  }
+
+  /// Uses the hour symbol of an hour cycle, and adds or removes the day
+  /// period (AM or PM) to match it.
+  fn set_hour_cycle(&mut self, hour_cycle: &str) {
+    let hour_symbol = match hour_cycle {
+      "h11" => 'K',
+      "h12" => 'h',
+      "h23" => 'H',
+      _ => 'k',
+    };
+    let mut last_time_field = None;
+    for (i, token) in self.0.iter_mut().enumerate() {
+      if let Token::Field(symbol, _) = token {
+        if matches!(symbol, 'h' | 'H' | 'K' | 'k') {
+          *symbol = hour_symbol;
+          last_time_field = Some(i);
+        } else if matches!(symbol, 'm' | 's' | 'S') {
+          last_time_field = Some(i);
+        }
+      }
+    }
+    let Some(last_time_field) = last_time_field else {
+      return;
+    };
+    if matches!(hour_symbol, 'H' | 'k') {
+      self.retain_fields(|symbol| symbol != 'a');
+    } else if !self.has_field('a') {
+      self
+        .0
+        .insert(last_time_field + 1, Token::Literal(" ".to_string()));
+      self.0.insert(last_time_field + 2, Token::Field('a', 1));
+    }
+  }
+
+  fn format(&self, date_time: &DateTime, symbols: &DateTimeSymbols) -> String {
+    let mut out = String::new();
+    for token in &self.0 {
+      let (symbol, count) = match token {
+        Token::Literal(literal) => {
+          out.push_str(literal);
+          continue;
+        }
+        Token::Field(symbol, count) => (*symbol, *count),
+      };
+      let month = date_time.month as usize - 1;
+      let hour = date_time.hour;
+      let number = |value: i64| format!("{value:0count$}");
+      let field = match symbol {
+        'y' if count == 2 => format!("{:02}", date_time.year.rem_euclid(100)),
+        'y' => number(date_time.year),
+        'M' | 'L' => match count {
+          1 | 2 => number(date_time.month as i64),
+          3 => symbols.months_abbreviated[month].to_string(),
+          4 => symbols.months[month].to_string(),
+          _ => symbols.months[month]
+            .chars()
+            .take(1)
+            .flat_map(char::to_uppercase)
+            .collect(),
+        },
+        'd' => number(date_time.day as i64),
+        'E' => match count {
+          1..=3 => symbols.weekdays_abbreviated[date_time.weekday()],
+          4 => symbols.weekdays[date_time.weekday()],
+          _ => symbols.weekdays_narrow[date_time.weekday()],
+        }
+        .to_string(),
+        'a' => symbols.am_pm[(hour >= 12) as usize].to_string(),
+        'h' => number(if hour % 12 == 0 { 12 } else { hour % 12 } as i64),
+        'H' => number(hour as i64),
+        'K' => number((hour % 12) as i64),
+        'k' => number(if hour == 0 { 24 } else { hour } as i64),
+        'm' => number(date_time.minute as i64),
+        's' => number(date_time.second as i64),
+        'S' => format!("{:0<count$.count$}", date_time.fraction),
+        'z' => time_zone_name(date_time.offset.unwrap_or(0), count >= 4),
+        _ => symbol.to_string().repeat(count),
+      };
+      out.push_str(&field);
+    }
+    out
+  }
+}
+
+/// The localized GMT format of an offset, like `GMT+2` (short) or
+/// `GMT+02:00` (long).
+fn time_zone_name(offset: i32, long: bool) -> String {
+  if offset == 0 {
+    return "GMT".to_string();
+  }
+  let sign = if offset < 0 { '-' } else { '+' };
+  let (hours, minutes) = (offset.abs() / 60, offset.abs() % 60);
+  if long {
+    format!("GMT{sign}{hours:02}:{minutes:02}")
+  } else if minutes == 0 {
+    format!("GMT{sign}{hours}")
+  } else {
+    format!("GMT{sign}{hours}:{minutes:02}")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::civil_from_days;
+  use super::days_from_civil;
+  use super::DateTime;
+  use super::Pattern;
+
+  fn retain(source: &str, symbols: &str) -> Pattern {
+    let mut pattern = Pattern::parse(source);
+    pattern.retain_fields(|symbol| symbols.contains(symbol));
+    pattern
+  }
+
+  #[test]
+  fn converts_between_days_and_dates() {
+    assert_eq!(days_from_civil(1970, 1, 1), 0);
+    assert_eq!(days_from_civil(2000, 3, 1), 11017);
+    for days in [-800_000, -1, 0, 59, 11016, 19_843, 2_932_896] {
+      let (year, month, day) = civil_from_days(days);
+      assert_eq!(days_from_civil(year, month, day), days);
+    }
+  }
+
+  #[test]
+  fn parses_iso_8601() {
+    let date_time = DateTime::parse("2024-02-29T23:30:15.25-05:30").unwrap();
+    assert_eq!(
+      (
+        date_time.hour,
+        date_time.second,
+        date_time.fraction.as_str()
+      ),
+      (23, 15, "25")
+    );
+    assert_eq!(date_time.offset, Some(-330));
+    let converted = date_time.with_offset(0);
+    assert_eq!((converted.month, converted.day), (3, 1));
+    assert_eq!((converted.hour, converted.minute), (5, 0));
+    for invalid in [
+      "2023-02-29",
+      "2024-13-01",
+      "2024-1-01",
+      "2024-01-01T",
+      "2024-01-01T24:00",
+      "2024-01-01T10:00:00.",
+      "2024-01-01T10:00+25:00",
+      "2024-01-01 10:00",
+    ] {
+      assert_eq!(DateTime::parse(invalid), None, "{invalid}");
+    }
+  }
+
+  #[test]
+  fn removes_fields_with_their_separators() {
+    assert_eq!(retain("EEEE, MMMM d, y", "Md"), Pattern::parse("MMMM d"));
+    assert_eq!(retain("EEEE, MMMM d, y", "My"), Pattern::parse("MMMM y"));
+    assert_eq!(
+      retain("d 'de' MMMM 'de' y", "My"),
+      Pattern::parse("MMMM 'de' y")
+    );
+    assert_eq!(retain("H:mm:ss (zzzz)", "Hms"), Pattern::parse("H:mm:ss"));
+    assert_eq!(retain("h:mm:ss a", "hma"), Pattern::parse("h:mm a"));
+    assert_eq!(retain("h:mm:ss a", "hsa"), Pattern::parse("h:ss a"));
+  }
+}
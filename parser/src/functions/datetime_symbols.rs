@@ -0,0 +1,324 @@
+//! The CLDR date and time symbols and patterns of common languages, for the
+//! Gregorian calendar. Other languages use the data of the CLDR root locale,
+//! which is locale-neutral: its patterns are ISO-style, like `y-MM-dd` and
+//! `HH:mm`, and its months are numbered, like `M05`.
+//!
+//! Patterns use the CLDR date field symbols, like `MMM d, y`. See
+//! <https://unicode.org/reports/tr35/tr35-dates.html#Date_Field_Symbol_Table>.
+
+use super::Locale;
+
+pub(super) struct DateTimeSymbols {
+  pub months: [&'static str; 12],
+  pub months_abbreviated: [&'static str; 12],
+  /// From Sunday to Saturday.
+  pub weekdays: [&'static str; 7],
+  pub weekdays_abbreviated: [&'static str; 7],
+  pub weekdays_narrow: [&'static str; 7],
+  pub am_pm: [&'static str; 2],
+  /// The date patterns of the `full`, `long`, `medium`, and `short` styles.
+  pub date_patterns: [&'static str; 4],
+  /// The time patterns of the `full`, `long`, `medium`, and `short` styles.
+  pub time_patterns: [&'static str; 4],
+  /// The patterns that combine a date (`{1}`) and a time (`{0}`), for dates
+  /// in the `full` or `long` style, and for other dates.
+  pub date_time_patterns: [&'static str; 2],
+}
+
+impl DateTimeSymbols {
+  pub fn for_locale(locale: &Locale) -> &'static DateTimeSymbols {
+    match locale.language.as_str() {
+      "de" => &DE,
+      "es" => &ES,
+      "fr" => &FR,
+      "it" => &IT,
+      "nl" => &NL,
+      "pt" => &PT,
+      "en" => &EN,
+      _ => &ROOT,
+    }
+  }
+}
+
+const ROOT: DateTimeSymbols = DateTimeSymbols {
+  months: [
+    "M01", "M02", "M03", "M04", "M05", "M06", "M07", "M08", "M09", "M10",
+    "M11", "M12",
+  ],
+  months_abbreviated: [
+    "M01", "M02", "M03", "M04", "M05", "M06", "M07", "M08", "M09", "M10",
+    "M11", "M12",
+  ],
+  weekdays: ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+  weekdays_abbreviated: ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+  weekdays_narrow: ["S", "M", "T", "W", "T", "F", "S"],
+  am_pm: ["AM", "PM"],
+  date_patterns: ["y MMMM d, EEEE", "y MMMM d", "y MMM d", "y-MM-dd"],
+  time_patterns: ["HH:mm:ss zzzz", "HH:mm:ss z", "HH:mm:ss", "HH:mm"],
+  date_time_patterns: ["{1} {0}", "{1} {0}"],
+};
+
+const EN: DateTimeSymbols = DateTimeSymbols {
+  months: [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+  ],
+  months_abbreviated: [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct",
+    "Nov", "Dec",
+  ],
+  weekdays: [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+  ],
+  weekdays_abbreviated: ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+  weekdays_narrow: ["S", "M", "T", "W", "T", "F", "S"],
+  am_pm: ["AM", "PM"],
+  date_patterns: ["EEEE, MMMM d, y", "MMMM d, y", "MMM d, y", "M/d/yy"],
+  time_patterns: [
+    "h:mm:ss\u{202F}a zzzz",
+    "h:mm:ss\u{202F}a z",
+    "h:mm:ss\u{202F}a",
+    "h:mm\u{202F}a",
+  ],
+  date_time_patterns: ["{1} 'at' {0}", "{1}, {0}"],
+};
+
+const DE: DateTimeSymbols = DateTimeSymbols {
+  months: [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+  ],
+  months_abbreviated: [
+    "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.",
+    "Okt.", "Nov.", "Dez.",
+  ],
+  weekdays: [
+    "Sonntag",
+    "Montag",
+    "Dienstag",
+    "Mittwoch",
+    "Donnerstag",
+    "Freitag",
+    "Samstag",
+  ],
+  weekdays_abbreviated: ["So.", "Mo.", "Di.", "Mi.", "Do.", "Fr.", "Sa."],
+  weekdays_narrow: ["S", "M", "D", "M", "D", "F", "S"],
+  am_pm: ["AM", "PM"],
+  date_patterns: ["EEEE, d. MMMM y", "d. MMMM y", "dd.MM.y", "dd.MM.yy"],
+  time_patterns: ["HH:mm:ss zzzz", "HH:mm:ss z", "HH:mm:ss", "HH:mm"],
+  date_time_patterns: ["{1} 'um' {0}", "{1}, {0}"],
+};
+
+const ES: DateTimeSymbols = DateTimeSymbols {
+  months: [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+  ],
+  months_abbreviated: [
+    "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct",
+    "nov", "dic",
+  ],
+  weekdays: [
+    "domingo",
+    "lunes",
+    "martes",
+    "miércoles",
+    "jueves",
+    "viernes",
+    "sábado",
+  ],
+  weekdays_abbreviated: ["dom", "lun", "mar", "mié", "jue", "vie", "sáb"],
+  weekdays_narrow: ["D", "L", "M", "X", "J", "V", "S"],
+  am_pm: ["a.\u{A0}m.", "p.\u{A0}m."],
+  date_patterns: [
+    "EEEE, d 'de' MMMM 'de' y",
+    "d 'de' MMMM 'de' y",
+    "d MMM y",
+    "d/M/yy",
+  ],
+  time_patterns: ["H:mm:ss (zzzz)", "H:mm:ss z", "H:mm:ss", "H:mm"],
+  date_time_patterns: ["{1}, {0}", "{1}, {0}"],
+};
+
+const FR: DateTimeSymbols = DateTimeSymbols {
+  months: [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+  ],
+  months_abbreviated: [
+    "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.",
+    "oct.", "nov.", "déc.",
+  ],
+  weekdays: [
+    "dimanche", "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi",
+  ],
+  weekdays_abbreviated: [
+    "dim.", "lun.", "mar.", "mer.", "jeu.", "ven.", "sam.",
+  ],
+  weekdays_narrow: ["D", "L", "M", "M", "J", "V", "S"],
+  am_pm: ["AM", "PM"],
+  date_patterns: ["EEEE d MMMM y", "d MMMM y", "d MMM y", "dd/MM/y"],
+  time_patterns: ["HH:mm:ss zzzz", "HH:mm:ss z", "HH:mm:ss", "HH:mm"],
+  date_time_patterns: ["{1} 'à' {0}", "{1} {0}"],
+};
+
+const IT: DateTimeSymbols = DateTimeSymbols {
+  months: [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+  ],
+  months_abbreviated: [
+    "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott",
+    "nov", "dic",
+  ],
+  weekdays: [
+    "domenica",
+    "lunedì",
+    "martedì",
+    "mercoledì",
+    "giovedì",
+    "venerdì",
+    "sabato",
+  ],
+  weekdays_abbreviated: ["dom", "lun", "mar", "mer", "gio", "ven", "sab"],
+  weekdays_narrow: ["D", "L", "M", "M", "G", "V", "S"],
+  am_pm: ["AM", "PM"],
+  date_patterns: ["EEEE d MMMM y", "d MMMM y", "d MMM y", "dd/MM/yy"],
+  time_patterns: ["HH:mm:ss zzzz", "HH:mm:ss z", "HH:mm:ss", "HH:mm"],
+  date_time_patterns: ["{1} {0}", "{1}, {0}"],
+};
+
+const NL: DateTimeSymbols = DateTimeSymbols {
+  months: [
+    "januari",
+    "februari",
+    "maart",
+    "april",
+    "mei",
+    "juni",
+    "juli",
+    "augustus",
+    "september",
+    "oktober",
+    "november",
+    "december",
+  ],
+  months_abbreviated: [
+    "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt",
+    "nov", "dec",
+  ],
+  weekdays: [
+    "zondag",
+    "maandag",
+    "dinsdag",
+    "woensdag",
+    "donderdag",
+    "vrijdag",
+    "zaterdag",
+  ],
+  weekdays_abbreviated: ["zo", "ma", "di", "wo", "do", "vr", "za"],
+  weekdays_narrow: ["Z", "M", "D", "W", "D", "V", "Z"],
+  am_pm: ["a.m.", "p.m."],
+  date_patterns: ["EEEE d MMMM y", "d MMMM y", "d MMM y", "dd-MM-y"],
+  time_patterns: ["HH:mm:ss zzzz", "HH:mm:ss z", "HH:mm:ss", "HH:mm"],
+  date_time_patterns: ["{1} 'om' {0}", "{1} {0}"],
+};
+
+const PT: DateTimeSymbols = DateTimeSymbols {
+  months: [
+    "janeiro",
+    "fevereiro",
+    "março",
+    "abril",
+    "maio",
+    "junho",
+    "julho",
+    "agosto",
+    "setembro",
+    "outubro",
+    "novembro",
+    "dezembro",
+  ],
+  months_abbreviated: [
+    "jan.", "fev.", "mar.", "abr.", "mai.", "jun.", "jul.", "ago.", "set.",
+    "out.", "nov.", "dez.",
+  ],
+  weekdays: [
+    "domingo",
+    "segunda-feira",
+    "terça-feira",
+    "quarta-feira",
+    "quinta-feira",
+    "sexta-feira",
+    "sábado",
+  ],
+  weekdays_abbreviated: [
+    "dom.", "seg.", "ter.", "qua.", "qui.", "sex.", "sáb.",
+  ],
+  weekdays_narrow: ["D", "S", "T", "Q", "Q", "S", "S"],
+  am_pm: ["AM", "PM"],
+  date_patterns: [
+    "EEEE, d 'de' MMMM 'de' y",
+    "d 'de' MMMM 'de' y",
+    "d 'de' MMM 'de' y",
+    "dd/MM/y",
+  ],
+  time_patterns: ["HH:mm:ss zzzz", "HH:mm:ss z", "HH:mm:ss", "HH:mm"],
+  date_time_patterns: ["{1} {0}", "{1} {0}"],
+};
@@ -0,0 +1,583 @@
+//! The `:number` and `:integer` functions, which format numbers and select
+//! by their plural category.
+//!
+//! Numbers are formatted as decimals without losing precision, so that number
+//! literals like `{12345678901234567890 :number}` are formatted exactly.
+//! Rounding follows ECMA-402: numbers are rounded half away from zero.
+
+use std::fmt;
+
+use super::bad_option;
+use super::number_symbols_data::NUMBER_SYMBOLS;
+use super::plural;
+use super::plural::PluralOperands;
+use super::Function;
+use super::FunctionError;
+use super::FunctionOptions;
+use super::Locale;
+use crate::format::Value;
+
+/// The largest value of the digit size options, like `maximumFractionDigits`.
+const MAX_DIGITS: u32 = 20;
+
+/// The largest absolute value of the exponent of a number literal, which keeps
+/// the digits of numbers like `1e1000` from being written out.
+const MAX_EXPONENT: i32 = MAX_DIGITS as i32;
+
+/// The `:number` function.
+pub(super) struct NumberFunction;
+
+impl Function for NumberFunction {
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+  ) -> Result<String, FunctionError> {
+    let options = NumberOptions::resolve(options, false)?;
+    let number = options.round(Decimal::from_operand(operand)?);
+    Ok(options.format(&number, &Locale::parse(locale)))
+  }
+
+  fn select(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+    keys: &[&str],
+  ) -> Result<Vec<usize>, FunctionError> {
+    let options = NumberOptions::resolve(options, false)?;
+    let number = options.round(Decimal::from_operand(operand)?);
+    Ok(options.select(&number, &Locale::parse(locale), keys))
+  }
+
+  fn option_names(&self) -> Option<&[&str]> {
+    Some(&[
+      "select",
+      "minimumIntegerDigits",
+      "minimumFractionDigits",
+      "maximumFractionDigits",
+      "signDisplay",
+      "useGrouping",
+    ])
+  }
+
+  fn description(&self) -> Option<&str> {
+    Some(
+      "Formats a number. Selects the variant whose key is equal to the \
+       number, or otherwise the key of its plural category.",
+    )
+  }
+
+  fn option_description(&self, name: &str) -> Option<&str> {
+    option_description(name)
+  }
+}
+
+/// The `:integer` function, which is like `:number` without fraction digits.
+/// The operand is truncated to an integer.
+pub(super) struct IntegerFunction;
+
+impl Function for IntegerFunction {
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+  ) -> Result<String, FunctionError> {
+    let options = NumberOptions::resolve(options, true)?;
+    let number = Decimal::from_operand(operand)?.truncate();
+    Ok(options.format(&number, &Locale::parse(locale)))
+  }
+
+  fn select(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    locale: &str,
+    keys: &[&str],
+  ) -> Result<Vec<usize>, FunctionError> {
+    let options = NumberOptions::resolve(options, true)?;
+    let number = Decimal::from_operand(operand)?.truncate();
+    Ok(options.select(&number, &Locale::parse(locale), keys))
+  }
+
+  fn option_names(&self) -> Option<&[&str]> {
+    Some(&[
+      "select",
+      "minimumIntegerDigits",
+      "signDisplay",
+      "useGrouping",
+    ])
+  }
+
+  fn description(&self) -> Option<&str> {
+    Some(
+      "Formats a number without its fraction digits. Selects the variant \
+       whose key is equal to the integer, or otherwise the key of its plural \
+       category.",
+    )
+  }
+
+  fn option_description(&self, name: &str) -> Option<&str> {
+    option_description(name)
+  }
+}
+
+fn option_description(name: &str) -> Option<&'static str> {
+  Some(match name {
+    "select" => {
+      "How variants are selected: by `plural` category (the default), by \
+       `ordinal` category, or only by `exact` value."
+    }
+    "minimumIntegerDigits" => {
+      "The minimum number of integer digits, padded with zeros."
+    }
+    "minimumFractionDigits" => {
+      "The minimum number of fraction digits, padded with zeros."
+    }
+    "maximumFractionDigits" => {
+      "The maximum number of fraction digits, rounded half away from zero \
+       (`3` by default)."
+    }
+    "signDisplay" => {
+      "When the sign is displayed: `auto` (the default), `always`, \
+       `exceptZero`, `negative`, or `never`."
+    }
+    "useGrouping" => {
+      "When grouping separators are used: `auto` (the default), `always`, \
+       `min2`, or `never`."
+    }
+    _ => return None,
+  })
+}
+
+/// The resolved options of `:number` and `:integer`.
+struct NumberOptions {
+  /// How the number is selected: `plural`, `ordinal`, or `exact`.
+  select: &'static str,
+  minimum_integer_digits: usize,
+  minimum_fraction_digits: usize,
+  maximum_fraction_digits: usize,
+  /// When the sign is displayed: `auto`, `always`, `exceptZero`, `negative`,
+  /// or `never`.
+  sign_display: &'static str,
+  /// When grouping separators are used: `auto`, `always`, `min2`, or
+  /// `never`.
+  use_grouping: &'static str,
+}
+
+impl NumberOptions {
+  fn resolve(
+    options: &FunctionOptions,
+    integer: bool,
+  ) -> Result<NumberOptions, FunctionError> {
+    let select = options
+      .get_choice("select", &["plural", "ordinal", "exact"])?
+      .unwrap_or("plural");
+    let minimum_integer_digits =
+      digits_option(options, "minimumIntegerDigits", 1)?.unwrap_or(1);
+    let (minimum_fraction_digits, maximum_fraction_digits) = if integer {
+      (0, 0)
+    } else {
+      let minimum = digits_option(options, "minimumFractionDigits", 0)?;
+      let maximum = digits_option(options, "maximumFractionDigits", 0)?;
+      match (minimum, maximum) {
+        (Some(minimum), Some(maximum)) if minimum > maximum => {
+          return Err(bad_option("maximumFractionDigits"));
+        }
+        (minimum, maximum) => {
+          let minimum = minimum.unwrap_or(0);
+          (minimum, maximum.unwrap_or(minimum.max(3)))
+        }
+      }
+    };
+    let sign_display = options
+      .get_choice(
+        "signDisplay",
+        &["auto", "always", "exceptZero", "negative", "never"],
+      )?
+      .unwrap_or("auto");
+    let use_grouping = options
+      .get_choice("useGrouping", &["auto", "always", "min2", "never"])?
+      .unwrap_or("auto");
+    Ok(NumberOptions {
+      select,
+      minimum_integer_digits,
+      minimum_fraction_digits,
+      maximum_fraction_digits,
+      sign_display,
+      use_grouping,
+    })
+  }
+
+  fn round(&self, number: Decimal) -> Decimal {
+    number.round(self.maximum_fraction_digits)
+  }
+
+  fn format(&self, number: &Decimal, locale: &Locale) -> String {
+    let symbols = Symbols::for_locale(locale);
+    let mut out = String::new();
+
+    let negative = number.negative;
+    let zero = number.is_zero();
+    let sign = match self.sign_display {
+      "always" => Some(if negative { '-' } else { '+' }),
+      "exceptZero" if zero => None,
+      "exceptZero" => Some(if negative { '-' } else { '+' }),
+      "negative" => (negative && !zero).then_some('-'),
+      "never" => None,
+      _ => negative.then_some('-'),
+    };
+    out.extend(sign);
+
+    let padding = self
+      .minimum_integer_digits
+      .saturating_sub(number.integer.len());
+    let integer = "0".repeat(padding) + &number.integer;
+    let minimum_grouping = match self.use_grouping {
+      "never" => None,
+      "always" => Some(1),
+      "min2" => Some(2),
+      _ => Some(symbols.minimum_grouping),
+    };
+    match minimum_grouping {
+      Some(minimum) if integer.len() >= 3 + minimum => {
+        for (i, digit) in integer.chars().enumerate() {
+          if i != 0 && (integer.len() - i) % 3 == 0 {
+            out.push_str(symbols.group);
+          }
+          out.push(digit);
+        }
+      }
+      _ => out.push_str(&integer),
+    }
+
+    let fraction = self.pad_fraction(number);
+    if !fraction.is_empty() {
+      out.push_str(symbols.decimal);
+      out.push_str(&fraction);
+    }
+    out
+  }
+
+  /// The fraction digits of a rounded number, padded with zeros to the
+  /// minimum number of fraction digits.
+  fn pad_fraction(&self, number: &Decimal) -> String {
+    let padding = self
+      .minimum_fraction_digits
+      .saturating_sub(number.fraction.len());
+    number.fraction.clone() + &"0".repeat(padding)
+  }
+
+  /// Returns the indices of the keys that match a rounded number. Number
+  /// literal keys that are numerically equal to the number (so `1` matches
+  /// `1.0`) are preferred over the key of its plural category.
+  fn select(
+    &self,
+    number: &Decimal,
+    locale: &Locale,
+    keys: &[&str],
+  ) -> Vec<usize> {
+    let mut matches = keys
+      .iter()
+      .enumerate()
+      .filter(|(_, key)| Decimal::parse(key).is_some_and(|key| key == *number))
+      .map(|(i, _)| i)
+      .collect::<Vec<_>>();
+
+    let operands = self.plural_operands(number);
+    let category = match self.select {
+      "plural" => plural::cardinal(locale, operands),
+      "ordinal" => plural::ordinal(locale, operands),
+      _ => return matches,
+    };
+    matches.extend(keys.iter().position(|key| *key == category));
+    matches
+  }
+
+  fn plural_operands(&self, number: &Decimal) -> PluralOperands {
+    PluralOperands::new(&number.integer, &self.pad_fraction(number))
+  }
+}
+
+/// Gets a digit size option, which must be an integer between `minimum` and
+/// [`MAX_DIGITS`].
+fn digits_option(
+  options: &FunctionOptions,
+  name: &str,
+  minimum: u32,
+) -> Result<Option<usize>, FunctionError> {
+  match options.get_integer(name)? {
+    Some(value) if !(minimum..=MAX_DIGITS).contains(&value) => {
+      Err(bad_option(name))
+    }
+    value => Ok(value.map(|value| value as usize)),
+  }
+}
+
+/// The symbols used to format numbers in a locale.
+struct Symbols {
+  decimal: &'static str,
+  group: &'static str,
+  /// The number of digits that must come before the first grouping separator
+  /// for grouping to be used with `useGrouping=auto`. For example, Spanish
+  /// formats `1000` without, but `10.000` with a grouping separator.
+  minimum_grouping: usize,
+}
+
+impl Symbols {
+  /// Looks up the CLDR symbols of a locale, falling back from the language
+  /// and region to the language, like `pt-PT` to `pt`.
+  fn for_locale(locale: &Locale) -> Symbols {
+    let find = |id: &str| {
+      NUMBER_SYMBOLS
+        .binary_search_by(|(locale, ..)| (*locale).cmp(id))
+        .ok()
+        .map(|index| NUMBER_SYMBOLS[index])
+    };
+    let (_, decimal, group, minimum_grouping) = locale
+      .region
+      .as_ref()
+      .and_then(|region| find(&format!("{}-{}", locale.language, region)))
+      .or_else(|| find(&locale.language))
+      .unwrap_or(("", ".", ",", 1));
+    Symbols {
+      decimal,
+      group,
+      minimum_grouping,
+    }
+  }
+}
+
+/// A decimal number, as its digits.
+#[derive(Debug, Clone)]
+struct Decimal {
+  negative: bool,
+  /// The digits before the decimal point, without leading zeros. Zero has no
+  /// integer digits.
+  integer: String,
+  /// The digits after the decimal point, without trailing zeros.
+  fraction: String,
+}
+
+impl Decimal {
+  /// Converts an operand to a number. Strings must be number literals, like
+  /// `-1.5e3`.
+  fn from_operand(operand: Option<&Value>) -> Result<Decimal, FunctionError> {
+    match operand {
+      Some(Value::Number(number)) if number.is_finite() => {
+        // Floats are displayed as their shortest representation that
+        // round-trips, and never in exponential notation.
+        Decimal::parse(&number.to_string()).ok_or(FunctionError::BadOperand)
+      }
+      Some(Value::String(string)) => {
+        Decimal::parse(string).ok_or(FunctionError::BadOperand)
+      }
+      _ => Err(FunctionError::BadOperand),
+    }
+  }
+
+  /// Parses a number following the `number-literal` production of the
+  /// syntax. Numbers with an exponent beyond [`MAX_EXPONENT`] are rejected.
+  fn parse(s: &str) -> Option<Decimal> {
+    fn is_digits(s: &str) -> bool {
+      !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    let (negative, s) = match s.strip_prefix('-') {
+      Some(s) => (true, s),
+      None => (false, s),
+    };
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+      Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+      None => (s, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+      Some((integer, fraction)) => (integer, fraction),
+      None => (mantissa, ""),
+    };
+    if !is_digits(integer)
+      || (integer.len() > 1 && integer.starts_with('0'))
+      || (mantissa.contains('.') && !is_digits(fraction))
+    {
+      return None;
+    }
+    let exponent = match exponent {
+      None => 0,
+      Some(exponent) => {
+        let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        if !is_digits(digits) {
+          return None;
+        }
+        exponent
+          .parse::<i32>()
+          .ok()
+          .filter(|e| e.abs() <= MAX_EXPONENT)?
+      }
+    };
+
+    let digits = format!("{integer}{fraction}");
+    let point = integer.len() as i64 + exponent as i64;
+    let digits = if point < 0 {
+      "0".repeat(-point as usize) + &digits
+    } else if point as usize > digits.len() {
+      digits.clone() + &"0".repeat(point as usize - digits.len())
+    } else {
+      digits
+    };
+    let point = point.max(0) as usize;
+    Some(Decimal::new(negative, &digits[..point], &digits[point..]))
+  }
+
+  fn new(negative: bool, integer: &str, fraction: &str) -> Decimal {
+    Decimal {
+      negative,
+      integer: integer.trim_start_matches('0').to_string(),
+      fraction: fraction.trim_end_matches('0').to_string(),
+    }
+  }
+
+  fn is_zero(&self) -> bool {
+    self.integer.is_empty() && self.fraction.is_empty()
+  }
+
+  fn truncate(self) -> Decimal {
+    Decimal::new(self.negative, &self.integer, "")
+  }
+
+  /// Rounds the number to at most `digits` fraction digits, rounding half
+  /// away from zero.
+  fn round(self, digits: usize) -> Decimal {
+    if self.fraction.len() <= digits {
+      return self;
+    }
+    let round_up = self.fraction.as_bytes()[digits] >= b'5';
+    let mut kept =
+      format!("{}{}", self.integer, &self.fraction[..digits]).into_bytes();
+    let mut carry = round_up;
+    for digit in kept.iter_mut().rev() {
+      if !carry {
+        break;
+      }
+      if *digit == b'9' {
+        *digit = b'0';
+      } else {
+        *digit += 1;
+        carry = false;
+      }
+    }
+    if carry {
+      kept.insert(0, b'1');
+    }
+    let kept = String::from_utf8(kept).unwrap();
+    let (integer, fraction) = kept.split_at(kept.len() - digits);
+    Decimal::new(self.negative, integer, fraction)
+  }
+}
+
+/// Normalizes a number literal to a decimal without an exponent and without
+/// leading or trailing zeros, like `1500` for `1.5e3`. Returns `None` if the
+/// literal is not valid or its exponent is beyond [`MAX_EXPONENT`].
+pub(crate) fn normalize_number(literal: &str) -> Option<String> {
+  Decimal::parse(literal).map(|number| number.to_string())
+}
+
+impl fmt::Display for Decimal {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.negative && !self.is_zero() {
+      f.write_str("-")?;
+    }
+    if self.integer.is_empty() {
+      f.write_str("0")?;
+    }
+    f.write_str(&self.integer)?;
+    if !self.fraction.is_empty() {
+      write!(f, ".{}", self.fraction)?;
+    }
+    Ok(())
+  }
+}
+
+/// Numbers are equal if they have the same value, so zero is equal to
+/// negative zero.
+impl PartialEq for Decimal {
+  fn eq(&self, other: &Self) -> bool {
+    self.integer == other.integer
+      && self.fraction == other.fraction
+      && (self.negative == other.negative || self.is_zero())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::normalize_number;
+  use super::Decimal;
+  use super::Locale;
+  use super::Symbols;
+  use super::NUMBER_SYMBOLS;
+
+  fn round(number: &str, digits: usize) -> String {
+    let number = Decimal::parse(number).unwrap().round(digits);
+    let sign = if number.negative { "-" } else { "" };
+    format!("{sign}{}.{}", number.integer, number.fraction)
+  }
+
+  #[test]
+  fn parses_number_literals() {
+    assert_eq!(Decimal::parse("1.0"), Decimal::parse("1"));
+    assert_eq!(Decimal::parse("-0"), Decimal::parse("0"));
+    assert_eq!(Decimal::parse("1.5e3"), Decimal::parse("1500"));
+    assert_eq!(Decimal::parse("15E-4"), Decimal::parse("0.0015"));
+    assert_eq!(
+      Decimal::parse("1e20"),
+      Decimal::parse("100000000000000000000")
+    );
+    for invalid in [
+      "", "-", "01", "1.", ".5", "1e", "1e+", "+1", "0x1", "1e21", "1e-21",
+      "1e9999",
+    ] {
+      assert_eq!(Decimal::parse(invalid), None, "{invalid}");
+    }
+  }
+
+  #[test]
+  fn looks_up_symbols() {
+    // The locales are looked up with a binary search.
+    assert!(NUMBER_SYMBOLS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    let symbols = |locale: &str| {
+      let symbols = Symbols::for_locale(&Locale::parse(locale));
+      (symbols.decimal, symbols.group, symbols.minimum_grouping)
+    };
+    assert_eq!(symbols("en-US"), (".", ",", 1));
+    assert_eq!(symbols("de-DE"), (",", ".", 1));
+    assert_eq!(symbols("de-CH"), (".", "\u{2019}", 1));
+    assert_eq!(symbols("es"), (",", ".", 2));
+    assert_eq!(symbols("es-MX"), (".", ",", 1));
+    assert_eq!(symbols("pt-PT"), (",", "\u{A0}", 2));
+    assert_eq!(symbols("tlh"), (".", ",", 1));
+  }
+
+  #[test]
+  fn normalizes_numbers() {
+    assert_eq!(normalize_number("1.5e3").unwrap(), "1500");
+    assert_eq!(normalize_number("-0.50").unwrap(), "-0.5");
+    assert_eq!(normalize_number("-0").unwrap(), "0");
+    assert_eq!(normalize_number("15E-4").unwrap(), "0.0015");
+    assert_eq!(
+      normalize_number("12345678901234567890.1").unwrap(),
+      "12345678901234567890.1"
+    );
+    assert_eq!(normalize_number("1e1000"), None);
+  }
+
+  #[test]
+  fn rounds_half_away_from_zero() {
+    assert_eq!(round("2.5", 0), "3.");
+    assert_eq!(round("-2.5", 0), "-3.");
+    assert_eq!(round("1.005", 2), "1.01");
+    assert_eq!(round("9.996", 2), "10.");
+    assert_eq!(round("0.0004", 3), ".");
+    assert_eq!(round("1.25", 3), "1.25");
+  }
+}
@@ -0,0 +1,182 @@
+//! The CLDR decimal and grouping separators, and minimum grouping digits,
+//! by locale. Locales that are not listed use the symbols of their
+//! language, or `.`, `,`, and 1.
+//!
+//! Generated by `tools/generate_number_symbols.sh` from CLDR 42. Do
+//! not edit.
+
+pub(super) const NUMBER_SYMBOLS: &[(&str, &str, &str, usize)] = &[
+  ("af", ",", "\u{A0}", 1),
+  ("agq", ",", "\u{A0}", 1),
+  ("ar-DZ", ",", ".", 1),
+  ("ar-LB", ",", ".", 1),
+  ("ar-LY", ",", ".", 1),
+  ("ar-MA", ",", ".", 1),
+  ("ar-MR", ",", ".", 1),
+  ("ar-TN", ",", ".", 1),
+  ("ast", ",", ".", 1),
+  ("az", ",", ".", 1),
+  ("bas", ",", "\u{A0}", 1),
+  ("be", ",", "\u{A0}", 2),
+  ("bg", ",", "\u{A0}", 2),
+  ("br", ",", "\u{A0}", 1),
+  ("bs", ",", ".", 1),
+  ("ca", ",", ".", 1),
+  ("cs", ",", "\u{A0}", 1),
+  ("cv", ",", "\u{A0}", 1),
+  ("da", ",", ".", 1),
+  ("de", ",", ".", 1),
+  ("de-AT", ",", "\u{A0}", 1),
+  ("de-CH", ".", "\u{2019}", 1),
+  ("de-LI", ".", "\u{2019}", 1),
+  ("dje", ".", "\u{A0}", 1),
+  ("dsb", ",", ".", 1),
+  ("dua", ",", "\u{A0}", 1),
+  ("dyo", ",", "\u{A0}", 1),
+  ("ee", ".", ",", 3),
+  ("el", ",", ".", 1),
+  ("en-AT", ",", ".", 1),
+  ("en-BE", ",", ".", 1),
+  ("en-CH", ".", "\u{2019}", 1),
+  ("en-DE", ",", ".", 1),
+  ("en-DK", ",", ".", 1),
+  ("en-FI", ",", "\u{A0}", 1),
+  ("en-NL", ",", ".", 1),
+  ("en-SE", ",", "\u{A0}", 1),
+  ("en-SI", ",", ".", 1),
+  ("en-ZA", ",", "\u{A0}", 1),
+  ("eo", ",", "\u{A0}", 1),
+  ("es", ",", ".", 2),
+  ("es-419", ".", ",", 1),
+  ("es-AR", ",", ".", 1),
+  ("es-BO", ",", ".", 1),
+  ("es-BR", ".", ",", 1),
+  ("es-BZ", ".", ",", 1),
+  ("es-CL", ",", ".", 1),
+  ("es-CO", ",", ".", 1),
+  ("es-CR", ",", "\u{A0}", 1),
+  ("es-CU", ".", ",", 1),
+  ("es-DO", ".", ",", 1),
+  ("es-EC", ",", ".", 1),
+  ("es-GT", ".", ",", 1),
+  ("es-HN", ".", ",", 1),
+  ("es-MX", ".", ",", 1),
+  ("es-NI", ".", ",", 1),
+  ("es-PA", ".", ",", 1),
+  ("es-PE", ".", ",", 1),
+  ("es-PR", ".", ",", 1),
+  ("es-PY", ",", ".", 1),
+  ("es-SV", ".", ",", 1),
+  ("es-US", ".", ",", 1),
+  ("es-UY", ",", ".", 1),
+  ("es-VE", ",", ".", 1),
+  ("et", ",", "\u{A0}", 2),
+  ("eu", ",", ".", 1),
+  ("ewo", ",", "\u{A0}", 1),
+  ("ff", ",", "\u{A0}", 1),
+  ("fi", ",", "\u{A0}", 1),
+  ("fo", ",", ".", 1),
+  ("fr", ",", "\u{202F}", 1),
+  ("fr-CA", ",", "\u{A0}", 1),
+  ("fr-LU", ",", ".", 1),
+  ("fr-MA", ",", ".", 1),
+  ("fur", ",", ".", 1),
+  ("fy", ",", ".", 1),
+  ("gl", ",", ".", 1),
+  ("gsw", ".", "\u{2019}", 1),
+  ("hr", ",", ".", 1),
+  ("hsb", ",", ".", 1),
+  ("hu", ",", "\u{A0}", 1),
+  ("hy", ",", "\u{A0}", 1),
+  ("ia", ",", ".", 2),
+  ("id", ",", ".", 1),
+  ("is", ",", ".", 1),
+  ("it", ",", ".", 1),
+  ("it-CH", ".", "\u{2019}", 1),
+  ("jgo", ",", ".", 1),
+  ("jv", ",", ".", 1),
+  ("ka", ",", "\u{A0}", 2),
+  ("kab", ",", "\u{A0}", 1),
+  ("kea", ",", "\u{A0}", 1),
+  ("kgp", ",", ".", 1),
+  ("khq", ".", "\u{A0}", 1),
+  ("kk", ",", "\u{A0}", 1),
+  ("kkj", ",", ".", 1),
+  ("kl", ",", ".", 1),
+  ("km", ",", ".", 1),
+  ("ks", ".", "\u{60C}", 1),
+  ("ksf", ",", "\u{A0}", 1),
+  ("ksh", ",", "\u{A0}", 1),
+  ("ku", ",", ".", 1),
+  ("ky", ",", "\u{A0}", 1),
+  ("lb", ",", ".", 1),
+  ("ln", ",", ".", 1),
+  ("lo", ",", ".", 1),
+  ("lt", ",", "\u{A0}", 1),
+  ("lu", ",", ".", 1),
+  ("lv", ",", "\u{A0}", 2),
+  ("mfe", ".", "\u{A0}", 1),
+  ("mgh", ",", ".", 1),
+  ("mk", ",", ".", 1),
+  ("ms-BN", ",", ".", 1),
+  ("ms-ID", ",", ".", 1),
+  ("mua", ",", ".", 1),
+  ("nb", ",", "\u{A0}", 1),
+  ("nl", ",", ".", 1),
+  ("nmg", ",", "\u{A0}", 1),
+  ("nn", ",", "\u{A0}", 1),
+  ("nnh", ",", ".", 1),
+  ("no", ",", "\u{A0}", 1),
+  ("os", ",", "\u{A0}", 1),
+  ("pl", ",", "\u{A0}", 2),
+  ("ps", ",", ".", 1),
+  ("pt", ",", ".", 1),
+  ("pt-AO", ",", "\u{A0}", 1),
+  ("pt-CH", ",", "\u{A0}", 2),
+  ("pt-CV", ",", "\u{A0}", 2),
+  ("pt-GQ", ",", "\u{A0}", 2),
+  ("pt-GW", ",", "\u{A0}", 2),
+  ("pt-LU", ",", "\u{A0}", 2),
+  ("pt-MO", ",", "\u{A0}", 2),
+  ("pt-MZ", ",", "\u{A0}", 2),
+  ("pt-PT", ",", "\u{A0}", 2),
+  ("pt-ST", ",", "\u{A0}", 2),
+  ("pt-TL", ",", "\u{A0}", 2),
+  ("qu-BO", ",", ".", 1),
+  ("rm", ".", "\u{2019}", 1),
+  ("rn", ",", ".", 1),
+  ("ro", ",", ".", 1),
+  ("ru", ",", "\u{A0}", 1),
+  ("ru-UA", ",", "\u{A0}", 2),
+  ("rw", ",", ".", 1),
+  ("sah", ",", "\u{A0}", 1),
+  ("sc", ",", ".", 1),
+  ("se", ",", "\u{A0}", 1),
+  ("seh", ",", ".", 1),
+  ("ses", ".", "\u{A0}", 1),
+  ("sg", ",", ".", 1),
+  ("shi", ",", "\u{A0}", 1),
+  ("sk", ",", "\u{A0}", 1),
+  ("sl", ",", ".", 1),
+  ("smn", ",", "\u{A0}", 1),
+  ("sq", ",", "\u{A0}", 2),
+  ("sr", ",", ".", 1),
+  ("su", ",", ".", 1),
+  ("sv", ",", "\u{A0}", 1),
+  ("sw-CD", ",", ".", 1),
+  ("tg", ",", "\u{A0}", 1),
+  ("tk", ",", "\u{A0}", 1),
+  ("tr", ",", ".", 1),
+  ("tt", ",", "\u{A0}", 1),
+  ("twq", ".", "\u{A0}", 1),
+  ("tzm", ",", "\u{A0}", 1),
+  ("uk", ",", "\u{A0}", 1),
+  ("uz", ",", "\u{A0}", 1),
+  ("vi", ",", ".", 1),
+  ("wae", ",", "\u{2019}", 1),
+  ("wo", ",", ".", 1),
+  ("xh", ".", "\u{A0}", 1),
+  ("yav", ",", "\u{A0}", 1),
+  ("yrl", ",", ".", 1),
+  ("zgh", ",", "\u{A0}", 1),
+];
@@ -0,0 +1,262 @@
+//! The CLDR plural rules, which select a plural category (`zero`, `one`,
+//! `two`, `few`, `many`, or `other`) for a number.
+//!
+//! The rules of every locale of CLDR are bundled in
+//! [`plural_rules_data`](super::plural_rules_data), in the syntax of
+//! <https://unicode.org/reports/tr35/tr35-numbers.html#Language_Plural_Rules>.
+//! Languages without rules only use the `other` category.
+
+use super::plural_rules_data::CARDINAL;
+use super::plural_rules_data::ORDINAL;
+use super::Locale;
+
+/// The operands of a formatted number that the plural rules are based on.
+/// Operands with more than 18 digits only keep their last 18 digits, plus
+/// 10^18 so that they are not equal to a small number. The rules only take
+/// the operands modulo powers of ten up to 10^6, which this does not change.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PluralOperands {
+  /// The integer digits of the absolute value of the number.
+  pub i: u64,
+  /// The number of visible fraction digits, including trailing zeros.
+  pub v: usize,
+  /// The number of visible fraction digits, without trailing zeros.
+  pub w: usize,
+  /// The visible fraction digits, including trailing zeros.
+  pub f: u64,
+  /// The visible fraction digits, without trailing zeros.
+  pub t: u64,
+}
+
+impl PluralOperands {
+  /// Creates the operands from the integer and the visible fraction digits
+  /// of a number.
+  pub fn new(integer: &str, fraction: &str) -> PluralOperands {
+    let trimmed = fraction.trim_end_matches('0');
+    PluralOperands {
+      i: digits_value(integer),
+      v: fraction.len(),
+      w: trimmed.len(),
+      f: digits_value(fraction),
+      t: digits_value(trimmed),
+    }
+  }
+}
+
+/// Selects the plural category for counting things, like "1 day" and
+/// "2 days" in English.
+pub(super) fn cardinal(
+  locale: &Locale,
+  operands: PluralOperands,
+) -> &'static str {
+  select(CARDINAL, locale, operands)
+}
+
+/// Selects the plural category for ordering things, like "1st", "2nd", and
+/// "3rd" in English.
+pub(super) fn ordinal(
+  locale: &Locale,
+  operands: PluralOperands,
+) -> &'static str {
+  select(ORDINAL, locale, operands)
+}
+
+fn select(
+  rules: &'static [(&'static str, &'static str)],
+  locale: &Locale,
+  operands: PluralOperands,
+) -> &'static str {
+  let rules = locale_rules(rules, locale).unwrap_or("");
+  rules
+    .split("; ")
+    .filter_map(|rule| rule.split_once(": "))
+    .find(|(_, condition)| matches(condition, &operands))
+    .map_or("other", |(category, _)| category)
+}
+
+/// Finds the rules of a locale, falling back from the language and region to
+/// the language, like `pt-PT` to `pt`.
+fn locale_rules(
+  rules: &'static [(&'static str, &'static str)],
+  locale: &Locale,
+) -> Option<&'static str> {
+  let find = |id: &str| {
+    rules
+      .binary_search_by(|(locale, _)| (*locale).cmp(id))
+      .ok()
+      .map(|index| rules[index].1)
+  };
+  locale
+    .region
+    .as_ref()
+    .and_then(|region| find(&format!("{}-{}", locale.language, region)))
+    .or_else(|| find(&locale.language))
+}
+
+/// Evaluates a condition, like `i = 1 and v = 0 or n = 0..1`. `and` binds
+/// more tightly than `or`.
+fn matches(condition: &str, operands: &PluralOperands) -> bool {
+  condition.split(" or ").any(|conjunction| {
+    conjunction
+      .split(" and ")
+      .all(|relation| relation_matches(relation, operands).unwrap_or(false))
+  })
+}
+
+/// Evaluates a relation, like `n % 100 != 11..19, 71`. Malformed relations
+/// are `None`.
+fn relation_matches(relation: &str, operands: &PluralOperands) -> Option<bool> {
+  let (expression, is_equal, ranges) =
+    if let Some((expression, ranges)) = relation.split_once(" != ") {
+      (expression, false, ranges)
+    } else {
+      let (expression, ranges) = relation.split_once(" = ")?;
+      (expression, true, ranges)
+    };
+  let (operand, modulus) = match expression.split_once(" % ") {
+    Some((operand, modulus)) => (operand, Some(modulus.parse::<u64>().ok()?)),
+    None => (expression, None),
+  };
+  let value = match operand {
+    "n" | "i" => operands.i,
+    "v" => operands.v as u64,
+    "w" => operands.w as u64,
+    "f" => operands.f,
+    "t" => operands.t,
+    // Compact notation is not supported, so the exponent is always zero.
+    "c" | "e" => 0,
+    _ => return None,
+  };
+  let value = match modulus {
+    Some(0) => return None,
+    Some(modulus) => value % modulus,
+    None => value,
+  };
+  // The ranges only contain integers, which `n` is not if it has fraction
+  // digits (that are not zero).
+  let is_integer = operand != "n" || operands.t == 0;
+  let mut is_in_ranges = false;
+  for range in ranges.split(", ") {
+    let (start, end) = range.split_once("..").unwrap_or((range, range));
+    let (start, end) = (start.parse::<u64>().ok()?, end.parse::<u64>().ok()?);
+    is_in_ranges |= is_integer && start <= value && value <= end;
+  }
+  Some(is_in_ranges == is_equal)
+}
+
+/// The value of a string of digits, which keeps the last 18 digits and adds
+/// 10^18 if there are more (see [`PluralOperands`]).
+fn digits_value(digits: &str) -> u64 {
+  let (high, low) = digits.split_at(digits.len().saturating_sub(18));
+  low.parse::<u64>().unwrap_or(0)
+    + if high.is_empty() { 0 } else { 10u64.pow(18) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::cardinal;
+  use super::ordinal;
+  use super::relation_matches;
+  use super::Locale;
+  use super::PluralOperands;
+  use super::CARDINAL;
+  use super::ORDINAL;
+
+  fn operands(number: &str) -> PluralOperands {
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    PluralOperands::new(integer.trim_start_matches('0'), fraction)
+  }
+
+  #[test]
+  fn parses_all_bundled_rules() {
+    let operands = operands("1");
+    for rules in [CARDINAL, ORDINAL] {
+      // The locales are looked up with a binary search.
+      assert!(rules.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+    for (locale, rules) in CARDINAL.iter().chain(ORDINAL) {
+      for rule in rules.split("; ").filter(|rule| !rule.is_empty()) {
+        let (_, condition) = rule.split_once(": ").unwrap();
+        for relation in condition.split(" or ").flat_map(|c| c.split(" and ")) {
+          assert!(
+            relation_matches(relation, &operands).is_some(),
+            "{locale}: {relation}"
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn selects_cardinal_categories() {
+    let categories = |locale: &str, numbers: &[&str]| {
+      let locale = Locale::parse(locale);
+      numbers
+        .iter()
+        .map(|number| cardinal(&locale, operands(number)))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      categories("en", &["1", "1.0", "0", "2"]),
+      ["one", "other", "other", "other"]
+    );
+    assert_eq!(
+      categories("hi", &["0", "1", "0.5", "2"]),
+      ["one", "one", "one", "other"]
+    );
+    assert_eq!(categories("fa", &["0", "1", "2"]), ["one", "one", "other"]);
+    assert_eq!(
+      categories("lt", &["1", "2", "11", "0.5", "10"]),
+      ["one", "few", "other", "many", "other"]
+    );
+    assert_eq!(
+      categories("lv", &["0", "1", "21", "2", "0.1"]),
+      ["zero", "one", "one", "other", "one"]
+    );
+    assert_eq!(
+      categories("cy", &["0", "1", "2", "3", "6", "4"]),
+      ["zero", "one", "two", "few", "many", "other"]
+    );
+    assert_eq!(
+      categories("ar", &["0", "1", "2", "3", "11", "100", "2.5"]),
+      ["zero", "one", "two", "few", "many", "other", "other"]
+    );
+    assert_eq!(
+      categories("fr", &["0", "1.5", "1000000", "2"]),
+      ["one", "one", "many", "other"]
+    );
+    assert_eq!(
+      categories("pt-PT", &["0", "1", "1000000"]),
+      ["other", "one", "many"]
+    );
+    assert_eq!(categories("pt-BR", &["0", "1"]), ["one", "one"]);
+    assert_eq!(
+      categories("ru", &["1", "21", "3", "5", "11", "1.5"]),
+      ["one", "one", "few", "many", "many", "other"]
+    );
+    assert_eq!(categories("ru", &["100000000000000000021"]), ["one"],);
+    assert_eq!(categories("ja", &["1"]), ["other"]);
+    assert_eq!(categories("tlh", &["1"]), ["other"]);
+  }
+
+  #[test]
+  fn selects_ordinal_categories() {
+    let categories = |locale: &str, numbers: &[&str]| {
+      let locale = Locale::parse(locale);
+      numbers
+        .iter()
+        .map(|number| ordinal(&locale, operands(number)))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      categories("en", &["1", "2", "3", "4", "11", "12", "13", "21"]),
+      ["one", "two", "few", "other", "other", "other", "other", "one"]
+    );
+    assert_eq!(categories("sv", &["1", "2", "3"]), ["one", "one", "other"]);
+    assert_eq!(
+      categories("it", &["8", "11", "1"]),
+      ["many", "many", "other"]
+    );
+    assert_eq!(categories("de", &["1"]), ["other"]);
+  }
+}
@@ -0,0 +1,57 @@
+//! The `:string` function, which formats any value as a string, and selects
+//! the key that is equal to it.
+
+use unicode_normalization::UnicodeNormalization;
+
+use super::Function;
+use super::FunctionError;
+use super::FunctionOptions;
+use crate::format::Value;
+
+/// The `:string` function. Placeholders without an annotation are also
+/// formatted with it.
+pub(super) struct StringFunction;
+
+impl Function for StringFunction {
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    _options: &FunctionOptions,
+    _locale: &str,
+  ) -> Result<String, FunctionError> {
+    operand
+      .map(Value::to_string)
+      .ok_or(FunctionError::BadOperand)
+  }
+
+  /// Keys are in Unicode Normalization Form C, so the operand is normalized
+  /// before it is compared to them.
+  fn select(
+    &self,
+    operand: Option<&Value>,
+    _options: &FunctionOptions,
+    _locale: &str,
+    keys: &[&str],
+  ) -> Result<Vec<usize>, FunctionError> {
+    let operand = operand.ok_or(FunctionError::BadOperand)?;
+    let normalized = operand.to_string().nfc().collect::<String>();
+    Ok(
+      keys
+        .iter()
+        .position(|key| *key == normalized)
+        .into_iter()
+        .collect(),
+    )
+  }
+
+  fn option_names(&self) -> Option<&[&str]> {
+    Some(&[])
+  }
+
+  fn description(&self) -> Option<&str> {
+    Some(
+      "Formats any value as a string. Selects the variant whose key is equal \
+       to the value.",
+    )
+  }
+}
@@ -0,0 +1,295 @@
+//! Time zones, given as IANA identifiers like `Europe/Berlin` or as fixed
+//! offsets from UTC.
+//!
+//! The bundled data in [`time_zone_data`](super::time_zone_data) only has the
+//! current rules of each time zone (the POSIX TZ string at the end of its TZif
+//! file), which are applied to all dates. Dates before the latest change of
+//! the rules of a time zone can get a different offset than with the full tz
+//! database.
+
+use super::datetime::civil_from_days;
+use super::datetime::days_from_civil;
+use super::datetime::days_in_month;
+use super::datetime::digits;
+use super::datetime::is_leap_year;
+use super::time_zone_data::TIME_ZONES;
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+/// A time zone, with offsets from UTC in minutes.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct TimeZone {
+  /// The offset of standard time.
+  offset: i32,
+  dst: Option<DaylightSavingTime>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DaylightSavingTime {
+  offset: i32,
+  /// When daylight saving time starts, in local standard time.
+  start: Transition,
+  /// When daylight saving time ends, in local daylight saving time.
+  end: Transition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Transition {
+  date: TransitionDate,
+  /// The local time of the transition in minutes after midnight, which can be
+  /// negative or more than a day.
+  time: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TransitionDate {
+  /// `Jn`: the day of the year from 1 to 365, not counting February 29.
+  Julian(u32),
+  /// `n`: the day of the year from 0 to 365, counting February 29.
+  DayOfYear(u32),
+  /// `Mm.w.d`: the weekday `d` (0 is Sunday) of the week `w` of the month
+  /// `m`, where the week 5 is the last week of the month.
+  MonthWeekDay { month: u32, week: u32, weekday: u32 },
+}
+
+impl TimeZone {
+  pub fn fixed(offset: i32) -> TimeZone {
+    TimeZone { offset, dst: None }
+  }
+
+  /// Looks up an IANA time zone by its identifier, ignoring ASCII case.
+  pub fn for_id(id: &str) -> Option<TimeZone> {
+    let (_, rule) = TIME_ZONES
+      .iter()
+      .find(|(zone, _)| zone.eq_ignore_ascii_case(id))?;
+    TimeZone::parse_posix(rule)
+  }
+
+  /// Parses a POSIX TZ string, like `CET-1CEST,M3.5.0,M10.5.0/3`.
+  fn parse_posix(rule: &str) -> Option<TimeZone> {
+    let rest = skip_name(rule)?;
+    let (offset, rest) = parse_time(rest)?;
+    // POSIX offsets are positive west of Greenwich.
+    let offset = -offset;
+    if rest.is_empty() {
+      return Some(TimeZone::fixed(offset));
+    }
+
+    let rest = skip_name(rest)?;
+    let (dst_offset, rest) = match rest.strip_prefix(',') {
+      Some(_) => (offset + 60, rest),
+      None => {
+        let (dst_offset, rest) = parse_time(rest)?;
+        (-dst_offset, rest)
+      }
+    };
+    let (start, rest) = Transition::parse(rest.strip_prefix(',')?)?;
+    let (end, rest) = Transition::parse(rest.strip_prefix(',')?)?;
+    rest.is_empty().then_some(TimeZone {
+      offset,
+      dst: Some(DaylightSavingTime {
+        offset: dst_offset,
+        start,
+        end,
+      }),
+    })
+  }
+
+  /// The offset at an instant, given in minutes since 1970-01-01T00:00Z.
+  pub fn offset_at(&self, minutes: i64) -> i32 {
+    let Some(dst) = &self.dst else {
+      return self.offset;
+    };
+    let local_day = (minutes + self.offset as i64).div_euclid(MINUTES_PER_DAY);
+    let (year, _, _) = civil_from_days(local_day);
+    let start = dst.start.local_minutes(year) - self.offset as i64;
+    let end = dst.end.local_minutes(year) - dst.offset as i64;
+    let is_dst = if start < end {
+      start <= minutes && minutes < end
+    } else {
+      // Daylight saving time spans the turn of the year.
+      minutes < end || start <= minutes
+    };
+    if is_dst {
+      dst.offset
+    } else {
+      self.offset
+    }
+  }
+
+  /// The offset of a local time, given in minutes since 1970-01-01T00:00 in
+  /// the time zone. Local times that occur twice get the earlier offset, and
+  /// local times that are skipped get the offset before the transition.
+  pub fn offset_at_local(&self, minutes: i64) -> i32 {
+    let Some(dst) = &self.dst else {
+      return self.offset;
+    };
+    let earlier = self.offset.max(dst.offset);
+    let later = self.offset.min(dst.offset);
+    if self.offset_at(minutes - earlier as i64) == earlier {
+      earlier
+    } else if self.offset_at(minutes - later as i64) == later {
+      later
+    } else {
+      self.offset_at(minutes - earlier as i64)
+    }
+  }
+}
+
+impl Transition {
+  fn parse(s: &str) -> Option<(Transition, &str)> {
+    let (date, rest) = if let Some(rest) = s.strip_prefix('M') {
+      let (month, rest) = number(rest)?;
+      let (week, rest) = number(rest.strip_prefix('.')?)?;
+      let (weekday, rest) = number(rest.strip_prefix('.')?)?;
+      if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+        return None;
+      }
+      let date = TransitionDate::MonthWeekDay {
+        month,
+        week,
+        weekday,
+      };
+      (date, rest)
+    } else if let Some(rest) = s.strip_prefix('J') {
+      let (day, rest) = number(rest)?;
+      if !(1..=365).contains(&day) {
+        return None;
+      }
+      (TransitionDate::Julian(day), rest)
+    } else {
+      let (day, rest) = number(s)?;
+      if day > 365 {
+        return None;
+      }
+      (TransitionDate::DayOfYear(day), rest)
+    };
+    let (time, rest) = match rest.strip_prefix('/') {
+      Some(rest) => parse_time(rest)?,
+      None => (2 * 60, rest),
+    };
+    Some((Transition { date, time }, rest))
+  }
+
+  /// The local time of the transition in a year, in minutes since
+  /// 1970-01-01T00:00.
+  fn local_minutes(&self, year: i64) -> i64 {
+    let new_year = days_from_civil(year, 1, 1);
+    let day = match self.date {
+      TransitionDate::Julian(day) => {
+        let leap_day = (is_leap_year(year) && day >= 60) as i64;
+        new_year + day as i64 - 1 + leap_day
+      }
+      TransitionDate::DayOfYear(day) => new_year + day as i64,
+      TransitionDate::MonthWeekDay {
+        month,
+        week,
+        weekday,
+      } => {
+        let first = days_from_civil(year, month, 1);
+        // 1970-01-01 was a Thursday.
+        let first_weekday = (first + 4).rem_euclid(7);
+        let last = first + days_in_month(year, month) as i64 - 1;
+        let mut day = first
+          + (weekday as i64 - first_weekday).rem_euclid(7)
+          + (week as i64 - 1) * 7;
+        while day > last {
+          day -= 7;
+        }
+        day
+      }
+    };
+    day * MINUTES_PER_DAY + self.time as i64
+  }
+}
+
+/// Skips the name of a time zone in a POSIX TZ string, which is either
+/// alphabetic, like `CET`, or quoted, like `<+0330>`.
+fn skip_name(s: &str) -> Option<&str> {
+  if let Some(rest) = s.strip_prefix('<') {
+    let end = rest.find('>')?;
+    return Some(&rest[end + 1..]);
+  }
+  let len = s.bytes().take_while(u8::is_ascii_alphabetic).count();
+  (len >= 3).then(|| &s[len..])
+}
+
+/// Parses a time like `-3:30` or `25` in a POSIX TZ string, in minutes.
+/// Seconds are ignored.
+fn parse_time(s: &str) -> Option<(i32, &str)> {
+  let (sign, rest) = match s.as_bytes().first()? {
+    b'+' => (1, &s[1..]),
+    b'-' => (-1, &s[1..]),
+    _ => (1, s),
+  };
+  let (hours, mut rest) = number(rest)?;
+  let mut minutes = hours as i32 * 60;
+  if let Some(after_colon) = rest.strip_prefix(':') {
+    let (minute, after_minute) = digits(after_colon, 2)?;
+    minutes += minute as i32;
+    rest = after_minute;
+    if let Some(after_colon) = rest.strip_prefix(':') {
+      (_, rest) = digits(after_colon, 2)?;
+    }
+  }
+  Some((sign * minutes, rest))
+}
+
+/// Parses a number of one to three digits.
+fn number(s: &str) -> Option<(u32, &str)> {
+  let len = s.bytes().take_while(u8::is_ascii_digit).count();
+  if !(1..=3).contains(&len) {
+    return None;
+  }
+  Some((s[..len].parse().ok()?, &s[len..]))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::days_from_civil;
+  use super::TimeZone;
+  use super::TIME_ZONES;
+
+  fn minutes(year: i64, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+    days_from_civil(year, month, day) * 24 * 60 + hour * 60 + minute
+  }
+
+  #[test]
+  fn parses_all_bundled_time_zones() {
+    for (id, rule) in TIME_ZONES {
+      assert!(TimeZone::parse_posix(rule).is_some(), "{id}: {rule}");
+    }
+  }
+
+  #[test]
+  fn applies_daylight_saving_time() {
+    let berlin = TimeZone::for_id("europe/berlin").unwrap();
+    assert_eq!(berlin.offset_at(minutes(2024, 3, 31, 0, 59)), 60);
+    assert_eq!(berlin.offset_at(minutes(2024, 3, 31, 1, 0)), 120);
+    assert_eq!(berlin.offset_at(minutes(2024, 10, 27, 0, 59)), 120);
+    assert_eq!(berlin.offset_at(minutes(2024, 10, 27, 1, 0)), 60);
+
+    let sydney = TimeZone::for_id("Australia/Sydney").unwrap();
+    assert_eq!(sydney.offset_at(minutes(2024, 1, 15, 0, 0)), 660);
+    assert_eq!(sydney.offset_at(minutes(2024, 7, 15, 0, 0)), 600);
+
+    // Irish standard time is in summer, and daylight saving time in winter.
+    let dublin = TimeZone::for_id("Europe/Dublin").unwrap();
+    assert_eq!(dublin.offset_at(minutes(2024, 1, 15, 0, 0)), 0);
+    assert_eq!(dublin.offset_at(minutes(2024, 7, 15, 0, 0)), 60);
+
+    let kolkata = TimeZone::for_id("Asia/Kolkata").unwrap();
+    assert_eq!(kolkata.offset_at(minutes(2024, 7, 15, 0, 0)), 330);
+    assert_eq!(TimeZone::for_id("Mars/Olympus_Mons"), None);
+  }
+
+  #[test]
+  fn resolves_local_times() {
+    let new_york = TimeZone::for_id("America/New_York").unwrap();
+    // Skipped when daylight saving time starts.
+    assert_eq!(new_york.offset_at_local(minutes(2024, 3, 10, 2, 30)), -300);
+    // Repeated when daylight saving time ends.
+    assert_eq!(new_york.offset_at_local(minutes(2024, 11, 3, 1, 30)), -240);
+    assert_eq!(new_york.offset_at_local(minutes(2024, 11, 3, 2, 30)), -300);
+  }
+}
@@ -1,19 +1,66 @@
 use ast::Message;
+use cst::SyntaxNode;
 use parser::Parser;
 
 pub mod ast;
 mod chars;
+pub mod cst;
+pub mod data_model;
 mod diagnostic;
+pub mod format;
+pub mod functions;
+mod lower;
+mod options;
 mod parser;
+mod printer;
+mod tokens;
 mod util;
+mod validate;
 mod visitor;
 
 pub use diagnostic::Diagnostic;
+pub use options::{FormatOptions, ParseOptions, SpecVersion};
+pub use printer::{format_source, print};
+pub use tokens::{Token, TokenKind};
 pub use util::{Location, SourceTextInfo, Span, Spanned};
+pub use validate::{validate, validate_with_functions};
 pub use visitor::{Visit, Visitable};
 
 pub fn parse(message: &str) -> (Message, Vec<Diagnostic>, SourceTextInfo) {
-  Parser::new(message).parse()
+  parse_with_options(message, ParseOptions::default())
+}
+
+pub fn parse_with_options(
+  message: &str,
+  options: ParseOptions,
+) -> (Message, Vec<Diagnostic>, SourceTextInfo) {
+  let (root, diagnostics, info) = parse_cst(message, options);
+  (Message::from_cst(message, &root), diagnostics, info)
+}
+
+/// Parses a message into its lossless concrete syntax tree. The AST returned
+/// by [`parse_with_options`] is derived from this tree.
+pub fn parse_cst(
+  message: &str,
+  options: ParseOptions,
+) -> (SyntaxNode, Vec<Diagnostic>, SourceTextInfo) {
+  let (green, diagnostics, info) = Parser::new(message, options).parse();
+  (SyntaxNode::new_root(green), diagnostics, info)
+}
+
+/// Splits a message into its lexical tokens, following the same rules as the
+/// parser. The tokens cover the whole message without gaps, so concatenating
+/// their text gives back the message.
+pub fn tokenize(message: &str) -> impl Iterator<Item = Token<'_>> {
+  tokenize_with_options(message, ParseOptions::default())
+}
+
+pub fn tokenize_with_options(
+  message: &str,
+  options: ParseOptions,
+) -> impl Iterator<Item = Token<'_>> {
+  let (root, _diagnostics, _info) = parse_cst(message, options);
+  tokens::tokens(message, &root).into_iter()
 }
 
 #[cfg(test)]
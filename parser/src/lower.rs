@@ -0,0 +1,602 @@
+//! Derives the AST from the concrete syntax tree.
+//!
+//! The parser has already reported all diagnostics, and has shaped the tree
+//! such that every node has the children that the AST requires. The few parts
+//! of the AST that have no source text (like the empty literal of a
+//! placeholder without a body) are derived here.
+
+use std::borrow::Cow;
+
+use crate::ast::Annotation;
+use crate::ast::AnnotationExpression;
+use crate::ast::Attribute;
+use crate::ast::ComplexMessage;
+use crate::ast::ComplexMessageBody;
+use crate::ast::Declaration;
+use crate::ast::Escape;
+use crate::ast::Expression;
+use crate::ast::FnOrMarkupOption;
+use crate::ast::Function;
+use crate::ast::Identifier;
+use crate::ast::InputDeclaration;
+use crate::ast::Key;
+use crate::ast::Literal;
+use crate::ast::LiteralExpression;
+use crate::ast::LiteralOrVariable;
+use crate::ast::LocalDeclaration;
+use crate::ast::Markup;
+use crate::ast::MarkupKind;
+use crate::ast::Matcher;
+use crate::ast::Message;
+use crate::ast::Number;
+use crate::ast::Pattern;
+use crate::ast::PatternPart;
+use crate::ast::PrivateUseAnnotation;
+use crate::ast::Quoted;
+use crate::ast::QuotedPart;
+use crate::ast::QuotedPattern;
+use crate::ast::ReservedAnnotation;
+use crate::ast::ReservedBodyPart;
+use crate::ast::ReservedStatement;
+use crate::ast::Selector;
+use crate::ast::Star;
+use crate::ast::Text;
+use crate::ast::Variable;
+use crate::ast::VariableExpression;
+use crate::ast::Variant;
+use crate::cst::SyntaxElement;
+use crate::cst::SyntaxKind;
+use crate::cst::SyntaxNode;
+use crate::cst::SyntaxToken;
+use crate::util::Location;
+use crate::util::Span;
+use crate::util::Spanned;
+
+impl<'a> Message<'a> {
+  /// Derives the AST of a message from its concrete syntax tree. The `source`
+  /// must be the text the tree was parsed from.
+  pub fn from_cst(source: &'a str, root: &SyntaxNode) -> Message<'a> {
+    debug_assert_eq!(root.kind(), SyntaxKind::Root);
+    let lower = Lower { source };
+    for child in root.children() {
+      match child.kind() {
+        SyntaxKind::Pattern => return Message::Simple(lower.pattern(&child)),
+        SyntaxKind::ComplexMessage => {
+          return Message::Complex(lower.complex_message(&child))
+        }
+        _ => {}
+      }
+    }
+    unreachable!("the root node always contains a message")
+  }
+}
+
+struct Lower<'a> {
+  source: &'a str,
+}
+
+impl<'a> Lower<'a> {
+  fn source_text(&self, span: Span) -> &'a str {
+    &self.source[span.start.inner() as usize..span.end.inner() as usize]
+  }
+
+  fn slice(&self, span: Span) -> Cow<'a, str> {
+    Cow::Borrowed(self.source_text(span))
+  }
+
+  fn text(&self, token: &SyntaxToken) -> Text<'a> {
+    let span = token.span();
+    Text {
+      start: span.start,
+      content: self.slice(span),
+    }
+  }
+
+  fn empty_text(&self, start: Location) -> Text<'a> {
+    Text {
+      start,
+      content: Cow::Borrowed(""),
+    }
+  }
+
+  fn escape(&self, token: &SyntaxToken) -> Escape {
+    Escape {
+      start: token.span().start,
+      escaped_char: token.text().chars().nth(1).unwrap(),
+    }
+  }
+
+  fn pattern(&self, node: &SyntaxNode) -> Pattern<'a> {
+    let mut parts = vec![];
+    for child in node.children_with_tokens() {
+      match child {
+        SyntaxElement::Token(token) => match token.kind() {
+          SyntaxKind::Text => parts.push(PatternPart::Text(self.text(&token))),
+          SyntaxKind::Escape => {
+            parts.push(PatternPart::Escape(self.escape(&token)))
+          }
+          _ => {}
+        },
+        SyntaxElement::Node(node) => match node.kind() {
+          SyntaxKind::Markup => {
+            parts.push(PatternPart::Markup(self.markup(&node)))
+          }
+          kind if is_expression(kind) => {
+            parts.push(PatternPart::Expression(self.expression(&node)))
+          }
+          _ => {}
+        },
+      }
+    }
+    Pattern { parts }
+  }
+
+  fn literal_or_variable(
+    &self,
+    element: &SyntaxElement,
+  ) -> Option<LiteralOrVariable<'a>> {
+    match element {
+      SyntaxElement::Node(node) if node.kind() == SyntaxKind::Variable => {
+        Some(LiteralOrVariable::Variable(self.variable(node)))
+      }
+      _ => self.literal(element).map(LiteralOrVariable::Literal),
+    }
+  }
+
+  fn literal(&self, element: &SyntaxElement) -> Option<Literal<'a>> {
+    match element {
+      SyntaxElement::Node(node) if node.kind() == SyntaxKind::Quoted => {
+        Some(Literal::Quoted(self.quoted(node)))
+      }
+      SyntaxElement::Token(token) if token.kind() == SyntaxKind::Name => {
+        Some(Literal::Text(self.text(token)))
+      }
+      SyntaxElement::Token(token) if token.kind() == SyntaxKind::Number => {
+        let span = token.span();
+        Some(Literal::Number(Number::new(
+          span.start,
+          self.source_text(span),
+        )))
+      }
+      _ => None,
+    }
+  }
+
+  fn expression(&self, node: &SyntaxNode) -> Expression<'a> {
+    let span = node.span();
+    let mut value = None;
+    let mut annotation = None;
+    let mut attributes = vec![];
+
+    for child in node.children_with_tokens() {
+      if let SyntaxElement::Node(node) = &child {
+        match node.kind() {
+          SyntaxKind::Function => {
+            annotation = Some(Annotation::Function(self.function(node)));
+            continue;
+          }
+          SyntaxKind::PrivateUseAnnotation => {
+            annotation = Some(Annotation::PrivateUseAnnotation(
+              self.private_use_annotation(node),
+            ));
+            continue;
+          }
+          SyntaxKind::ReservedAnnotation => {
+            annotation = Some(Annotation::ReservedAnnotation(
+              self.reserved_annotation(node),
+            ));
+            continue;
+          }
+          SyntaxKind::Attribute => {
+            attributes.push(self.attribute(node));
+            continue;
+          }
+          _ => {}
+        }
+      }
+      if value.is_none() {
+        value = self.literal_or_variable(&child);
+      }
+    }
+
+    match (value, annotation) {
+      (Some(LiteralOrVariable::Variable(variable)), annotation) => {
+        Expression::VariableExpression(VariableExpression {
+          span,
+          variable,
+          annotation,
+          attributes,
+        })
+      }
+      (Some(LiteralOrVariable::Literal(literal)), annotation) => {
+        Expression::LiteralExpression(LiteralExpression {
+          span,
+          literal,
+          annotation,
+          attributes,
+        })
+      }
+      (None, Some(annotation)) => {
+        Expression::AnnotationExpression(AnnotationExpression {
+          span,
+          annotation,
+          attributes,
+        })
+      }
+      // A placeholder without a body has an empty text as its literal.
+      (None, None) => Expression::LiteralExpression(LiteralExpression {
+        span,
+        literal: Literal::Text(self.empty_text(span.start)),
+        annotation: None,
+        attributes,
+      }),
+    }
+  }
+
+  fn variable(&self, node: &SyntaxNode) -> Variable<'a> {
+    let name = node
+      .children_with_tokens()
+      .filter_map(SyntaxElement::into_token)
+      .find(|token| token.kind() == SyntaxKind::Name)
+      .map_or(Cow::Borrowed(""), |token| self.slice(token.span()));
+    Variable {
+      span: node.span(),
+      name,
+    }
+  }
+
+  fn identifier(&self, node: &SyntaxNode) -> Identifier<'a> {
+    let mut namespace = None;
+    let mut name = Cow::Borrowed("");
+    for token in node.children_with_tokens().filter_map(|c| c.into_token()) {
+      match token.kind() {
+        SyntaxKind::Name => name = self.slice(token.span()),
+        SyntaxKind::Colon => namespace = Some(std::mem::take(&mut name)),
+        _ => {}
+      }
+    }
+    Identifier {
+      start: node.span().start,
+      namespace,
+      name,
+    }
+  }
+
+  /// The identifier of a function, option, attribute, or markup.
+  fn child_identifier(&self, node: &SyntaxNode) -> Identifier<'a> {
+    let id = node
+      .children()
+      .find(|child| child.kind() == SyntaxKind::Identifier)
+      .unwrap();
+    self.identifier(&id)
+  }
+
+  fn function(&self, node: &SyntaxNode) -> Function<'a> {
+    Function {
+      start: node.span().start,
+      id: self.child_identifier(node),
+      options: self.options(node),
+    }
+  }
+
+  fn options(&self, node: &SyntaxNode) -> Vec<FnOrMarkupOption<'a>> {
+    node
+      .children()
+      .filter(|child| child.kind() == SyntaxKind::Option)
+      .map(|option| self.option(&option))
+      .collect()
+  }
+
+  fn option(&self, node: &SyntaxNode) -> FnOrMarkupOption<'a> {
+    let value = node
+      .children_with_tokens()
+      .find_map(|child| self.literal_or_variable(&child))
+      // A missing value is an empty text at the end of the option.
+      .unwrap_or_else(|| {
+        LiteralOrVariable::Literal(Literal::Text(
+          self.empty_text(node.span().end),
+        ))
+      });
+    FnOrMarkupOption {
+      key: self.child_identifier(node),
+      value,
+    }
+  }
+
+  fn attributes(&self, node: &SyntaxNode) -> Vec<Attribute<'a>> {
+    node
+      .children()
+      .filter(|child| child.kind() == SyntaxKind::Attribute)
+      .map(|attribute| self.attribute(&attribute))
+      .collect()
+  }
+
+  fn attribute(&self, node: &SyntaxNode) -> Attribute<'a> {
+    Attribute {
+      span: node.span(),
+      key: self.child_identifier(node),
+      value: node
+        .children_with_tokens()
+        .find_map(|child| self.literal_or_variable(&child)),
+    }
+  }
+
+  fn reserved_body(&self, node: &SyntaxNode) -> Vec<ReservedBodyPart<'a>> {
+    let mut parts = vec![];
+    for child in node.children_with_tokens() {
+      match child {
+        SyntaxElement::Token(token) => match token.kind() {
+          SyntaxKind::Text => {
+            parts.push(ReservedBodyPart::Text(self.text(&token)))
+          }
+          SyntaxKind::Escape => {
+            parts.push(ReservedBodyPart::Escape(self.escape(&token)))
+          }
+          _ => {}
+        },
+        SyntaxElement::Node(node) if node.kind() == SyntaxKind::Quoted => {
+          parts.push(ReservedBodyPart::Quoted(self.quoted(&node)))
+        }
+        SyntaxElement::Node(_) => {}
+      }
+    }
+    parts
+  }
+
+  fn sigil(&self, node: &SyntaxNode) -> char {
+    let token = node
+      .children_with_tokens()
+      .find_map(SyntaxElement::into_token)
+      .unwrap();
+    debug_assert_eq!(token.kind(), SyntaxKind::Sigil);
+    token.text().chars().next().unwrap()
+  }
+
+  fn private_use_annotation(
+    &self,
+    node: &SyntaxNode,
+  ) -> PrivateUseAnnotation<'a> {
+    PrivateUseAnnotation {
+      start: node.span().start,
+      sigil: self.sigil(node),
+      body: self.reserved_body(node),
+    }
+  }
+
+  fn reserved_annotation(&self, node: &SyntaxNode) -> ReservedAnnotation<'a> {
+    ReservedAnnotation {
+      start: node.span().start,
+      sigil: self.sigil(node),
+      body: self.reserved_body(node),
+    }
+  }
+
+  fn quoted(&self, node: &SyntaxNode) -> Quoted<'a> {
+    let mut parts = vec![];
+    for token in node.children_with_tokens().filter_map(|c| c.into_token()) {
+      match token.kind() {
+        SyntaxKind::Text => parts.push(QuotedPart::Text(self.text(&token))),
+        SyntaxKind::Escape => {
+          parts.push(QuotedPart::Escape(self.escape(&token)))
+        }
+        _ => {}
+      }
+    }
+    Quoted {
+      span: node.span(),
+      parts,
+    }
+  }
+
+  fn markup(&self, node: &SyntaxNode) -> Markup<'a> {
+    let mut kind = None;
+    for token in node.children_with_tokens().filter_map(|c| c.into_token()) {
+      kind = match (kind, token.kind()) {
+        (None, SyntaxKind::Hash) => Some(MarkupKind::Open),
+        (None, SyntaxKind::Slash) => Some(MarkupKind::Close),
+        (Some(_), SyntaxKind::Slash) => Some(MarkupKind::Standalone),
+        (kind, _) => kind,
+      };
+    }
+    Markup {
+      span: node.span(),
+      kind: kind.unwrap(),
+      id: self.child_identifier(node),
+      options: self.options(node),
+      attributes: self.attributes(node),
+    }
+  }
+
+  fn complex_message(&self, node: &SyntaxNode) -> ComplexMessage<'a> {
+    let mut declarations = vec![];
+    let mut body = None;
+    for child in node.children() {
+      match child.kind() {
+        SyntaxKind::InputDeclaration => declarations.push(
+          Declaration::InputDeclaration(self.input_declaration(&child)),
+        ),
+        SyntaxKind::LocalDeclaration => declarations.push(
+          Declaration::LocalDeclaration(self.local_declaration(&child)),
+        ),
+        SyntaxKind::ReservedStatement => declarations.push(
+          Declaration::ReservedStatement(self.reserved_statement(&child)),
+        ),
+        // Only the first body is used, any further bodies are an error.
+        SyntaxKind::Matcher if body.is_none() => {
+          body = Some(ComplexMessageBody::Matcher(self.matcher(&child)));
+        }
+        SyntaxKind::QuotedPattern if body.is_none() => {
+          body = Some(ComplexMessageBody::QuotedPattern(
+            self.quoted_pattern(&child),
+          ));
+        }
+        _ => {}
+      }
+    }
+
+    // A missing body is an empty pattern at the end of the message.
+    let body = body.unwrap_or_else(|| {
+      let end = Location::new(self.source.len() as u32);
+      ComplexMessageBody::QuotedPattern(QuotedPattern {
+        span: Span::new(end..end),
+        pattern: Pattern {
+          parts: vec![PatternPart::Text(self.empty_text(end))],
+        },
+      })
+    });
+
+    ComplexMessage { declarations, body }
+  }
+
+  fn expressions(
+    &self,
+    node: &SyntaxNode,
+  ) -> impl Iterator<Item = Expression<'a>> + '_ {
+    node
+      .children()
+      .filter(|child| is_expression(child.kind()))
+      .map(|child| self.expression(&child))
+  }
+
+  fn input_declaration(&self, node: &SyntaxNode) -> InputDeclaration<'a> {
+    let expression = match self.expressions(node).next() {
+      Some(Expression::VariableExpression(expression)) => expression,
+      _ => unreachable!(
+        "the parser only creates input declarations for variable expressions"
+      ),
+    };
+    InputDeclaration {
+      start: node.span().start,
+      expression,
+    }
+  }
+
+  fn local_declaration(&self, node: &SyntaxNode) -> LocalDeclaration<'a> {
+    let variable = node
+      .children()
+      .find(|child| child.kind() == SyntaxKind::Variable)
+      .unwrap();
+    let variable = self.variable(&variable);
+
+    let expression = self.expressions(node).next().unwrap_or_else(|| {
+      // A missing expression is an empty literal at the `=`, or after the
+      // variable if the `=` is missing as well.
+      let start = node
+        .children_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .find(|token| token.kind() == SyntaxKind::Equals)
+        .map_or(variable.span.end, |token| token.span().start);
+      Expression::LiteralExpression(LiteralExpression {
+        span: Span::new(start..start),
+        literal: Literal::Text(self.empty_text(start)),
+        annotation: None,
+        attributes: vec![],
+      })
+    });
+
+    LocalDeclaration {
+      start: node.span().start,
+      variable,
+      expression,
+    }
+  }
+
+  fn reserved_statement(&self, node: &SyntaxNode) -> ReservedStatement<'a> {
+    let keyword = node
+      .children_with_tokens()
+      .find_map(SyntaxElement::into_token)
+      .unwrap();
+    debug_assert_eq!(keyword.kind(), SyntaxKind::Keyword);
+    let keyword = keyword.span();
+    ReservedStatement {
+      start: node.span().start,
+      name: self.slice(Span::new(keyword.start + '.'..keyword.end)),
+      body: self.reserved_body(node),
+      expressions: self.expressions(node).collect(),
+    }
+  }
+
+  fn quoted_pattern(&self, node: &SyntaxNode) -> QuotedPattern<'a> {
+    let pattern = node
+      .children()
+      .find(|child| child.kind() == SyntaxKind::Pattern)
+      .unwrap();
+    let pattern = self.pattern(&pattern);
+    let is_quoted = node
+      .children_with_tokens()
+      .any(|child| child.kind() == SyntaxKind::DoubleOpenBrace);
+    // Patterns that were recovered as quoted patterns only span their parts.
+    let span = if is_quoted {
+      node.span()
+    } else {
+      pattern.span()
+    };
+    QuotedPattern { span, pattern }
+  }
+
+  fn matcher(&self, node: &SyntaxNode) -> Matcher<'a> {
+    let mut selectors = vec![];
+    let mut variants = vec![];
+    for child in node.children() {
+      match child.kind() {
+        SyntaxKind::Variable => {
+          selectors.push(Selector::Variable(self.variable(&child)))
+        }
+        kind if is_expression(kind) => {
+          selectors.push(Selector::Expression(self.expression(&child)))
+        }
+        SyntaxKind::Variant => variants.push(self.variant(&child)),
+        _ => {}
+      }
+    }
+    Matcher {
+      start: node.span().start,
+      selectors,
+      variants,
+    }
+  }
+
+  fn variant(&self, node: &SyntaxNode) -> Variant<'a> {
+    let mut keys = vec![];
+    let mut pattern = None;
+    for child in node.children_with_tokens() {
+      match &child {
+        SyntaxElement::Token(token) if token.kind() == SyntaxKind::Star => keys
+          .push(Key::Star(Star {
+            start: token.span().start,
+          })),
+        // A variable used as a key is recovered as a text key.
+        SyntaxElement::Node(node) if node.kind() == SyntaxKind::Variable => {
+          let span = node.span();
+          keys.push(Key::Literal(Literal::Text(Text {
+            start: span.start,
+            content: self.slice(span),
+          })))
+        }
+        SyntaxElement::Node(node)
+          if node.kind() == SyntaxKind::QuotedPattern =>
+        {
+          pattern = Some(self.quoted_pattern(node))
+        }
+        _ => {
+          if let Some(literal) = self.literal(&child) {
+            keys.push(Key::Literal(literal))
+          }
+        }
+      }
+    }
+    Variant {
+      keys,
+      pattern: pattern.unwrap(),
+    }
+  }
+}
+
+fn is_expression(kind: SyntaxKind) -> bool {
+  matches!(
+    kind,
+    SyntaxKind::LiteralExpression
+      | SyntaxKind::VariableExpression
+      | SyntaxKind::AnnotationExpression
+  )
+}
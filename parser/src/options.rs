@@ -0,0 +1,66 @@
+/// The version of the MessageFormat 2 specification to parse against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpecVersion {
+  /// The LDML 45 tech preview, which still has reserved and private-use
+  /// syntax, and uses expressions as `.match` selectors.
+  Draft,
+  /// LDML 46, which drops reserved and private-use syntax and adds support
+  /// for bidi marks and isolates.
+  Ldml46,
+  /// LDML 47, the final version of the syntax, which requires `.match`
+  /// selectors to be variables.
+  #[default]
+  Ldml47,
+}
+
+impl SpecVersion {
+  pub(crate) fn has_reserved_syntax(self) -> bool {
+    matches!(self, SpecVersion::Draft)
+  }
+
+  pub(crate) fn has_variable_attribute_values(self) -> bool {
+    matches!(self, SpecVersion::Draft)
+  }
+
+  pub(crate) fn has_bidi(self) -> bool {
+    !matches!(self, SpecVersion::Draft)
+  }
+
+  pub(crate) fn has_variable_selectors(self) -> bool {
+    matches!(self, SpecVersion::Ldml47)
+  }
+}
+
+/// Options for [`parse_with_options`](crate::parse_with_options). More
+/// options can be added in the future, so the options are created with
+/// [`Default::default`] and the `with_` methods.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ParseOptions {
+  pub spec_version: SpecVersion,
+}
+
+impl ParseOptions {
+  pub fn with_spec_version(mut self, spec_version: SpecVersion) -> Self {
+    self.spec_version = spec_version;
+    self
+  }
+}
+
+/// Options for [`format_source`](crate::format_source).
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+  /// The number of spaces the variants of a matcher are indented by.
+  pub indent_width: usize,
+  /// Whether the keys of the variants of a matcher are aligned into columns.
+  pub align_keys: bool,
+}
+
+impl Default for FormatOptions {
+  fn default() -> Self {
+    FormatOptions {
+      indent_width: 2,
+      align_keys: true,
+    }
+  }
+}
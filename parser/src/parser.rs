@@ -1,145 +1,254 @@
 use std::ops::Range;
+use std::sync::Arc;
 
-use crate::ast::Annotation;
-use crate::ast::AnnotationExpression;
-use crate::ast::Attribute;
-use crate::ast::ComplexMessage;
-use crate::ast::ComplexMessageBody;
-use crate::ast::Declaration;
-use crate::ast::Escape;
-use crate::ast::ExponentSign;
-use crate::ast::Expression;
-use crate::ast::FnOrMarkupOption;
-use crate::ast::Function;
 use crate::ast::Identifier;
-use crate::ast::InputDeclaration;
-use crate::ast::Key;
-use crate::ast::Literal;
-use crate::ast::LiteralExpression;
-use crate::ast::LiteralOrVariable;
-use crate::ast::LocalDeclaration;
-use crate::ast::Markup;
-use crate::ast::MarkupKind;
-use crate::ast::Matcher;
-use crate::ast::Message;
 use crate::ast::Number;
-use crate::ast::Pattern;
-use crate::ast::PatternPart;
-use crate::ast::PrivateUseAnnotation;
-use crate::ast::Quoted;
-use crate::ast::QuotedPart;
-use crate::ast::QuotedPattern;
-use crate::ast::ReservedAnnotation;
-use crate::ast::ReservedBodyPart;
-use crate::ast::ReservedStatement;
-use crate::ast::Star;
-use crate::ast::Text;
-use crate::ast::Variable;
-use crate::ast::VariableExpression;
-use crate::ast::Variant;
 use crate::chars;
+use crate::cst::build_tree;
+use crate::cst::Event;
+use crate::cst::GreenNode;
+use crate::cst::SyntaxKind;
 use crate::diagnostic::Diagnostic;
-use crate::util::LengthShort;
+use crate::options::ParseOptions;
 use crate::util::Location;
 use crate::util::SourceTextInfo;
 use crate::util::SourceTextIterator;
 use crate::Span;
 use crate::Spanned as _;
 
+/// The parser turns the source text into a lossless concrete syntax tree (see
+/// [`crate::cst`]), and reports diagnostics along the way. The AST is derived
+/// from the concrete syntax tree afterwards.
+///
+/// While parsing, the parser emits a flat list of events (start node, token,
+/// finish node). Characters are consumed one by one, and are emitted as a
+/// token once the parser knows what kind of token they form. All characters
+/// between the end of the last emitted token and the current location are
+/// "pending", and are part of the next emitted token.
 pub struct Parser<'a> {
+  input: &'a str,
   text: SourceTextIterator<'a>,
   diagnostics: Vec<Diagnostic<'a>>,
+  options: ParseOptions,
+  events: Vec<Event>,
+  token_start: Location,
 }
 
 impl<'a> Parser<'a> {
-  pub fn new(input: &'a str) -> Self {
+  pub fn new(input: &'a str, options: ParseOptions) -> Self {
     Self {
+      input,
       text: SourceTextIterator::new(input),
       diagnostics: vec![],
+      options,
+      events: vec![],
+      token_start: Location::dummy(),
     }
   }
 
   pub fn parse(
     mut self,
-  ) -> (Message<'a>, Vec<Diagnostic<'a>>, SourceTextInfo<'a>) {
+  ) -> (Arc<GreenNode>, Vec<Diagnostic<'a>>, SourceTextInfo<'a>) {
+    self.start_node(SyntaxKind::Root);
+    self.parse_message();
+
+    // Trailing content after the body of a complex message is not consumed
+    // while parsing, but it still has to be part of the tree.
+    while self.next().is_some() {}
+    if self.token_start != self.current_location() {
+      self.token(SyntaxKind::Error);
+    }
+    self.finish_node_with_trivia();
+
+    let green = build_tree(self.input, self.events);
+    (green, self.diagnostics, self.text.into_info())
+  }
+
+  fn parse_message(&mut self) {
     while let Some((_, c)) = self.peek() {
       match c {
         chars::space!() => {
           self.next();
         }
+        c if self.is_bidi(c) => {
+          self.next();
+        }
 
         chars::content!() | '@' | '|' // simple-start-char
          | '\\' // escaped-char
          | '\0' | '}' // error recovery
         => {
-          return (
-            Message::Simple(self.parse_pattern(self.text.start_location(), false)),
-            self.diagnostics,
-            self.text.into_info(),
-          )
+          self.parse_pattern(self.text.start_location(), false);
+          return;
         }
         '{' => {
           // This could now either be a quoted pattern (so a complex message),
           // or a placeholder (so a simple message).
           match self.peek2() {
             Some((_, '{')) => {
-              return (
-                Message::Complex(self.parse_complex_message()),
-                self.diagnostics,
-                self.text.into_info(),
-              )
+              self.flush_trivia();
+              self.parse_complex_message();
+              return;
             }
             _ => {
-              return (
-                Message::Simple(self.parse_pattern(self.text.start_location(), false)),
-                self.diagnostics,
-                self.text.into_info(),
-              )
+              self.parse_pattern(self.text.start_location(), false);
+              return;
             }
           }
         }
         '.' => {
-          return (
-            Message::Complex(self.parse_complex_message()),
-            self.diagnostics,
-            self.text.into_info(),
-          )
+          self.flush_trivia();
+          self.parse_complex_message();
+          return;
         }
       }
     }
 
-    let start = self.text.start_location();
-    let end = self.text.end_location();
-
-    (
-      Message::Simple(Pattern {
-        parts: vec![PatternPart::Text(self.slice_text(start..end))],
-      }),
-      self.diagnostics,
-      self.text.into_info(),
-    )
+    // The message only consists of whitespace, which is all text.
+    self.start_node(SyntaxKind::Pattern);
+    self.token(SyntaxKind::Text);
+    self.finish_node();
   }
 
   fn current_location(&self) -> Location {
     self.text.current_location()
   }
 
-  fn slice_text(&self, range: Range<Location>) -> Text<'a> {
-    let start = range.start;
-    let content = self.text.slice(range);
-    Text { start, content }
-  }
-
   fn report(&mut self, diagnostic: Diagnostic<'a>) {
     self.diagnostics.push(diagnostic);
   }
 
+  /// Starts a new node, and returns a handle that can be used to change the
+  /// kind of the node before it is finished.
+  fn start_node(&mut self, kind: SyntaxKind) -> usize {
+    self.events.push(Event::StartNode { kind });
+    self.events.len() - 1
+  }
+
+  fn set_node_kind(&mut self, node: usize, new_kind: SyntaxKind) {
+    if let Event::StartNode { kind } = &mut self.events[node] {
+      *kind = new_kind;
+    }
+  }
+
+  /// Removes a node that was started, but that turned out to be empty.
+  fn abandon_node(&mut self, node: usize) {
+    debug_assert_eq!(node, self.events.len() - 1);
+    self.events.truncate(node);
+  }
+
+  /// Finishes the current node. Trailing whitespace and bidi marks are moved
+  /// out of the node, so that the node ends at its last significant token.
+  fn finish_node(&mut self) {
+    debug_assert_eq!(self.token_start, self.current_location());
+    let mut trivia = vec![];
+    while let Some(Event::Token { kind, .. }) = self.events.last() {
+      if !kind.is_trivia() {
+        break;
+      }
+      trivia.push(self.events.pop().unwrap());
+    }
+    self.events.push(Event::FinishNode);
+    self.events.extend(trivia.into_iter().rev());
+  }
+
+  /// Finishes the current node, keeping trailing whitespace and bidi marks
+  /// inside of it. This is used for nodes that are delimited by braces, which
+  /// extend to the end of the input if they are not closed.
+  fn finish_node_with_trivia(&mut self) {
+    debug_assert_eq!(self.token_start, self.current_location());
+    self.events.push(Event::FinishNode);
+  }
+
+  /// Emits all pending characters as a single token.
+  fn token(&mut self, kind: SyntaxKind) {
+    self.token_to(kind, self.current_location());
+  }
+
+  /// Emits the pending characters up to `end` as a single token.
+  fn token_to(&mut self, kind: SyntaxKind, end: Location) {
+    let len = end.inner() - self.token_start.inner();
+    self.token_start = end;
+    // Consecutive invalid characters and whitespace are merged into a single
+    // token.
+    if kind.is_trivia() || kind == SyntaxKind::Error {
+      if let Some(Event::Token {
+        kind: last_kind,
+        len: last_len,
+      }) = self.events.last_mut()
+      {
+        if *last_kind == kind {
+          *last_len += len;
+          return;
+        }
+      }
+    }
+    self.events.push(Event::Token { kind, len });
+  }
+
+  /// Emits the pending characters, which must all be whitespace or bidi
+  /// marks, as trivia tokens.
+  fn flush_trivia(&mut self) {
+    let pending = self.text.slice(self.token_start..self.current_location());
+    let mut loc = self.token_start;
+    for c in pending.chars() {
+      let kind = match c {
+        chars::space!() => SyntaxKind::Whitespace,
+        _ => {
+          debug_assert!(self.is_bidi(c));
+          SyntaxKind::Bidi
+        }
+      };
+      loc = loc + c;
+      self.token_to(kind, loc);
+    }
+  }
+
+  /// Consumes the next character and emits it as a token.
+  fn bump(&mut self, kind: SyntaxKind) -> Location {
+    debug_assert_eq!(self.token_start, self.current_location());
+    let (loc, _) = self.next().unwrap();
+    self.token(kind);
+    loc
+  }
+
+  /// Wraps everything parsed by `parse` in an error node.
+  fn parse_invalid(&mut self, parse: impl FnOnce(&mut Self)) {
+    self.start_node(SyntaxKind::Error);
+    parse(self);
+    self.finish_node();
+  }
+
+  /// Resets the parser to an earlier location. Only tokens can have been
+  /// emitted since that location.
+  fn reset_to(&mut self, loc: Location) {
+    while self.token_start > loc {
+      match self.events.pop() {
+        Some(Event::Token { len, .. }) => {
+          self.token_start = Location::new(self.token_start.inner() - len);
+        }
+        _ => unreachable!("only tokens can be reset"),
+      }
+    }
+    debug_assert_eq!(self.token_start, loc);
+    self.text.reset_to(loc);
+  }
+
+  /// Parses a pattern, returning the span from the start of the first part to
+  /// the end of the last part, if the pattern has any parts.
   fn parse_pattern(
     &mut self,
     mut start: Location,
     inside_quoted: bool,
-  ) -> Pattern<'a> {
-    let mut parts = vec![];
+  ) -> Option<Span> {
+    self.start_node(SyntaxKind::Pattern);
+
+    let mut parts_start = None;
+    let mut parts_end = None;
+    let mut add_part = |range: Range<Location>| {
+      parts_start.get_or_insert(range.start);
+      parts_end = Some(range.end);
+    };
 
     let mut open_quoted_patterns = vec![];
 
@@ -147,11 +256,11 @@ impl<'a> Parser<'a> {
       match c {
         '\\' => {
           if loc != start {
-            parts.push(PatternPart::Text(self.slice_text(start..loc)));
+            self.token(SyntaxKind::Text);
+            add_part(start..loc);
           }
-          let escape = self.parse_escape();
-          if let Some(escape) = escape {
-            parts.push(PatternPart::Escape(escape));
+          if self.parse_escape() {
+            add_part(loc..self.current_location());
           }
           start = self.current_location();
         }
@@ -162,9 +271,11 @@ impl<'a> Parser<'a> {
             open_quoted_patterns.push(loc);
           } else {
             if loc != start {
-              parts.push(PatternPart::Text(self.slice_text(start..loc)));
+              self.token(SyntaxKind::Text);
+              add_part(start..loc);
             }
-            parts.push(self.parse_placeholder());
+            self.parse_placeholder();
+            add_part(loc..self.current_location());
             start = self.current_location();
           }
         }
@@ -211,64 +322,71 @@ impl<'a> Parser<'a> {
 
     let end = self.current_location();
     if end != start {
-      parts.push(PatternPart::Text(self.slice_text(start..end)));
+      self.token(SyntaxKind::Text);
+      add_part(start..end);
     }
 
-    Pattern { parts }
+    self.finish_node();
+
+    Some(Span::new(parts_start?..parts_end?))
   }
 
-  fn parse_escape(&mut self) -> Option<Escape> {
+  /// Parses an escape sequence. Returns `false` if the backslash is not
+  /// followed by any character, in which case it is emitted as an error.
+  fn parse_escape(&mut self) -> bool {
     let (start, c) = self.next().unwrap(); // consume '\'
     debug_assert_eq!(c, '\\');
 
-    let escaped_char = match self.next() {
-      Some((_, c @ ('}' | '{' | '|' | '\\'))) => c,
+    match self.next() {
+      Some((_, '}' | '{' | '|' | '\\')) => {}
       Some((loc, c)) => {
         self.report(Diagnostic::EscapeInvalidCharacter {
           char: c,
           char_loc: loc,
         });
-        c
       }
       None => {
         self.report(Diagnostic::EscapeMissingCharacter { slash_loc: start });
-        return None;
+        self.token(SyntaxKind::Error);
+        return false;
       }
     };
 
-    Some(Escape {
-      start,
-      escaped_char,
-    })
+    self.token(SyntaxKind::Escape);
+    true
   }
 
-  fn parse_placeholder(&mut self) -> PatternPart<'a> {
-    let (start, c) = self.next().unwrap(); // consume '{'
-    debug_assert_eq!(c, '{');
+  fn parse_placeholder(&mut self) -> Span {
+    let node = self.start_node(SyntaxKind::LiteralExpression);
+    let start = self.bump(SyntaxKind::OpenBrace);
 
     self.skip_spaces();
 
     match self.peek() {
       Some((_, '#')) => {
-        return PatternPart::Markup(
-          self.parse_markup(start, MarkupStartKind::OpenOrStandalone),
-        )
-      }
-      Some((_, '/')) => {
-        return PatternPart::Markup(
-          self.parse_markup(start, MarkupStartKind::Close),
-        )
+        self.parse_markup(node, start, MarkupStartKind::OpenOrStandalone)
       }
-      _ => {}
+      Some((_, '/')) => self.parse_markup(node, start, MarkupStartKind::Close),
+      _ => self.parse_expression(node, start).1,
     }
+  }
 
-    PatternPart::Expression(self.parse_expression(start))
+  /// Parses an expression, starting at its opening `{`.
+  fn parse_braced_expression(&mut self) -> (SyntaxKind, Span) {
+    let node = self.start_node(SyntaxKind::LiteralExpression);
+    let start = self.bump(SyntaxKind::OpenBrace);
+    self.skip_spaces();
+    self.parse_expression(node, start)
   }
 
-  // Caller must consume the opening `{` before calling this function and pass
-  // the location of the opening `{` as `start`. The caller must also consume
-  // spaces after the opening `{` before calling this function.
-  fn parse_expression(&mut self, start: Location) -> Expression<'a> {
+  // Caller must start the expression node, consume the opening `{` and the
+  // spaces after it before calling this function, and pass the location of
+  // the opening `{` as `start`.
+  fn parse_expression(
+    &mut self,
+    node: usize,
+    start: Location,
+  ) -> (SyntaxKind, Span) {
     let lit_or_var = self.parse_literal_or_variable();
 
     let had_space_before_annotation =
@@ -276,20 +394,16 @@ impl<'a> Parser<'a> {
     let mut had_space = false;
 
     let annotation = self.maybe_parse_annotation(&mut had_space);
-    if let Some(ref annotation) = annotation {
+    if let Some(span) = annotation {
       if !had_space_before_annotation {
-        self.report(Diagnostic::AnnotationMissingSpaceBefore {
-          span: annotation.span(),
-        });
+        self.report(Diagnostic::AnnotationMissingSpaceBefore { span });
       }
     } else {
       had_space = had_space || had_space_before_annotation;
     }
 
-    let mut attributes = vec![];
-
     while let Some((start, '@')) = self.peek() {
-      attributes.push(self.parse_attribute(start, &mut had_space));
+      self.parse_attribute(start, &mut had_space);
     }
 
     let contents_end = self.current_location();
@@ -298,22 +412,30 @@ impl<'a> Parser<'a> {
     loop {
       match self.peek() {
         Some((_, '|')) => {
-          self.parse_quoted();
+          self.parse_invalid(|this| {
+            this.parse_quoted();
+          });
           after_invalid = Some(self.current_location());
         }
         Some((_, '}')) => {
-          self.next();
+          self.bump(SyntaxKind::CloseBrace);
           break;
         }
         Some((_, chars::space!())) => {
-          self.next();
+          self.skip_spaces();
+        }
+        Some((_, c)) if self.is_bidi(c) => {
+          self.skip_spaces();
         }
         Some((_, '\\')) => {
-          self.parse_escape();
+          self.parse_invalid(|this| {
+            this.parse_escape();
+          });
           after_invalid = Some(self.current_location());
         }
         Some(_) => {
           self.next();
+          self.token(SyntaxKind::Error);
           after_invalid = Some(self.current_location());
         }
         None => {
@@ -334,71 +456,54 @@ impl<'a> Parser<'a> {
     let end = self.current_location();
     let span = Span::new(start..end);
 
-    match lit_or_var {
-      Some(LiteralOrVariable::Variable(variable)) => {
-        Expression::VariableExpression(VariableExpression {
-          span,
-          variable,
-          annotation,
-          attributes,
-        })
-      }
-      Some(LiteralOrVariable::Literal(literal)) => {
-        Expression::LiteralExpression(LiteralExpression {
-          span,
-          literal,
-          annotation,
-          attributes,
-        })
-      }
+    let kind = match lit_or_var {
+      Some((SyntaxKind::Variable, _)) => SyntaxKind::VariableExpression,
+      Some(_) => SyntaxKind::LiteralExpression,
+      None if annotation.is_some() => SyntaxKind::AnnotationExpression,
       None => {
-        if let Some(annotation) = annotation {
-          Expression::AnnotationExpression(AnnotationExpression {
-            span,
-            annotation,
-            attributes,
-          })
-        } else {
-          self.report(Diagnostic::PlaceholderMissingBody { span });
-
-          // We recover from this by injecting a literal expression with an
-          // empty text as its literal.
-          Expression::LiteralExpression(LiteralExpression {
-            span,
-            literal: Literal::Text(Text {
-              start: span.start,
-              content: "",
-            }),
-            annotation: None,
-            attributes,
-          })
-        }
+        // We recover from this by deriving a literal expression with an
+        // empty text as its literal.
+        self.report(Diagnostic::PlaceholderMissingBody { span });
+        SyntaxKind::LiteralExpression
       }
-    }
+    };
+    self.set_node_kind(node, kind);
+    self.finish_node_with_trivia();
+
+    (kind, span)
   }
 
-  fn parse_literal_or_variable(&mut self) -> Option<LiteralOrVariable<'a>> {
-    let value = match self.peek() {
-      Some((_, '$')) => LiteralOrVariable::Variable(self.parse_variable()),
+  /// Parses a literal or variable, returning the kind of element that was
+  /// emitted for it, and its span.
+  fn parse_literal_or_variable(&mut self) -> Option<(SyntaxKind, Span)> {
+    let start = self.current_location();
+    let kind = match self.peek() {
+      Some((_, '$')) => {
+        self.parse_variable();
+        SyntaxKind::Variable
+      }
       Some((_, '|')) => {
-        LiteralOrVariable::Literal(Literal::Quoted(self.parse_quoted()))
+        self.parse_quoted();
+        SyntaxKind::Quoted
       }
       Some((_, chars::name_start!())) => {
-        LiteralOrVariable::Literal(Literal::Text(self.parse_literal_name()))
+        self.parse_literal_name();
+        SyntaxKind::Name
       }
       // '.' is for error recovery of a fractional number literal that is missing the integral part
       // fixme: only allow '.' if the character after is a digit
       Some((_, '-' | '.' | '0'..='9')) => {
-        LiteralOrVariable::Literal(Literal::Number(self.parse_number()))
+        self.parse_number();
+        SyntaxKind::Number
       }
       _ => return None,
     };
-    Some(value)
+    Some((kind, Span::new(start..self.current_location())))
   }
 
-  fn parse_variable(&mut self) -> Variable<'a> {
-    let (start, n) = self.next().unwrap(); // consume '$'
-    debug_assert_eq!(n, '$');
+  fn parse_variable(&mut self) -> Span {
+    self.start_node(SyntaxKind::Variable);
+    let start = self.bump(SyntaxKind::Dollar);
 
     let name = self.parse_name();
     let span = Span::new(start..self.current_location());
@@ -407,16 +512,13 @@ impl<'a> Parser<'a> {
       self.report(Diagnostic::VariableMissingName { span });
     }
 
-    Variable { span, name }
+    self.finish_node();
+    span
   }
 
-  fn parse_attribute(
-    &mut self,
-    start: Location,
-    had_space: &mut bool,
-  ) -> Attribute<'a> {
-    let c = self.next();
-    debug_assert!(matches!(c, Some((_, '@'))));
+  fn parse_attribute(&mut self, start: Location, had_space: &mut bool) -> Span {
+    self.start_node(SyntaxKind::Attribute);
+    self.bump(SyntaxKind::At);
 
     let report_missing_space_before_attribute = !*had_space;
     if self.skip_spaces() {
@@ -425,29 +527,32 @@ impl<'a> Parser<'a> {
       });
     }
 
-    let (key, is_key_empty) = self.parse_identifier();
+    let (_, is_key_empty) = self.parse_identifier();
 
     let mut end = self.current_location();
     *had_space = self.skip_spaces();
 
-    let value = self.eat('=').and_then(|_| {
+    if self.eat_token('=', SyntaxKind::Equals).is_some() {
       end = self.current_location();
       *had_space = self.skip_spaces();
 
       match self.parse_literal_or_variable() {
-        Some(v) => {
+        Some((kind, span)) => {
+          if kind == SyntaxKind::Variable
+            && !self.options.spec_version.has_variable_attribute_values()
+          {
+            self.report(Diagnostic::AttributeValueIsVariable { span });
+          }
           end = self.current_location();
           *had_space = self.skip_spaces();
-          Some(v)
         }
         None => {
           self.report(Diagnostic::AttributeMissingValue {
             span: Span::new(start..end),
           });
-          None
         }
       }
-    });
+    }
 
     let span = Span::new(start..end);
 
@@ -459,27 +564,29 @@ impl<'a> Parser<'a> {
       self.report(Diagnostic::AttributeMissingKey { span });
     }
 
-    Attribute { span, key, value }
+    self.finish_node();
+    span
   }
 
   // Returns the identifier and a boolean indicating if the identifier is empty.
   // The caller should report an error if the identifier is empty.
   fn parse_identifier(&mut self) -> (Identifier<'a>, bool) {
+    self.start_node(SyntaxKind::Identifier);
     let start = self.current_location();
     let name_or_namespace = self.parse_name();
 
-    let id = if self.eat(':').is_some() {
+    let id = if self.eat_token(':', SyntaxKind::Colon).is_some() {
       let name = self.parse_name();
       Identifier {
         start,
-        namespace: Some(name_or_namespace),
-        name,
+        namespace: Some(name_or_namespace.into()),
+        name: name.into(),
       }
     } else {
       Identifier {
         start,
         namespace: None,
-        name: name_or_namespace,
+        name: name_or_namespace.into(),
       }
     };
 
@@ -490,23 +597,49 @@ impl<'a> Parser<'a> {
         identifier: id.clone(),
       });
     }
-    if matches!(id.namespace, Some(s) if s.is_empty()) {
+    if matches!(&id.namespace, Some(s) if s.is_empty()) {
       self.report(Diagnostic::MissingIdentifierNamespace {
         identifier: id.clone(),
       });
     }
 
+    self.finish_node();
+
     let is_empty = id.namespace.is_none() && id.name.is_empty();
     (id, is_empty)
   }
 
-  fn skip_name(&mut self) {
+  fn skip_name_chars(&mut self) -> bool {
     if let Some((_, chars::name_start!())) = self.peek() {
       self.next();
 
       while let Some((_, chars::name!())) = self.peek() {
         self.next();
       }
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Skips a name, including a single bidi mark or isolate at the start and
+  /// at the end of the name. The bidi marks are only skipped if the name is
+  /// not empty.
+  fn skip_name(&mut self) {
+    if let (Some((_, c)), Some((_, chars::name_start!()))) =
+      (self.peek(), self.peek2())
+    {
+      if self.is_bidi(c) {
+        self.next();
+      }
+    }
+
+    if self.skip_name_chars() {
+      if let Some((_, c)) = self.peek() {
+        if self.is_bidi(c) {
+          self.next();
+        }
+      }
     }
   }
 
@@ -516,15 +649,25 @@ impl<'a> Parser<'a> {
     self.skip_name();
     let end = self.current_location();
 
+    if end != start {
+      self.token(SyntaxKind::Name);
+    }
     self.text.slice(start..end)
   }
 
-  fn parse_literal_name(&mut self) -> Text<'a> {
+  // Unlike names, keywords can not be surrounded by bidi marks. The caller
+  // must emit the keyword token.
+  fn parse_keyword(&mut self) -> &'a str {
     let start = self.current_location();
-    self.skip_name();
+    self.skip_name_chars();
     let end = self.current_location();
 
-    self.slice_text(start..end)
+    self.text.slice(start..end)
+  }
+
+  fn parse_literal_name(&mut self) {
+    self.skip_name();
+    self.token(SyntaxKind::Name);
   }
 
   fn next(&mut self) -> Option<(Location, char)> {
@@ -539,6 +682,7 @@ impl<'a> Parser<'a> {
     self.text.peek2()
   }
 
+  /// Consumes the next character if it is `c`, without emitting a token.
   fn eat(&mut self, c: char) -> Option<Location> {
     if let Some((loc, ch)) = self.text.peek() {
       if ch == c {
@@ -549,27 +693,47 @@ impl<'a> Parser<'a> {
     None
   }
 
+  /// Consumes the next character if it is `c`, and emits it as a token.
+  fn eat_token(&mut self, c: char, kind: SyntaxKind) -> Option<Location> {
+    let loc = self.eat(c)?;
+    self.token(kind);
+    Some(loc)
+  }
+
+  fn is_bidi(&self, c: char) -> bool {
+    self.options.spec_version.has_bidi() && matches!(c, chars::bidi!())
+  }
+
+  /// Skips whitespace and bidi marks. Returns `true` if any whitespace was
+  /// skipped, as bidi marks alone are not a valid separator.
   fn skip_spaces(&mut self) -> bool {
     let mut any_spaces = false;
-    while let Some((_, chars::space!())) = self.peek() {
-      any_spaces = true;
-      self.next();
+    loop {
+      match self.peek() {
+        Some((_, chars::space!())) => {
+          any_spaces = true;
+          self.next();
+        }
+        Some((_, c)) if self.is_bidi(c) => {
+          self.next();
+        }
+        _ => break,
+      }
     }
+    self.flush_trivia();
     any_spaces
   }
 
-  fn maybe_parse_annotation(
-    &mut self,
-    had_space: &mut bool,
-  ) -> Option<Annotation<'a>> {
+  /// Parses an annotation if there is one, and returns its span.
+  fn maybe_parse_annotation(&mut self, had_space: &mut bool) -> Option<Span> {
     match self.peek() {
       Some((start, ':')) => {
         // function
-        self.next(); // consume ':'
+        self.start_node(SyntaxKind::Function);
+        self.bump(SyntaxKind::Colon);
 
         let (id, is_id_empty) = self.parse_identifier();
-
-        let mut options = vec![];
+        let mut end = id.span().end;
 
         loop {
           *had_space = self.skip_spaces();
@@ -587,88 +751,86 @@ impl<'a> Parser<'a> {
             break;
           }
 
-          options.push(self.parse_option());
+          end = self.parse_option().end;
         }
 
-        let function = Function { start, id, options };
+        let span = Span::new(start..end);
 
         if is_id_empty {
-          self.report(Diagnostic::FunctionMissingIdentifier {
-            span: function.span(),
-          });
+          self.report(Diagnostic::FunctionMissingIdentifier { span });
         }
 
-        Some(Annotation::Function(function))
+        self.finish_node();
+        Some(span)
       }
       Some((start, sigil @ ('^' | '&'))) => {
         // private-use-annotation
-        self.next(); // consume start
+        self.start_node(SyntaxKind::PrivateUseAnnotation);
+        self.bump(SyntaxKind::Sigil);
 
-        let reserved_body = self.parse_reserved_body(had_space, false);
+        let body_end = self.parse_reserved_body(had_space, false);
+        let span = Span::new(start..body_end.unwrap_or(start + sigil));
 
-        Some(Annotation::PrivateUseAnnotation(PrivateUseAnnotation {
-          start,
-          sigil,
-          body: reserved_body,
-        }))
+        if !self.options.spec_version.has_reserved_syntax() {
+          self.report(Diagnostic::PrivateUseAnnotationNotAllowed { span });
+        }
+
+        self.finish_node();
+        Some(span)
       }
       Some((
         start,
         sigil @ ('!' | '%' | '*' | '+' | '<' | '>' | '?' | '~'),
       )) => {
         // reserved annotation
-        self.next(); // consume start
+        self.start_node(SyntaxKind::ReservedAnnotation);
+        self.bump(SyntaxKind::Sigil);
 
-        let reserved_body = self.parse_reserved_body(had_space, false);
+        let body_end = self.parse_reserved_body(had_space, false);
+        let span = Span::new(start..body_end.unwrap_or(start + sigil));
+
+        if !self.options.spec_version.has_reserved_syntax() {
+          self.report(Diagnostic::ReservedAnnotationNotAllowed { span });
+        }
 
-        Some(Annotation::ReservedAnnotation(ReservedAnnotation {
-          start,
-          sigil,
-          body: reserved_body,
-        }))
+        self.finish_node();
+        Some(span)
       }
       _ => None,
     }
   }
 
-  fn parse_option(&mut self) -> FnOrMarkupOption<'a> {
+  fn parse_option(&mut self) -> Span {
+    self.start_node(SyntaxKind::Option);
     let (key, is_key_empty) = self.parse_identifier();
     self.skip_spaces();
-    let value = if let Some(equals_loc) = self.eat('=') {
+    if let Some(equals_loc) = self.eat_token('=', SyntaxKind::Equals) {
       self.skip_spaces();
-      self.parse_literal_or_variable().unwrap_or_else(|| {
-        self.text.reset_to(equals_loc + '='); // un-eat the spaces after the equals
+      if self.parse_literal_or_variable().is_none() {
+        self.reset_to(equals_loc + '='); // un-eat the spaces after the equals
         self.report(Diagnostic::OptionMissingValue {
           span: Span::new(key.start..self.current_location()),
         });
-        LiteralOrVariable::Literal(Literal::Text(Text {
-          start: self.current_location(),
-          content: "",
-        }))
-      })
+      }
     } else {
-      self.text.reset_to(key.span().end); // un-eat the spaces after the identifier
+      self.reset_to(key.span().end); // un-eat the spaces after the identifier
       self.report(Diagnostic::OptionMissingValue {
         span: Span::new(key.start..self.current_location()),
       });
-      LiteralOrVariable::Literal(Literal::Text(Text {
-        start: self.current_location(),
-        content: "",
-      }))
-    };
+    }
 
-    let option = FnOrMarkupOption { key, value };
+    // A missing value is derived as an empty text at the end of the option.
+    let span = Span::new(key.start..self.current_location());
 
     if is_key_empty {
-      self.report(Diagnostic::OptionMissingKey {
-        span: option.span(),
-      })
+      self.report(Diagnostic::OptionMissingKey { span })
     }
 
-    option
+    self.finish_node();
+    span
   }
 
-  /// Parses a reserved body.
+  /// Parses a reserved body, and returns the end of its last part.
   ///
   /// The `bail_on_dot` parameter is used to determine if the function should stop parsing when it
   /// encounters a dot that is preceded by a non-name character.
@@ -676,10 +838,9 @@ impl<'a> Parser<'a> {
     &mut self,
     had_space: &mut bool,
     bail_on_dot: bool,
-  ) -> Vec<ReservedBodyPart<'a>> {
-    let mut parts = vec![];
-
+  ) -> Option<Location> {
     let mut start = self.current_location();
+    let mut last_part_end = None;
     let mut last_space_start = None;
     let mut had_name = false;
 
@@ -707,11 +868,11 @@ impl<'a> Parser<'a> {
         }
         '\\' => {
           if loc != start {
-            parts.push(ReservedBodyPart::Text(self.slice_text(start..loc)));
+            self.token(SyntaxKind::Text);
+            last_part_end = Some(loc);
           }
-          let escape = self.parse_escape();
-          if let Some(escape) = escape {
-            parts.push(ReservedBodyPart::Escape(escape));
+          if self.parse_escape() {
+            last_part_end = Some(self.current_location());
           }
           start = self.current_location();
           last_space_start = None;
@@ -719,9 +880,10 @@ impl<'a> Parser<'a> {
         }
         '|' => {
           if loc != start {
-            parts.push(ReservedBodyPart::Text(self.slice_text(start..loc)));
+            self.token(SyntaxKind::Text);
           }
-          parts.push(ReservedBodyPart::Quoted(self.parse_quoted()));
+          self.parse_quoted();
+          last_part_end = Some(self.current_location());
           start = self.current_location();
           last_space_start = None;
           had_name = false;
@@ -738,16 +900,17 @@ impl<'a> Parser<'a> {
     };
 
     if end != start {
-      parts.push(ReservedBodyPart::Text(self.slice_text(start..end)));
+      self.token_to(SyntaxKind::Text, end);
+      last_part_end = Some(end);
     }
+    self.flush_trivia();
 
-    parts
+    last_part_end
   }
 
-  fn parse_quoted(&mut self) -> Quoted<'a> {
-    let (open, c) = self.next().unwrap(); // consume '|'
-    debug_assert_eq!(c, '|');
-    let mut parts = vec![];
+  fn parse_quoted(&mut self) -> Span {
+    self.start_node(SyntaxKind::Quoted);
+    let open = self.bump(SyntaxKind::Pipe);
 
     let mut start = self.current_location();
 
@@ -755,17 +918,14 @@ impl<'a> Parser<'a> {
       match ch {
         '\\' => {
           if start != loc {
-            parts.push(QuotedPart::Text(self.slice_text(start..loc)));
-          }
-          let escape = self.parse_escape();
-          if let Some(escape) = escape {
-            parts.push(QuotedPart::Escape(escape));
+            self.token(SyntaxKind::Text);
           }
+          self.parse_escape();
           start = self.current_location();
         }
         '|' => {
           if start != loc {
-            parts.push(QuotedPart::Text(self.slice_text(start..loc)));
+            self.token(SyntaxKind::Text);
           }
           break;
         }
@@ -779,19 +939,25 @@ impl<'a> Parser<'a> {
       }
     }
 
-    let maybe_close = self.eat('|');
+    // The text of an unterminated quoted literal is not part of its value.
+    if self.token_start != self.current_location() {
+      self.token(SyntaxKind::Error);
+    }
+
+    let maybe_close = self.eat_token('|', SyntaxKind::Pipe);
     let span = Span::new(open..self.current_location());
 
     if maybe_close.is_none() {
       self.report(Diagnostic::UnterminatedQuoted { span });
     }
 
-    Quoted { span, parts }
+    self.finish_node();
+    span
   }
 
-  fn parse_number(&mut self) -> Number<'a> {
+  fn parse_number(&mut self) -> Span {
     let start = self.current_location();
-    let is_negative = self.eat('-').is_some();
+    self.eat('-');
 
     let integral_part = self.parse_digits();
 
@@ -803,29 +969,18 @@ impl<'a> Parser<'a> {
 
     let exponent_part = if let Some((_, 'e' | 'E')) = self.peek() {
       self.next(); // consume 'e' or 'E'
-      let sign = if self.eat('-').is_some() {
-        ExponentSign::Minus
-      } else if self.eat('+').is_some() {
-        ExponentSign::Plus
-      } else {
-        ExponentSign::None
-      };
-      Some((sign, self.parse_digits()))
+      if self.eat('-').is_none() {
+        self.eat('+');
+      }
+      Some(self.parse_digits())
     } else {
       None
     };
 
     let end = self.current_location();
+    self.token(SyntaxKind::Number);
 
-    let num = Number {
-      start,
-      raw: self.text.slice(start..end),
-      is_negative,
-      integral_len: LengthShort::new_from_str(integral_part),
-      fractional_len: fractional_part.map(LengthShort::new_from_str),
-      exponent_len: exponent_part
-        .map(|c| (c.0, LengthShort::new_from_str(c.1))),
-    };
+    let num = Number::new(start, self.text.slice(start..end));
 
     if integral_part.len() > 1 && integral_part.starts_with('0') {
       self.report(Diagnostic::NumberLeadingZeroIntegralPart {
@@ -842,13 +997,11 @@ impl<'a> Parser<'a> {
         number: num.clone(),
       });
     }
-    if matches!(exponent_part, Some((_, s)) if s.is_empty()) {
-      self.report(Diagnostic::NumberMissingExponentPart {
-        number: num.clone(),
-      });
+    if matches!(exponent_part, Some(s) if s.is_empty()) {
+      self.report(Diagnostic::NumberMissingExponentPart { number: num });
     }
 
-    num
+    Span::new(start..end)
   }
 
   // Caller must handle empty digits, and leading zero
@@ -861,31 +1014,40 @@ impl<'a> Parser<'a> {
     self.text.slice(start..end)
   }
 
+  // Caller must start the markup node, consume the opening `{` and the spaces
+  // after it before calling this function.
   fn parse_markup(
     &mut self,
+    node: usize,
     open: Location,
     kind: MarkupStartKind,
-  ) -> Markup<'a> {
-    let c = self.next();
-    debug_assert!(matches!(c, Some((_, '#' | '/'))));
+  ) -> Span {
+    self.set_node_kind(node, SyntaxKind::Markup);
+    let is_close = match kind {
+      MarkupStartKind::OpenOrStandalone => {
+        self.bump(SyntaxKind::Hash);
+        false
+      }
+      MarkupStartKind::Close => {
+        self.bump(SyntaxKind::Slash);
+        true
+      }
+    };
 
-    let (id, is_id_empty) = self.parse_identifier();
+    let (_, is_id_empty) = self.parse_identifier();
 
-    let mut markup_kind = match kind {
-      MarkupStartKind::OpenOrStandalone => MarkupKind::Open,
-      MarkupStartKind::Close => MarkupKind::Close,
-    };
-    let mut options = vec![];
-    let mut attributes = vec![];
+    let mut previous_attribute = None;
 
     let mut had_space = self.skip_spaces();
     let report_missing_close = 'outer: loop {
       match self.peek() {
         Some((start, '@')) => {
-          attributes.push(self.parse_attribute(start, &mut had_space));
+          previous_attribute =
+            Some(self.parse_attribute(start, &mut had_space));
         }
         Some((self_close, '/')) => {
-          self.next(); // consume '/'
+          self.bump(SyntaxKind::Slash);
+          let self_close_token = self.events.len() - 1;
 
           had_space = self.skip_spaces();
 
@@ -901,40 +1063,45 @@ impl<'a> Parser<'a> {
                 );
               }
 
-              self.next(); // consume '}'
+              self.bump(SyntaxKind::CloseBrace);
               false
             }
             None => true,
             Some(_) => {
+              // The `/` is not a self-close, but the start of invalid
+              // contents.
+              if let Event::Token { kind, .. } =
+                &mut self.events[self_close_token]
+              {
+                *kind = SyntaxKind::Error;
+              }
               self.skip_invalid_markup_contents(self_close, &mut had_space);
               continue 'outer;
             }
           };
 
-          if matches!(markup_kind, MarkupKind::Close) {
+          if is_close {
             self.report(Diagnostic::MarkupCloseInvalidSelfClose {
               self_close_loc: self_close,
             });
           }
-          markup_kind = MarkupKind::Standalone;
 
           break report_missing_close;
         }
         Some((_, '}')) => {
-          self.next(); // consume '}'
+          self.bump(SyntaxKind::CloseBrace);
           break false;
         }
         // also allow : as error recovery for `{#fn a:b=c :d=e}` (missing namespace on option)
         // also allow = as error recovery for {#fn a=b =c} (missing key on option)
         Some((_, chars::name_start!() | ':' | '=')) if had_space => {
           let option = self.parse_option();
-          if let Some(previous_attribute) = attributes.last() {
+          if let Some(previous_attribute) = previous_attribute {
             self.report(Diagnostic::MarkupOptionAfterAttribute {
-              previous_attribute: previous_attribute.clone(),
-              option: option.clone(),
+              previous_attribute,
+              option,
             })
           }
-          options.push(option);
           had_space = self.skip_spaces();
         }
         Some((loc, _)) => {
@@ -946,25 +1113,18 @@ impl<'a> Parser<'a> {
       }
     };
 
-    let end = self.current_location();
-
-    let markup = Markup {
-      span: Span::new(open..end),
-      kind: markup_kind,
-      id,
-      options,
-      attributes,
-    };
+    let span = Span::new(open..self.current_location());
 
     if is_id_empty {
-      self.report(Diagnostic::MarkupMissingIdentifier { span: markup.span })
+      self.report(Diagnostic::MarkupMissingIdentifier { span })
     }
 
     if report_missing_close {
-      self.report(Diagnostic::MarkupMissingClosingBrace { span: markup.span });
+      self.report(Diagnostic::MarkupMissingClosingBrace { span });
     }
 
-    markup
+    self.finish_node_with_trivia();
+    span
   }
 
   fn skip_invalid_markup_contents(
@@ -980,11 +1140,15 @@ impl<'a> Parser<'a> {
           break;
         }
         '\\' => {
-          self.parse_escape();
+          self.parse_invalid(|this| {
+            this.parse_escape();
+          });
           last_space_start = None;
         }
         '|' => {
-          self.parse_quoted();
+          self.parse_invalid(|this| {
+            this.parse_quoted();
+          });
           last_space_start = None;
         }
         '/' | '@' => {
@@ -995,12 +1159,14 @@ impl<'a> Parser<'a> {
             last_space_start = Some(loc);
           }
           self.next();
+          self.flush_trivia();
         }
         chars::name_start!() | ':' | '=' if last_space_start.is_some() => {
           break;
         }
         _ => {
           self.next();
+          self.token(SyntaxKind::Error);
           last_space_start = None;
         }
       }
@@ -1021,57 +1187,60 @@ impl<'a> Parser<'a> {
     }
   }
 
-  fn parse_complex_message(&mut self) -> ComplexMessage<'a> {
-    let mut declarations = vec![];
-    let mut body = None;
+  fn parse_complex_message(&mut self) {
+    self.start_node(SyntaxKind::ComplexMessage);
+    let mut has_body = false;
 
     loop {
       match self.peek() {
         Some((_, chars::space!())) => {
-          self.next();
+          self.skip_spaces();
+        }
+        Some((_, c)) if self.is_bidi(c) => {
+          self.skip_spaces();
         }
         Some((start, '.')) => {
+          let node = self.start_node(SyntaxKind::ReservedStatement);
           self.next(); // consume '.'
-          let name = self.parse_name();
-          let declaration = match name {
-            "input" => {
-              let input = self.parse_input_declaration(start);
-              Declaration::InputDeclaration(input)
-            }
-            "local" => self.parse_local_declaration(start),
+          let name = self.parse_keyword();
+          self.token(SyntaxKind::Keyword);
+          let span = match name {
+            "input" => self.parse_input_declaration(node, start),
+            "local" => self.parse_local_declaration(node, start),
             "match" => {
-              let matcher = self.parse_matcher(start);
-              if body.is_some() {
-                self.report(Diagnostic::ComplexMessageMultipleBodies {
-                  span: matcher.span(),
-                });
+              self.set_node_kind(node, SyntaxKind::Matcher);
+              let span = self.parse_matcher(start);
+              self.finish_node();
+              if has_body {
+                self.report(Diagnostic::ComplexMessageMultipleBodies { span });
               } else {
-                body = Some(ComplexMessageBody::Matcher(matcher));
+                has_body = true;
               }
               continue;
             }
             name => {
-              let reserved = self.parse_reserved_statement(start, name);
-              Declaration::ReservedStatement(reserved)
+              if !self.options.spec_version.has_reserved_syntax() {
+                self.report(Diagnostic::ReservedStatementNotAllowed {
+                  span: Span::new(start..self.current_location()),
+                });
+              }
+              self.parse_reserved_statement(start, name)
             }
           };
-          if body.is_some() {
-            self.report(Diagnostic::ComplexMessageDeclarationAfterBody {
-              span: declaration.span(),
-            });
+          self.finish_node();
+          if has_body {
+            self
+              .report(Diagnostic::ComplexMessageDeclarationAfterBody { span });
           }
-          declarations.push(declaration);
         }
         Some((loc, '{')) => {
           // parse quoted pattern, or error recover for placeholder
           if let Some((_, '{')) = self.peek2() {
-            let quoted = self.parse_quoted_pattern(loc);
-            if body.is_some() {
-              self.report(Diagnostic::ComplexMessageMultipleBodies {
-                span: quoted.span(),
-              });
+            let span = self.parse_quoted_pattern(loc);
+            if has_body {
+              self.report(Diagnostic::ComplexMessageMultipleBodies { span });
             } else {
-              body = Some(ComplexMessageBody::QuotedPattern(quoted));
+              has_body = true;
             }
           } else {
             break;
@@ -1086,61 +1255,61 @@ impl<'a> Parser<'a> {
     // error recovery for an unquoted pattern
     if self.peek().is_some() {
       debug_assert!(!matches!(self.peek(), Some((_, chars::space!()))));
-      if body.is_some() {
+      debug_assert!(!self.peek().is_some_and(|(_, c)| self.is_bidi(c)));
+      if has_body {
+        // The trailing content is not consumed here, and ends up in the
+        // root node.
         self.report(Diagnostic::ComplexMessageTrailingContent {
           span: Span::new(self.current_location()..self.text.end_location()),
         });
       } else {
-        let pattern = self.parse_pattern(self.current_location(), false);
+        self.start_node(SyntaxKind::QuotedPattern);
+        let span = self
+          .parse_pattern(self.current_location(), false)
+          .unwrap_or_else(|| Span::new(Location::dummy()..Location::dummy()));
         // todo: remove trailing spaces from the pattern
-        self.report(Diagnostic::ComplexMessageBodyNotQuoted {
-          span: pattern.span(),
-        });
-        body = Some(ComplexMessageBody::QuotedPattern(QuotedPattern {
-          span: pattern.span(),
-          pattern,
-        }));
+        self.report(Diagnostic::ComplexMessageBodyNotQuoted { span });
+        self.finish_node();
+        has_body = true;
       }
     }
 
-    let body = body.unwrap_or_else(|| {
+    if !has_body {
+      // The missing body is derived as an empty pattern at the end of the
+      // message.
       self.report(Diagnostic::ComplexMessageMissingBody {
         span: Span::new(self.current_location()..self.current_location()),
       });
-      ComplexMessageBody::QuotedPattern(QuotedPattern {
-        span: Span::new(self.current_location()..self.current_location()),
-        pattern: Pattern {
-          parts: vec![PatternPart::Text(Text {
-            start: self.current_location(),
-            content: "",
-          })],
-        },
-      })
-    });
-
-    ComplexMessage { declarations, body }
+    }
+
+    self.finish_node();
   }
 
-  fn parse_local_declaration(&mut self, start: Location) -> Declaration<'a> {
+  fn parse_local_declaration(&mut self, node: usize, start: Location) -> Span {
     // At this point, `.local` has already been consumed. `start` is the location of the `.`.
+    self.set_node_kind(node, SyntaxKind::LocalDeclaration);
     let before_spaces = self.current_location();
     let has_space = self.skip_spaces();
 
     let next = self.peek();
-    let variable = match next {
+    let variable_span = match next {
       Some((_, '$')) => self.parse_variable(),
       Some((start, chars::name_start!())) => {
-        let name = self.parse_name();
+        self.start_node(SyntaxKind::Variable);
+        self.parse_name();
         let span = Span::new(start..self.current_location());
         self.report(Diagnostic::LocalVariableMissingDollar { span });
-        Variable { span, name }
+        self.finish_node();
+        span
       }
       _ => {
-        self.text.reset_to(before_spaces);
+        self.reset_to(before_spaces);
+        self.report(Diagnostic::LocalDeclarationMissingVariable {
+          span: Span::new(start..before_spaces),
+        });
         // parse as reserved statement
-        return Declaration::ReservedStatement(
-          self.parse_reserved_statement(start, "local"),
-        );
+        self.set_node_kind(node, SyntaxKind::ReservedStatement);
+        return self.parse_reserved_statement(start, "local");
       }
     };
     if !has_space {
@@ -1152,106 +1321,88 @@ impl<'a> Parser<'a> {
       });
     }
 
-    let mut last_visible_char = variable.span.end;
+    let mut last_visible_char = variable_span.end;
 
     self.skip_spaces();
 
-    if let Some(loc) = self.eat('=') {
+    if let Some(loc) = self.eat_token('=', SyntaxKind::Equals) {
       last_visible_char = loc;
       self.skip_spaces();
     } else {
       self.report(Diagnostic::LocalDeclarationVariableMissingTrailingEquals {
-        span: variable.span,
+        span: variable_span,
       });
     }
 
+    // The missing expression is derived as an empty literal expression at the
+    // `=`, or at the end of the variable if there is no `=`.
     let bail_and_report = |this: &mut Self| {
       this.report(Diagnostic::LocalDeclarationMissingExpression {
         span: Span::new(start..start + ".local"),
       });
-      Expression::LiteralExpression(LiteralExpression {
-        span: Span::new(last_visible_char..last_visible_char),
-        literal: Literal::Text(Text {
-          start: last_visible_char,
-          content: "",
-        }),
-        annotation: None,
-        attributes: vec![],
-      })
+      Span::new(last_visible_char..last_visible_char)
     };
 
-    let expression = if let Some((open, '{')) = self.peek() {
+    let expression_span = if let Some((_, '{')) = self.peek() {
       if matches!(self.peek2(), Some((_, '{'))) {
         bail_and_report(self)
       } else {
-        self.next().unwrap(); // consume '{'
-        self.skip_spaces();
-        self.parse_expression(open)
+        self.parse_braced_expression().1
       }
     } else if let Some((_, '.')) = self.peek() {
       // error recovery, next statement is starting. This would also be covered by
       // `parse_literal_or_variable` due to the number error recovery, but we want to report a
       // better error message here.
       bail_and_report(self)
-    } else if let Some(var) = self.parse_literal_or_variable() {
-      let span = var.span();
-      self.report(Diagnostic::LocalDeclarationValueNotWrappedInBraces { span });
-      match var {
-        LiteralOrVariable::Literal(literal) => {
-          Expression::LiteralExpression(LiteralExpression {
-            span,
-            literal,
-            annotation: None,
-            attributes: vec![],
-          })
-        }
-        LiteralOrVariable::Variable(variable) => {
-          Expression::VariableExpression(VariableExpression {
-            span,
-            variable,
-            annotation: None,
-            attributes: vec![],
-          })
+    } else {
+      let node = self.start_node(SyntaxKind::LiteralExpression);
+      if let Some((kind, span)) = self.parse_literal_or_variable() {
+        self
+          .report(Diagnostic::LocalDeclarationValueNotWrappedInBraces { span });
+        if kind == SyntaxKind::Variable {
+          self.set_node_kind(node, SyntaxKind::VariableExpression);
         }
+        self.finish_node();
+        span
+      } else {
+        self.abandon_node(node);
+        bail_and_report(self)
       }
-    } else {
-      bail_and_report(self)
     };
 
-    Declaration::LocalDeclaration(LocalDeclaration {
-      start,
-      variable,
-      expression,
-    })
+    Span::new(start..expression_span.end)
   }
 
-  fn parse_input_declaration(
-    &mut self,
-    start: Location,
-  ) -> InputDeclaration<'a> {
+  fn parse_input_declaration(&mut self, node: usize, start: Location) -> Span {
     // At this point, `.input` has already been consumed. `start` is the location of the `.`.
-
+    self.set_node_kind(node, SyntaxKind::InputDeclaration);
+    let before_spaces = self.current_location();
     self.skip_spaces();
 
-    let Some(open) = self.eat('{') else {
-      todo!("go into declaration error recovery");
+    match (self.peek(), self.peek2()) {
+      (Some((_, '{')), next) if !matches!(next, Some((_, '{'))) => {}
+      _ => {
+        self.reset_to(before_spaces);
+        self.report(Diagnostic::InputDeclarationMissingExpression {
+          span: Span::new(start..before_spaces),
+        });
+        // parse as reserved statement
+        self.set_node_kind(node, SyntaxKind::ReservedStatement);
+        return self.parse_reserved_statement(start, "input");
+      }
     };
 
-    self.skip_spaces();
-
-    let expression = self.parse_expression(open);
-    let Expression::VariableExpression(expression) = expression else {
-      todo!("report non variable input declaration")
-    };
+    let (kind, span) = self.parse_braced_expression();
+    if kind != SyntaxKind::VariableExpression {
+      self.report(Diagnostic::InputDeclarationNotVariable { span });
+      // recover as a reserved statement, to not lose the expression
+      self.set_node_kind(node, SyntaxKind::ReservedStatement);
+    }
 
-    InputDeclaration { start, expression }
+    Span::new(start..span.end)
   }
 
-  fn parse_reserved_statement(
-    &mut self,
-    start: Location,
-    name: &'a str,
-  ) -> ReservedStatement<'a> {
+  fn parse_reserved_statement(&mut self, start: Location, name: &str) -> Span {
     // At this point, the keyword has already been consumed. `start` is the location of the `.`
     // preceding the keyword.
     let mut before_spaces = self.current_location();
@@ -1262,78 +1413,123 @@ impl<'a> Parser<'a> {
       });
     }
 
-    let body = self.parse_reserved_body(&mut had_space, true);
-    if let Some(last) = body.last() {
-      before_spaces = last.span().end;
+    let body_end = self.parse_reserved_body(&mut had_space, true);
+    if let Some(body_end) = body_end {
+      before_spaces = body_end;
     }
 
-    let mut expressions = vec![];
+    let mut expressions_end = None;
 
-    while let Some((loc, '{')) = self.peek() {
+    while let Some((_, '{')) = self.peek() {
       if matches!(self.peek2(), Some((_, '{'))) {
         break;
       } else {
-        self.next().unwrap(); // consume '{'
-        self.skip_spaces();
-        expressions.push(self.parse_expression(loc));
+        let (_, span) = self.parse_braced_expression();
+        expressions_end = Some(span.end);
         before_spaces = self.current_location();
         self.skip_spaces();
       }
     }
 
-    if expressions.is_empty() {
+    if expressions_end.is_none() {
       self.report(Diagnostic::ReservedStatementMissingExpression {
         span: Span::new(start..before_spaces),
       });
     }
 
-    ReservedStatement {
-      start,
-      name,
-      body,
-      expressions,
-    }
+    let end = expressions_end
+      .or(body_end)
+      .unwrap_or_else(|| start + '.' + name);
+    Span::new(start..end)
   }
 
-  fn parse_matcher(&mut self, start: Location) -> Matcher<'a> {
+  fn parse_matcher(&mut self, start: Location) -> Span {
     // At this point, `.match` has already been consumed. `start` is the location of the `.`.
 
-    let mut selectors = vec![];
+    let mut selectors_end = None;
+    let mut last_selector_is_variable = false;
+    let variable_selectors = self.options.spec_version.has_variable_selectors();
 
-    self.skip_spaces();
-    while let Some(open) = self.eat('{') {
-      self.skip_spaces();
-      let expression = self.parse_expression(open);
-      selectors.push(expression);
-      self.skip_spaces();
+    let mut had_space = self.skip_spaces();
+    loop {
+      match self.peek() {
+        Some((_, '$')) => {
+          let span = self.parse_variable();
+          if !variable_selectors {
+            self.report(Diagnostic::MatcherSelectorNotExpression { span });
+          } else if !had_space {
+            self.report(Diagnostic::MissingSpaceBeforeSelector { span });
+          }
+          selectors_end = Some(span.end);
+          last_selector_is_variable = true;
+        }
+        Some((_, '{')) if !matches!(self.peek2(), Some((_, '{'))) => {
+          let (_, span) = self.parse_braced_expression();
+          if variable_selectors {
+            self.report(Diagnostic::MatcherSelectorNotVariable { span });
+          }
+          selectors_end = Some(span.end);
+          last_selector_is_variable = false;
+        }
+        _ => break,
+      }
+      had_space = self.skip_spaces();
     }
 
-    // todo, report error for no selectors
+    if selectors_end.is_none() {
+      self.report(Diagnostic::MatcherMissingSelectors {
+        span: Span::new(start..start + ".match"),
+      });
+    }
 
-    let mut variants = vec![];
-    let mut current_variant_keys = vec![];
+    let mut variants_end = None;
+    // The span of the keys of the current variant, if it has any keys.
+    let mut current_variant_keys: Option<Span> = None;
 
-    let mut had_space_or_closing_curly = true; // we had an expression
+    // an expression selector ends in a closing curly, so no space is needed
+    let mut had_space_or_closing_curly =
+      had_space || !last_selector_is_variable;
     while let Some((loc, c)) = self.peek() {
       match c {
         '*' => {
-          self.next();
-          let key = Key::Star(Star { start: loc });
+          if current_variant_keys.is_none() {
+            self.start_node(SyntaxKind::Variant);
+          }
+          self.bump(SyntaxKind::Star);
+          let span = Span::new(loc..loc + '*');
           if !had_space_or_closing_curly {
-            self.report(Diagnostic::MissingSpaceBeforeKey { span: key.span() })
+            self.report(Diagnostic::MissingSpaceBeforeKey { span })
           }
-          current_variant_keys.push(key);
+          extend_keys(&mut current_variant_keys, span);
           had_space_or_closing_curly = self.skip_spaces();
         }
         '{' => {
-          if let Some((_, '{')) = self.peek2() {
-            let pattern = self.parse_quoted_pattern(loc);
-            let keys = std::mem::take(&mut current_variant_keys);
-            // todo, at least one key is required
-            variants.push(Variant { keys, pattern });
-          } else {
-            todo!("parse as expression and use as quoted pattern for variant")
+          let keys = current_variant_keys.take();
+          if keys.is_none() {
+            self.start_node(SyntaxKind::Variant);
           }
+          let span = if let Some((_, '{')) = self.peek2() {
+            let span = self.parse_quoted_pattern(loc);
+            if keys.is_none() {
+              self.report(Diagnostic::VariantMissingKeys { span });
+            }
+            span
+          } else {
+            // error recovery for a variant pattern that is a placeholder
+            // instead of a quoted pattern
+            self.start_node(SyntaxKind::QuotedPattern);
+            self.start_node(SyntaxKind::Pattern);
+            let span = self.parse_placeholder();
+            self.finish_node();
+            self.finish_node();
+            self.report(Diagnostic::VariantPatternNotQuoted { span });
+            if keys.is_none() {
+              self.report(Diagnostic::VariantMissingKeys { span });
+            }
+            span
+          };
+          self.finish_node();
+          variants_end = Some(span.end);
           self.skip_spaces();
           had_space_or_closing_curly = true;
         }
@@ -1341,61 +1537,71 @@ impl<'a> Parser<'a> {
           break;
         }
         _ => {
-          let literal_or_variable = self.parse_literal_or_variable();
-          let key = match literal_or_variable {
-            Some(LiteralOrVariable::Literal(literal)) => Key::Literal(literal),
-            Some(LiteralOrVariable::Variable(variable)) => {
-              let span = variable.span();
-              self.report(Diagnostic::MatcherKeyIsVariable { span });
-              Key::Literal(Literal::Text(Text {
-                start: variable.span.start,
-                content: self.text.slice(span.start..span.end),
-              }))
+          let node = if current_variant_keys.is_none() {
+            Some(self.start_node(SyntaxKind::Variant))
+          } else {
+            None
+          };
+          match self.parse_literal_or_variable() {
+            Some((kind, span)) => {
+              if kind == SyntaxKind::Variable {
+                self.report(Diagnostic::MatcherKeyIsVariable { span });
+              }
+              if !had_space_or_closing_curly {
+                self.report(Diagnostic::MissingSpaceBeforeKey { span })
+              }
+              extend_keys(&mut current_variant_keys, span);
             }
             None => {
+              if let Some(node) = node {
+                self.abandon_node(node);
+              }
               // eat until the next space or quoted pattern
-
-              todo!("error recovery for invalid matcher key")
+              self.next();
+              while let Some((_, c)) = self.peek() {
+                if matches!(c, chars::space!() | '{') || self.is_bidi(c) {
+                  break;
+                }
+                self.next();
+              }
+              self.token(SyntaxKind::Error);
+              self.report(Diagnostic::MatcherInvalidKey {
+                span: Span::new(loc..self.current_location()),
+              });
             }
-          };
-          if !had_space_or_closing_curly {
-            self.report(Diagnostic::MissingSpaceBeforeKey { span: key.span() })
           }
-          current_variant_keys.push(key);
           had_space_or_closing_curly = self.skip_spaces();
         }
       }
     }
 
-    if !current_variant_keys.is_empty() {
-      variants.push(Variant {
-        keys: std::mem::take(&mut current_variant_keys),
-        pattern: QuotedPattern {
-          span: Span::new(self.current_location()..self.current_location()),
-          pattern: Pattern {
-            parts: vec![PatternPart::Text(Text {
-              start: self.current_location(),
-              content: "",
-            })],
-          },
-        },
-      });
-      todo!("report missing pattern for matcher variant")
+    if let Some(span) = current_variant_keys {
+      self.report(Diagnostic::VariantMissingPattern { span });
+      // The missing pattern is an empty quoted pattern at the current
+      // location, with an empty text.
+      self.start_node(SyntaxKind::QuotedPattern);
+      self.start_node(SyntaxKind::Pattern);
+      self.token(SyntaxKind::Text);
+      self.finish_node();
+      self.finish_node();
+      self.finish_node();
+      variants_end = Some(self.current_location());
     }
 
-    Matcher {
-      start,
-      selectors,
-      variants,
-    }
+    let end = variants_end
+      .or(selectors_end)
+      .unwrap_or_else(|| start + ".match");
+    Span::new(start..end)
   }
 
-  fn parse_quoted_pattern(&mut self, start: Location) -> QuotedPattern<'a> {
+  fn parse_quoted_pattern(&mut self, start: Location) -> Span {
     // At this point we know we have {{
+    self.start_node(SyntaxKind::QuotedPattern);
     self.eat('{').unwrap();
     self.eat('{').unwrap();
+    self.token(SyntaxKind::DoubleOpenBrace);
 
-    let pattern = self.parse_pattern(self.current_location(), true);
+    self.parse_pattern(self.current_location(), true);
 
     // Now consume the closing `}}`.
 
@@ -1403,6 +1609,7 @@ impl<'a> Parser<'a> {
     match maybe_close {
       Some((_, '}')) => {
         self.eat('}').unwrap(); // consume the second '}' - parse_pattern guarantees it's there
+        self.token(SyntaxKind::DoubleCloseBrace);
       }
       Some(_) => unreachable!(),
       None => {
@@ -1412,13 +1619,15 @@ impl<'a> Parser<'a> {
       }
     }
 
-    QuotedPattern {
-      span: Span::new(start..self.current_location()),
-      pattern,
-    }
+    self.finish_node();
+    Span::new(start..self.current_location())
   }
 }
 
+fn extend_keys(keys: &mut Option<Span>, span: Span) {
+  keys.get_or_insert(span).end = span.end;
+}
+
 enum MarkupStartKind {
   OpenOrStandalone,
   Close,
@@ -0,0 +1,408 @@
+use unicode_width::UnicodeWidthStr;
+
+use crate::ast::*;
+use crate::chars;
+use crate::options::FormatOptions;
+
+/// Prints a message back to Message Format 2 source text.
+///
+/// The output is valid Message Format 2 syntax for any message that does not
+/// contain syntax that was recovered from an error. Text in patterns and
+/// quoted literals is escaped where needed, and unquoted literals that are not
+/// valid names are printed quoted. Complex messages are printed with one
+/// declaration per line, and one variant per line.
+pub fn print(message: &Message) -> String {
+  Printer::new(None).print_message(message)
+}
+
+/// Formats a message into the canonical Message Format 2 style.
+///
+/// In addition to what [`print`] does, the formatter indents the variants of
+/// a matcher, aligns their keys into columns, sorts the options of functions
+/// and markup by name, and removes the quotes from literals that do not need
+/// them. The text in patterns is left untouched.
+///
+/// Messages with diagnostics should not be formatted, because the AST of a
+/// message with syntax errors may not contain all of the source text.
+pub fn format_source(message: &Message, options: &FormatOptions) -> String {
+  Printer::new(Some(options)).print_message(message)
+}
+
+struct Printer<'o> {
+  out: String,
+  /// `None` when printing, and the formatting options when formatting.
+  format: Option<&'o FormatOptions>,
+}
+
+impl<'o> Printer<'o> {
+  fn new(format: Option<&'o FormatOptions>) -> Self {
+    Printer {
+      out: String::new(),
+      format,
+    }
+  }
+
+  fn print_message(mut self, message: &Message) -> String {
+    match message {
+      Message::Simple(pattern) => self.print_simple_message(pattern),
+      Message::Complex(complex) => self.print_complex_message(complex),
+    }
+    self.out
+  }
+
+  fn print_simple_message(&mut self, pattern: &Pattern) {
+    // A simple message may not start with a `.` (after leading whitespace),
+    // because it would be parsed as a complex message. There is no escape for
+    // `.`, so such patterns are printed as a quoted pattern instead.
+    let starts_with_dot = match pattern.parts.first() {
+      Some(PatternPart::Text(text)) => text
+        .content
+        .trim_start_matches(|c| matches!(c, chars::space!() | chars::bidi!()))
+        .starts_with('.'),
+      _ => false,
+    };
+
+    if starts_with_dot {
+      self.print_quoted_pattern(pattern);
+    } else {
+      self.print_pattern(pattern);
+    }
+  }
+
+  fn print_complex_message(&mut self, complex: &ComplexMessage) {
+    for declaration in &complex.declarations {
+      self.print_declaration(declaration);
+      self.out.push('\n');
+    }
+    match &complex.body {
+      ComplexMessageBody::QuotedPattern(quoted) => {
+        self.print_quoted_pattern(&quoted.pattern)
+      }
+      ComplexMessageBody::Matcher(matcher) => self.print_matcher(matcher),
+    }
+  }
+
+  fn print_declaration(&mut self, declaration: &Declaration) {
+    match declaration {
+      Declaration::InputDeclaration(input) => {
+        self.out.push_str(".input ");
+        self.print_variable_expression(&input.expression);
+      }
+      Declaration::LocalDeclaration(local) => {
+        self.out.push_str(".local ");
+        self.print_variable(&local.variable);
+        self.out.push_str(" = ");
+        self.print_expression(&local.expression);
+      }
+      Declaration::ReservedStatement(statement) => {
+        self.out.push('.');
+        self.out.push_str(&statement.name);
+        if !statement.body.is_empty() {
+          self.out.push(' ');
+          self.print_reserved_body(&statement.body);
+        }
+        for expression in &statement.expressions {
+          self.out.push(' ');
+          self.print_expression(expression);
+        }
+      }
+    }
+  }
+
+  fn print_matcher(&mut self, matcher: &Matcher) {
+    self.out.push_str(".match");
+    for selector in &matcher.selectors {
+      self.out.push(' ');
+      match selector {
+        Selector::Variable(variable) => self.print_variable(variable),
+        Selector::Expression(expression) => self.print_expression(expression),
+      }
+    }
+
+    let keys = matcher
+      .variants
+      .iter()
+      .map(|variant| {
+        variant
+          .keys
+          .iter()
+          .map(|key| {
+            let mut printer = Printer::new(self.format);
+            match key {
+              Key::Literal(literal) => printer.print_literal(literal),
+              Key::Star(_) => printer.out.push('*'),
+            }
+            printer.out
+          })
+          .collect::<Vec<_>>()
+      })
+      .collect::<Vec<_>>();
+
+    let mut column_widths = vec![];
+    if self.format.is_some_and(|format| format.align_keys) {
+      for variant_keys in &keys {
+        for (column, key) in variant_keys.iter().enumerate() {
+          if column_widths.len() <= column {
+            column_widths.push(0);
+          }
+          column_widths[column] = column_widths[column].max(key.width());
+        }
+      }
+    }
+    let indent_width = self.format.map_or(0, |format| format.indent_width);
+
+    for (variant, variant_keys) in matcher.variants.iter().zip(keys) {
+      self.out.push('\n');
+      self.out.extend(std::iter::repeat(' ').take(indent_width));
+      for (column, key) in variant_keys.iter().enumerate() {
+        self.out.push_str(key);
+        if let Some(width) = column_widths.get(column) {
+          let padding = width - key.width();
+          self.out.extend(std::iter::repeat(' ').take(padding));
+        }
+        self.out.push(' ');
+      }
+      self.print_quoted_pattern(&variant.pattern.pattern);
+    }
+  }
+
+  fn print_quoted_pattern(&mut self, pattern: &Pattern) {
+    self.out.push_str("{{");
+    self.print_pattern(pattern);
+    self.out.push_str("}}");
+  }
+
+  fn print_pattern(&mut self, pattern: &Pattern) {
+    for part in &pattern.parts {
+      match part {
+        PatternPart::Text(text) => {
+          self.print_escaped(&text.content, &['\\', '{', '}'])
+        }
+        PatternPart::Escape(escape) => self.print_escape(escape),
+        PatternPart::Expression(expression) => {
+          self.print_expression(expression)
+        }
+        PatternPart::Markup(markup) => self.print_markup(markup),
+      }
+    }
+  }
+
+  fn print_expression(&mut self, expression: &Expression) {
+    match expression {
+      Expression::LiteralExpression(expression) => {
+        self.out.push('{');
+        self.print_literal(&expression.literal);
+        if let Some(annotation) = &expression.annotation {
+          self.out.push(' ');
+          self.print_annotation(annotation);
+        }
+        self.print_attributes(&expression.attributes);
+        self.out.push('}');
+      }
+      Expression::VariableExpression(expression) => {
+        self.print_variable_expression(expression)
+      }
+      Expression::AnnotationExpression(expression) => {
+        self.out.push('{');
+        self.print_annotation(&expression.annotation);
+        self.print_attributes(&expression.attributes);
+        self.out.push('}');
+      }
+    }
+  }
+
+  fn print_variable_expression(&mut self, expression: &VariableExpression) {
+    self.out.push('{');
+    self.print_variable(&expression.variable);
+    if let Some(annotation) = &expression.annotation {
+      self.out.push(' ');
+      self.print_annotation(annotation);
+    }
+    self.print_attributes(&expression.attributes);
+    self.out.push('}');
+  }
+
+  fn print_markup(&mut self, markup: &Markup) {
+    self.out.push_str(match markup.kind {
+      MarkupKind::Open | MarkupKind::Standalone => "{#",
+      MarkupKind::Close => "{/",
+    });
+    self.print_identifier(&markup.id);
+    self.print_options(&markup.options);
+    self.print_attributes(&markup.attributes);
+    self.out.push_str(match markup.kind {
+      MarkupKind::Standalone => " /}",
+      MarkupKind::Open | MarkupKind::Close => "}",
+    });
+  }
+
+  fn print_annotation(&mut self, annotation: &Annotation) {
+    match annotation {
+      Annotation::Function(function) => {
+        self.out.push(':');
+        self.print_identifier(&function.id);
+        self.print_options(&function.options);
+      }
+      Annotation::PrivateUseAnnotation(annotation) => {
+        self.out.push(annotation.sigil);
+        self.print_reserved_body(&annotation.body);
+      }
+      Annotation::ReservedAnnotation(annotation) => {
+        self.out.push(annotation.sigil);
+        self.print_reserved_body(&annotation.body);
+      }
+    }
+  }
+
+  fn print_options(&mut self, options: &[FnOrMarkupOption]) {
+    let mut options = options.iter().collect::<Vec<_>>();
+    if self.format.is_some() {
+      options.sort_by(|a, b| {
+        (&a.key.namespace, &a.key.name).cmp(&(&b.key.namespace, &b.key.name))
+      });
+    }
+    for option in options {
+      self.out.push(' ');
+      self.print_identifier(&option.key);
+      self.out.push('=');
+      self.print_literal_or_variable(&option.value);
+    }
+  }
+
+  fn print_attributes(&mut self, attributes: &[Attribute]) {
+    for attribute in attributes {
+      self.out.push_str(" @");
+      self.print_identifier(&attribute.key);
+      if let Some(value) = &attribute.value {
+        self.out.push('=');
+        self.print_literal_or_variable(value);
+      }
+    }
+  }
+
+  fn print_reserved_body(&mut self, body: &[ReservedBodyPart]) {
+    for part in body {
+      match part {
+        ReservedBodyPart::Text(text) => self.out.push_str(&text.content),
+        ReservedBodyPart::Escape(escape) => self.print_escape(escape),
+        ReservedBodyPart::Quoted(quoted) => self.print_quoted(quoted),
+      }
+    }
+  }
+
+  fn print_identifier(&mut self, identifier: &Identifier) {
+    if let Some(namespace) = &identifier.namespace {
+      self.out.push_str(namespace);
+      self.out.push(':');
+    }
+    self.out.push_str(&identifier.name);
+  }
+
+  fn print_literal_or_variable(&mut self, value: &LiteralOrVariable) {
+    match value {
+      LiteralOrVariable::Literal(literal) => self.print_literal(literal),
+      LiteralOrVariable::Variable(variable) => self.print_variable(variable),
+    }
+  }
+
+  fn print_variable(&mut self, variable: &Variable) {
+    self.out.push('$');
+    self.out.push_str(&variable.name);
+  }
+
+  fn print_literal(&mut self, literal: &Literal) {
+    match literal {
+      Literal::Quoted(quoted) if self.format.is_some() => {
+        let value =
+          quoted.parts.iter().fold(String::new(), |mut value, part| {
+            match part {
+              QuotedPart::Text(text) => value.push_str(&text.content),
+              QuotedPart::Escape(escape) => value.push(escape.escaped_char),
+            }
+            value
+          });
+        // Bidi marks around unquoted literals are not allowed in all versions
+        // of the spec, so those literals stay quoted.
+        if strip_bidi(&value) == value && (is_name(&value) || is_number(&value))
+        {
+          self.out.push_str(&value);
+        } else {
+          self.print_quoted(quoted);
+        }
+      }
+      Literal::Quoted(quoted) => self.print_quoted(quoted),
+      Literal::Text(text) if is_name(&text.content) => {
+        self.out.push_str(&text.content)
+      }
+      Literal::Text(text) => {
+        self.out.push('|');
+        self.print_escaped(&text.content, &['\\', '|']);
+        self.out.push('|');
+      }
+      Literal::Number(number) => self.out.push_str(&number.raw),
+    }
+  }
+
+  fn print_quoted(&mut self, quoted: &Quoted) {
+    self.out.push('|');
+    for part in &quoted.parts {
+      match part {
+        QuotedPart::Text(text) => {
+          self.print_escaped(&text.content, &['\\', '|'])
+        }
+        QuotedPart::Escape(escape) => self.print_escape(escape),
+      }
+    }
+    self.out.push('|');
+  }
+
+  fn print_escape(&mut self, escape: &Escape) {
+    self.out.push('\\');
+    self.out.push(escape.escaped_char);
+  }
+
+  fn print_escaped(&mut self, text: &str, escaped: &[char]) {
+    for c in text.chars() {
+      if escaped.contains(&c) {
+        self.out.push('\\');
+      }
+      self.out.push(c);
+    }
+  }
+}
+
+/// Whether the text can be printed as an unquoted literal, which is the case
+/// if it is a name (optionally surrounded by bidi marks or isolates).
+pub(crate) fn is_name(text: &str) -> bool {
+  is_valid_name(strip_bidi(text))
+}
+
+/// Whether the text is a valid number literal.
+pub(crate) fn is_number(text: &str) -> bool {
+  fn skip_digits(text: &str) -> &str {
+    text.trim_start_matches(|c: char| c.is_ascii_digit())
+  }
+
+  let text = text.strip_prefix('-').unwrap_or(text);
+  let mut rest = match text.strip_prefix('0') {
+    Some(rest) => rest,
+    None => skip_digits(text),
+  };
+  if rest.len() == text.len() {
+    return false;
+  }
+  if let Some(fractional) = rest.strip_prefix('.') {
+    rest = skip_digits(fractional);
+    if rest.len() == fractional.len() {
+      return false;
+    }
+  }
+  if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+    let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+    rest = skip_digits(exponent);
+    if rest.len() == exponent.len() {
+      return false;
+    }
+  }
+  rest.is_empty()
+}
@@ -0,0 +1,133 @@
+use crate::cst::SyntaxElement;
+use crate::cst::SyntaxKind;
+use crate::cst::SyntaxNode;
+use crate::util::Span;
+use crate::util::Spanned;
+
+/// The kind of a [`Token`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+  /// Spaces, tabs, and newlines between other tokens.
+  Whitespace,
+  /// Bidi marks and isolates between other tokens.
+  Bidi,
+  /// Text in a pattern or in a reserved body.
+  Text,
+  /// A `\` followed by the escaped character, outside of quoted literals.
+  Escape,
+  /// `{`
+  OpenBrace,
+  /// `}`
+  CloseBrace,
+  /// `{{`, opening a quoted pattern.
+  OpenQuotedPattern,
+  /// `}}`, closing a quoted pattern.
+  CloseQuotedPattern,
+  /// A `.` followed by a keyword, like `.match`.
+  Keyword,
+  /// A `$` followed by the name of the variable.
+  Variable,
+  /// The `:` that starts a function.
+  FunctionSigil,
+  /// The sigil of a reserved or private-use annotation.
+  AnnotationSigil,
+  /// The `#` or `/` that starts markup.
+  MarkupSigil,
+  /// The `/` that makes markup self-closing.
+  SelfClose,
+  /// The `@` that starts an attribute.
+  At,
+  /// The `:` between the namespace and the name of an identifier.
+  Colon,
+  /// `=`
+  Equals,
+  /// The `*` catch-all key.
+  Star,
+  /// An identifier name, or an unquoted literal.
+  Name,
+  /// A quoted literal, including the `|` delimiters.
+  Quoted,
+  /// A number literal.
+  Number,
+  /// Invalid contents that the parser recovered from.
+  Error,
+}
+
+/// A lexical token of a message.
+#[derive(Debug, Clone, Copy)]
+pub struct Token<'a> {
+  pub kind: TokenKind,
+  pub span: Span,
+  pub text: &'a str,
+}
+
+impl Spanned for Token<'_> {
+  fn span(&self) -> Span {
+    self.span
+  }
+}
+
+/// Splits the concrete syntax tree of a message into tokens. The tokens cover
+/// the source text without gaps.
+pub(crate) fn tokens<'a>(source: &'a str, root: &SyntaxNode) -> Vec<Token<'a>> {
+  let mut tokens = vec![];
+  collect_tokens(source, root, &mut tokens);
+  tokens
+}
+
+fn collect_tokens<'a>(
+  source: &'a str,
+  node: &SyntaxNode,
+  tokens: &mut Vec<Token<'a>>,
+) {
+  let mut after_markup_sigil = false;
+  for child in node.children_with_tokens() {
+    let kind = match &child {
+      SyntaxElement::Node(child) => match child.kind() {
+        SyntaxKind::Variable => TokenKind::Variable,
+        SyntaxKind::Quoted => TokenKind::Quoted,
+        SyntaxKind::Error => TokenKind::Error,
+        _ => {
+          collect_tokens(source, child, tokens);
+          continue;
+        }
+      },
+      SyntaxElement::Token(token) => match token.kind() {
+        SyntaxKind::Whitespace => TokenKind::Whitespace,
+        SyntaxKind::Bidi => TokenKind::Bidi,
+        SyntaxKind::Text => TokenKind::Text,
+        SyntaxKind::Escape => TokenKind::Escape,
+        SyntaxKind::Name => TokenKind::Name,
+        SyntaxKind::Number => TokenKind::Number,
+        SyntaxKind::Keyword => TokenKind::Keyword,
+        SyntaxKind::OpenBrace => TokenKind::OpenBrace,
+        SyntaxKind::CloseBrace => TokenKind::CloseBrace,
+        SyntaxKind::DoubleOpenBrace => TokenKind::OpenQuotedPattern,
+        SyntaxKind::DoubleCloseBrace => TokenKind::CloseQuotedPattern,
+        SyntaxKind::Colon if node.kind() == SyntaxKind::Function => {
+          TokenKind::FunctionSigil
+        }
+        SyntaxKind::Colon => TokenKind::Colon,
+        SyntaxKind::Equals => TokenKind::Equals,
+        SyntaxKind::At => TokenKind::At,
+        SyntaxKind::Hash => TokenKind::MarkupSigil,
+        SyntaxKind::Slash if after_markup_sigil => TokenKind::SelfClose,
+        SyntaxKind::Slash => TokenKind::MarkupSigil,
+        SyntaxKind::Star => TokenKind::Star,
+        SyntaxKind::Sigil => TokenKind::AnnotationSigil,
+        // Variables and quoted literals are single tokens, so their parts
+        // never show up here.
+        _ => TokenKind::Error,
+      },
+    };
+    after_markup_sigil |= kind == TokenKind::MarkupSigil;
+
+    // Empty tokens are only created for missing parts of the message.
+    let span = child.span();
+    if span.start != span.end {
+      let text =
+        &source[span.start.inner() as usize..span.end.inner() as usize];
+      tokens.push(Token { kind, span, text });
+    }
+  }
+}
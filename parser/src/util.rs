@@ -144,7 +144,22 @@ pub struct SourceTextInfo<'a> {
   utf8_line_starts: Vec<u32>,
 }
 
-impl SourceTextInfo<'_> {
+impl<'a> SourceTextInfo<'a> {
+  /// Creates the info of a text without parsing it. Returns `None` if the
+  /// text is longer than `u32::MAX` bytes, which locations can not address.
+  pub fn new(text: &'a str) -> Option<SourceTextInfo<'a>> {
+    if text.len() > u32::MAX as usize {
+      return None;
+    }
+    let utf8_line_starts = std::iter::once(0)
+      .chain(text.match_indices('\n').map(|(i, _)| i as u32 + 1))
+      .collect();
+    Some(SourceTextInfo {
+      text,
+      utf8_line_starts,
+    })
+  }
+
   pub fn utf8_line_col(&self, loc: Location) -> (u32, u32) {
     let result = self.utf8_line_starts.binary_search_by(|&x| x.cmp(&loc.0));
     match result {
@@ -172,12 +187,46 @@ impl SourceTextInfo<'_> {
       }
     }
   }
+
+  /// The source text of a span.
+  pub fn slice(&self, span: Span) -> &str {
+    &self.text[span.start.0 as usize..span.end.0 as usize]
+  }
+
+  /// The inverse of [`utf16_line_col`](Self::utf16_line_col). Columns past
+  /// the end of a line are clamped to the end of the line, and lines past
+  /// the end of the text to the end of the text.
+  pub fn location_from_utf16_line_col(&self, line: u32, col: u32) -> Location {
+    Location(self.byte_index_from_utf16_line_col(line, col) as u32)
+  }
+
+  /// Like [`location_from_utf16_line_col`](Self::location_from_utf16_line_col),
+  /// but returns the byte index in the text.
+  pub fn byte_index_from_utf16_line_col(&self, line: u32, col: u32) -> usize {
+    let Some(&line_start) = self.utf8_line_starts.get(line as usize) else {
+      return self.text.len();
+    };
+    let line_start = line_start as usize;
+    let mut utf16_col = 0;
+    for (i, c) in self.text[line_start..].char_indices() {
+      if utf16_col >= col || matches!(c, '\n' | '\r') {
+        return line_start + i;
+      }
+      utf16_col += c.len_utf16() as u32;
+    }
+    self.text.len()
+  }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location(u32);
 
 impl Location {
+  pub(crate) fn new(byte_index: u32) -> Location {
+    Location(byte_index)
+  }
+
   pub(crate) fn dummy() -> Location {
     Location(0)
   }
@@ -221,7 +270,8 @@ impl Add<LengthShort> for Location {
   }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
   pub start: Location,
   pub end: Location,
@@ -248,6 +298,7 @@ pub trait Spanned {
 
 /// A short length (maximum u16)
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LengthShort(u16);
 
 impl Debug for LengthShort {
@@ -0,0 +1,321 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::ast::strip_bidi;
+use crate::ast::ComplexMessage;
+use crate::ast::ComplexMessageBody;
+use crate::ast::Declaration;
+use crate::ast::Expression;
+use crate::ast::FnOrMarkupOption;
+use crate::ast::Function;
+use crate::ast::Identifier;
+use crate::ast::Key;
+use crate::ast::Markup;
+use crate::ast::Matcher;
+use crate::ast::Message;
+use crate::ast::Selector;
+use crate::ast::Variable;
+use crate::ast::Variant;
+use crate::diagnostic::Diagnostic;
+use crate::functions::FunctionRegistry;
+use crate::util::Span;
+use crate::util::Spanned;
+use crate::visitor::Visit;
+use crate::visitor::Visitable;
+
+/// Checks a message for the data model errors defined by the specification:
+/// variant key mismatches, missing fallback variants, missing selector
+/// annotations, duplicate declarations, duplicate option names, and duplicate
+/// variants.
+///
+/// These are not syntax errors, so they are not reported by the parser. Parts
+/// of the message that are missing because of syntax errors are skipped, so
+/// that they are not reported twice.
+pub fn validate<'a>(message: &Message<'a>) -> Vec<Diagnostic<'a>> {
+  let mut validator = Validator {
+    functions: None,
+    diagnostics: vec![],
+  };
+  message.apply_visitor(&mut validator);
+  validator.diagnostics
+}
+
+/// Like [`validate`], but also reports options that are not supported by
+/// the function of their annotation, as listed by
+/// [`Function::option_names`](crate::functions::Function::option_names).
+/// Options with a namespace and annotations of functions that are not in the
+/// registry are not checked.
+pub fn validate_with_functions<'a>(
+  message: &Message<'a>,
+  functions: &FunctionRegistry,
+) -> Vec<Diagnostic<'a>> {
+  let mut validator = Validator {
+    functions: Some(functions),
+    diagnostics: vec![],
+  };
+  message.apply_visitor(&mut validator);
+  validator.diagnostics
+}
+
+struct Validator<'f> {
+  functions: Option<&'f FunctionRegistry>,
+  diagnostics: Vec<Diagnostic<'static>>,
+}
+
+impl Visit for Validator<'_> {
+  fn visit_complex_message(&mut self, message: &ComplexMessage) {
+    self.check_declarations(&message.declarations);
+    if let ComplexMessageBody::Matcher(matcher) = &message.body {
+      self.check_matcher(matcher, &message.declarations);
+    }
+    message.apply_visitor_to_children(self);
+  }
+
+  fn visit_function(&mut self, function: &Function) {
+    self.check_options(&function.options);
+    self.check_supported_options(function);
+    function.apply_visitor_to_children(self);
+  }
+
+  fn visit_markup(&mut self, markup: &Markup) {
+    self.check_options(&markup.options);
+    markup.apply_visitor_to_children(self);
+  }
+}
+
+impl Validator<'_> {
+  /// A variable may only be declared once, and may not be declared after it
+  /// was used. The expression of a local declaration is evaluated before its
+  /// variable is declared, so it may not use the variable either.
+  fn check_declarations(&mut self, declarations: &[Declaration]) {
+    // The first declaration or use of each variable.
+    let mut seen = HashMap::<String, Span>::new();
+
+    for declaration in declarations {
+      let (variable, used) = match declaration {
+        Declaration::InputDeclaration(input) => {
+          let mut used = Variables::default();
+          if let Some(annotation) = &input.expression.annotation {
+            annotation.apply_visitor(&mut used);
+          }
+          for attribute in &input.expression.attributes {
+            attribute.apply_visitor(&mut used);
+          }
+          self.check_declaration(&mut seen, &input.expression.variable);
+          (None, used)
+        }
+        Declaration::LocalDeclaration(local) => {
+          let mut used = Variables::default();
+          local.expression.apply_visitor(&mut used);
+          (Some(&local.variable), used)
+        }
+        Declaration::ReservedStatement(statement) => {
+          let mut used = Variables::default();
+          statement.apply_visitor(&mut used);
+          (None, used)
+        }
+      };
+
+      for (name, span) in used.0 {
+        seen.entry(name).or_insert(span);
+      }
+      if let Some(variable) = variable {
+        self.check_declaration(&mut seen, variable);
+      }
+    }
+  }
+
+  fn check_declaration(
+    &mut self,
+    seen: &mut HashMap<String, Span>,
+    variable: &Variable,
+  ) {
+    let name = strip_bidi(&variable.name);
+    if name.is_empty() {
+      return;
+    }
+    match seen.get(name) {
+      Some(previous) => {
+        self.diagnostics.push(Diagnostic::DuplicateDeclaration {
+          variable: variable.span(),
+          previous: *previous,
+        });
+      }
+      None => {
+        seen.insert(name.to_string(), variable.span());
+      }
+    }
+  }
+
+  fn check_matcher(&mut self, matcher: &Matcher, declarations: &[Declaration]) {
+    for selector in &matcher.selectors {
+      let has_annotation = match selector {
+        Selector::Variable(variable) => {
+          variable_has_annotation(&variable.name, declarations)
+        }
+        Selector::Expression(expression) => {
+          expression_has_annotation(expression, declarations)
+        }
+      };
+      if !has_annotation {
+        self
+          .diagnostics
+          .push(Diagnostic::MissingSelectorAnnotation {
+            selector: selector.span(),
+          });
+      }
+    }
+
+    // Matchers without selectors and variants without keys are syntax errors.
+    if matcher.selectors.is_empty() {
+      return;
+    }
+
+    let mut has_fallback = false;
+    let mut previous_keys = Vec::<(Vec<Option<Cow<str>>>, Span)>::new();
+    for variant in &matcher.variants {
+      let Some(keys_span) = keys_span(variant) else {
+        continue;
+      };
+      if variant.keys.len() != matcher.selectors.len() {
+        self
+          .diagnostics
+          .push(Diagnostic::VariantKeyMismatch { keys: keys_span });
+        continue;
+      }
+
+      has_fallback |=
+        variant.keys.iter().all(|key| matches!(key, Key::Star(_)));
+
+      let keys = variant
+        .keys
+        .iter()
+        .map(|key| match key {
+          Key::Literal(literal) => Some(literal.normalized_value()),
+          Key::Star(_) => None,
+        })
+        .collect::<Vec<_>>();
+      match previous_keys.iter().find(|(previous, _)| *previous == keys) {
+        Some((_, previous)) => {
+          self.diagnostics.push(Diagnostic::DuplicateVariant {
+            keys: keys_span,
+            previous: *previous,
+          });
+        }
+        None => previous_keys.push((keys, keys_span)),
+      }
+    }
+
+    if !has_fallback {
+      self.diagnostics.push(Diagnostic::MissingFallbackVariant {
+        span: Span::new(matcher.start..matcher.start + ".match"),
+      });
+    }
+  }
+
+  fn check_options(&mut self, options: &[FnOrMarkupOption]) {
+    for (i, option) in options.iter().enumerate() {
+      if strip_bidi(&option.key.name).is_empty() {
+        continue;
+      }
+      let previous = options[..i]
+        .iter()
+        .find(|previous| same_identifier(&previous.key, &option.key));
+      if let Some(previous) = previous {
+        self.diagnostics.push(Diagnostic::DuplicateOptionName {
+          key: option.key.span(),
+          previous: previous.key.span(),
+        });
+      }
+    }
+  }
+
+  fn check_supported_options(&mut self, function: &Function) {
+    let Some(functions) = self.functions else {
+      return;
+    };
+    let Some(option_names) = functions
+      .get(&function.id)
+      .and_then(|function| function.option_names())
+    else {
+      return;
+    };
+    for option in &function.options {
+      let name = strip_bidi(&option.key.name);
+      if option.key.namespace.is_some() || name.is_empty() {
+        continue;
+      }
+      if !option_names.contains(&name) {
+        self.diagnostics.push(Diagnostic::UnsupportedOption {
+          key: option.key.span(),
+        });
+      }
+    }
+  }
+}
+
+/// Collects the names and spans of the variables used in a node.
+#[derive(Default)]
+struct Variables(Vec<(String, Span)>);
+
+impl Visit for Variables {
+  fn visit_variable(&mut self, variable: &Variable) {
+    let name = strip_bidi(&variable.name);
+    if !name.is_empty() {
+      self.0.push((name.to_string(), variable.span()));
+    }
+  }
+}
+
+/// Whether the variable is declared with a function annotation, directly or
+/// through the declaration of another variable.
+fn variable_has_annotation(name: &str, declarations: &[Declaration]) -> bool {
+  let name = strip_bidi(name);
+  for (i, declaration) in declarations.iter().enumerate().rev() {
+    match declaration {
+      Declaration::InputDeclaration(input)
+        if strip_bidi(&input.expression.variable.name) == name =>
+      {
+        return input.expression.annotation.is_some();
+      }
+      Declaration::LocalDeclaration(local)
+        if strip_bidi(&local.variable.name) == name =>
+      {
+        return expression_has_annotation(
+          &local.expression,
+          &declarations[..i],
+        );
+      }
+      _ => {}
+    }
+  }
+  false
+}
+
+fn expression_has_annotation(
+  expression: &Expression,
+  declarations: &[Declaration],
+) -> bool {
+  match expression {
+    Expression::LiteralExpression(expression) => {
+      expression.annotation.is_some()
+    }
+    Expression::VariableExpression(expression) => {
+      expression.annotation.is_some()
+        || variable_has_annotation(&expression.variable.name, declarations)
+    }
+    Expression::AnnotationExpression(_) => true,
+  }
+}
+
+fn same_identifier(a: &Identifier, b: &Identifier) -> bool {
+  a.namespace.as_deref().map(strip_bidi)
+    == b.namespace.as_deref().map(strip_bidi)
+    && strip_bidi(&a.name) == strip_bidi(&b.name)
+}
+
+fn keys_span(variant: &Variant) -> Option<Span> {
+  let first = variant.keys.first()?;
+  let last = variant.keys.last()?;
+  Some(Span::new(first.span().start..last.span().end))
+}
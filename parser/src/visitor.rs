@@ -41,6 +41,7 @@ pub trait Visit {
   visit!(visit_complex_message_body, body, ComplexMessageBody);
   visit!(visit_quoted_pattern, pattern, QuotedPattern);
   visit!(visit_matcher, matcher, Matcher);
+  visit!(visit_selector, selector, Selector);
   visit!(visit_variant, variant, Variant);
   visit!(visit_key, key, Key);
   visit!(visit_star, star, Star);
@@ -0,0 +1,24 @@
+#!/bin/sh
+# Vendors the test files of the message-format-wg repository at a commit, and
+# records the commit in UPSTREAM_COMMIT.
+#
+#   ./update.sh <commit>
+
+set -eu
+
+commit="${1:?usage: update.sh <message-format-wg commit>}"
+# Branches and tags move, so only full commit hashes are accepted.
+if ! printf '%s' "$commit" | grep -Eq '^[0-9a-f]{40}$'; then
+  echo "update.sh: expected a full commit hash, got '$commit'" >&2
+  exit 1
+fi
+dir="$(cd "$(dirname "$0")" && pwd)"
+checkout="$(mktemp -d)"
+trap 'rm -rf "$checkout"' EXIT
+
+git clone --quiet https://github.com/unicode-org/message-format-wg "$checkout"
+git -C "$checkout" checkout --quiet "$commit"
+
+find "$dir" -name '*.json' -delete
+cp -R "$checkout/test/tests/." "$dir/"
+git -C "$checkout" rev-parse HEAD > "$dir/UPSTREAM_COMMIT"
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+use file_test_runner::collect_and_run_tests;
+use file_test_runner::collection::strategies::FileTestMapperStrategy;
+use file_test_runner::collection::strategies::TestPerFileCollectionStrategy;
+use file_test_runner::collection::CollectOptions;
+use file_test_runner::collection::CollectTestsError;
+use file_test_runner::collection::CollectedCategoryOrTest;
+use file_test_runner::collection::CollectedTest;
+use file_test_runner::collection::CollectedTestCategory;
+use file_test_runner::RunOptions;
+use file_test_runner::TestResult;
+use mf2_parser::ast::Message;
+use mf2_parser::data_model;
+use mf2_parser::parse;
+use mf2_parser::validate;
+use serde_json::Value;
+
+/// Conformance tests that are known to fail, by their name (see
+/// [`case_name`]). A test in this list that passes is reported as a failure,
+/// so that the list is kept up to date.
+const KNOWN_FAILURES: &[&str] = &[];
+
+#[derive(Clone)]
+struct ConformanceTest {
+  src: String,
+  expectation: Expectation,
+}
+
+#[derive(Clone, PartialEq)]
+enum Expectation {
+  /// The message is valid. It may still produce runtime errors, but those are
+  /// not reported by `parse` or `validate`.
+  Valid,
+  /// The message has at least one syntax error.
+  SyntaxError,
+  /// The message is syntactically valid, but `validate` reports a diagnostic
+  /// of this kind.
+  DataModelError(String),
+}
+
+fn main() {
+  let base = Path::new("tests").join("conformance");
+  if !base.join("UPSTREAM_COMMIT").exists() {
+    eprintln!(
+      "warning: the upstream conformance tests are not vendored (see \
+       tests/conformance/README.md), so these results do not measure \
+       conformance"
+    );
+  }
+  collect_and_run_tests(
+    CollectOptions {
+      base,
+      strategy: Box::new(FileTestMapperStrategy {
+        base_strategy: TestPerFileCollectionStrategy {
+          file_pattern: Some(r"\.json$".to_string()),
+        },
+        map: map_test_file,
+      }),
+      filter_override: None,
+    },
+    RunOptions { parallel: true },
+    |test| {
+      let is_known_failure = KNOWN_FAILURES.contains(&test.name.as_str());
+      let result = TestResult::from_maybe_panic(AssertUnwindSafe(|| {
+        run_test(test);
+      }));
+      match (is_known_failure, result.is_failed()) {
+        (false, _) => result,
+        (true, true) => TestResult::Ignored,
+        (true, false) => TestResult::Failed {
+          output: b"test is in KNOWN_FAILURES, but passed".to_vec(),
+        },
+      }
+    },
+  )
+}
+
+/// Maps a conformance test file (in the message-format-wg test schema) into a
+/// category with one test per test case.
+fn map_test_file(
+  test: CollectedTest,
+) -> Result<CollectedCategoryOrTest<ConformanceTest>, CollectTestsError> {
+  let file_text = test.read_to_string()?;
+  let file: Value = serde_json::from_str(&file_text)
+    .map_err(|err| anyhow::anyhow!("{}: {}", test.path.display(), err))?;
+
+  let default_errors = &file["defaultTestProperties"]["expErrors"];
+  // test names may only contain alphanumeric characters and underscores
+  let name = test.name.replace('-', "_");
+
+  let cases = file["tests"]
+    .as_array()
+    .into_iter()
+    .flatten()
+    .map(|case| {
+      let src = match &case["src"] {
+        Value::String(src) => src.clone(),
+        // sources can be split into multiple lines
+        Value::Array(lines) => {
+          lines.iter().filter_map(|line| line.as_str()).collect()
+        }
+        _ => String::new(),
+      };
+      let errors = match &case["expErrors"] {
+        Value::Null => default_errors,
+        errors => errors,
+      };
+      let error_types = errors
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|error| error["type"].as_str())
+        .collect::<Vec<_>>();
+      let data_model_error = error_types
+        .iter()
+        .find_map(|error_type| data_model_error_kind(error_type));
+      let expectation = if error_types.contains(&"syntax-error") {
+        Expectation::SyntaxError
+      } else if let Some(kind) = data_model_error {
+        Expectation::DataModelError(kind)
+      } else {
+        Expectation::Valid
+      };
+
+      let case_name = case_name(case["description"].as_str(), &src);
+      (case_name, src, expectation)
+    })
+    .collect::<Vec<_>>();
+
+  let mut name_counts = HashMap::new();
+  for (case_name, _, _) in &cases {
+    *name_counts.entry(case_name.clone()).or_insert(0) += 1;
+  }
+  let mut names = HashSet::new();
+  let children = cases
+    .into_iter()
+    .map(|(mut case_name, src, expectation)| {
+      // Sources that only differ in punctuation get the same name, so they
+      // are told apart by a hash of the source. Cases with the same source
+      // are interchangeable, so they are numbered.
+      if name_counts[&case_name] > 1 {
+        case_name = format!("{}_{:08x}", case_name, fnv1a(&src));
+      }
+      if !names.insert(case_name.clone()) {
+        case_name = (2..)
+          .map(|i| format!("{}_{}", case_name, i))
+          .find(|candidate| names.insert(candidate.clone()))
+          .unwrap();
+      }
+
+      CollectedCategoryOrTest::Test(CollectedTest {
+        name: format!("{}::{}", name, case_name),
+        path: test.path.clone(),
+        data: ConformanceTest { src, expectation },
+      })
+    })
+    .collect();
+
+  Ok(CollectedCategoryOrTest::Category(CollectedTestCategory {
+    name,
+    path: test.path,
+    children,
+  }))
+}
+
+/// Names a test case by its description, or by its source text if it has no
+/// description, so that the names (and `KNOWN_FAILURES`) do not depend on the
+/// order of the cases. Test names may only contain alphanumeric characters and
+/// underscores, so ASCII punctuation and whitespace are replaced by `_`, and
+/// other characters by their code point.
+fn case_name(description: Option<&str>, src: &str) -> String {
+  let text = description.unwrap_or(src);
+  let mut name = String::new();
+  for c in text.chars() {
+    if c.is_ascii_alphanumeric() {
+      name.push(c.to_ascii_lowercase());
+    } else {
+      if !name.is_empty() && !name.ends_with('_') {
+        name.push('_');
+      }
+      // Keep other characters (like bidi marks) apart from punctuation and
+      // whitespace, which are frequent and make names long.
+      if !c.is_ascii() {
+        name.push_str(&format!("u{:x}_", c as u32));
+      }
+    }
+    if name.len() >= 60 {
+      break;
+    }
+  }
+  let name = name.trim_end_matches('_');
+  if name.is_empty() {
+    "empty".to_string()
+  } else {
+    name.to_string()
+  }
+}
+
+/// The 32-bit FNV-1a hash, which is stable across Rust versions unlike the
+/// hashers of the standard library.
+fn fnv1a(text: &str) -> u32 {
+  text.bytes().fold(0x811c9dc5, |hash, byte| {
+    (hash ^ byte as u32).wrapping_mul(0x01000193)
+  })
+}
+
+/// Maps the type of a data model error in the test schema (like
+/// `"duplicate-declaration"`) to the kind of the diagnostic that `validate`
+/// reports for it (like `"DuplicateDeclaration"`).
+fn data_model_error_kind(error_type: &str) -> Option<String> {
+  let is_data_model_error = matches!(
+    error_type,
+    "variant-key-mismatch"
+      | "missing-fallback-variant"
+      | "missing-selector-annotation"
+      | "duplicate-declaration"
+      | "duplicate-option-name"
+      | "duplicate-variant"
+  );
+  is_data_model_error.then(|| {
+    error_type
+      .split('-')
+      .map(|word| word[..1].to_uppercase() + &word[1..])
+      .collect()
+  })
+}
+
+fn run_test(test: &CollectedTest<ConformanceTest>) {
+  let ConformanceTest { src, expectation } = &test.data;
+  let (message, diagnostics, _info) = parse(src);
+
+  match expectation {
+    Expectation::Valid => {
+      assert!(
+        diagnostics.is_empty(),
+        "expected no diagnostics for {src:?}, but got {diagnostics:#?}",
+      );
+      let diagnostics = validate(&message);
+      assert!(
+        diagnostics.is_empty(),
+        "expected no data model errors for {src:?}, but got {diagnostics:#?}",
+      );
+      assert_data_model_round_trips(src, &message);
+    }
+    Expectation::DataModelError(kind) => {
+      assert!(
+        diagnostics.is_empty(),
+        "expected no diagnostics for {src:?}, but got {diagnostics:#?}",
+      );
+      let diagnostics = validate(&message);
+      assert!(
+        diagnostics
+          .iter()
+          .any(|diagnostic| diagnostic.kind() == kind),
+        "expected a {kind} error for {src:?}, but got {diagnostics:#?}",
+      );
+    }
+    Expectation::SyntaxError => {
+      assert!(
+        !diagnostics.is_empty(),
+        "expected a syntax error for {src:?}, but got no diagnostics",
+      );
+    }
+  }
+}
+
+/// Checks that generating source text from the data model of a message gives
+/// a message with the same data model.
+fn assert_data_model_round_trips(src: &str, message: &Message) {
+  // Syntax from earlier drafts of the spec can not be converted.
+  let Ok(data_model) = data_model::Message::from_ast(message) else {
+    return;
+  };
+  let generated = data_model.to_source().unwrap();
+  let (message, diagnostics, _info) = parse(&generated);
+  assert!(
+    diagnostics.is_empty(),
+    "expected no diagnostics for {generated:?} (generated from {src:?}), but got {diagnostics:#?}",
+  );
+  let round_tripped = data_model::Message::from_ast(&message).unwrap();
+  pretty_assertions::assert_eq!(round_tripped, data_model, "{src:?}");
+}
@@ -0,0 +1,48 @@
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+use file_test_runner::collect_and_run_tests;
+use file_test_runner::collection::strategies::TestPerFileCollectionStrategy;
+use file_test_runner::collection::CollectOptions;
+use file_test_runner::collection::CollectedTest;
+use file_test_runner::RunOptions;
+use file_test_runner::TestResult;
+use mf2_parser::parse_cst;
+use mf2_parser::ParseOptions;
+
+fn main() {
+  collect_and_run_tests(
+    CollectOptions {
+      base: Path::new("tests").join("cst"),
+      strategy: Box::new(TestPerFileCollectionStrategy { file_pattern: None }),
+      filter_override: None,
+    },
+    RunOptions { parallel: true },
+    |test| {
+      TestResult::from_maybe_panic(AssertUnwindSafe(|| {
+        run_test(test);
+      }))
+    },
+  )
+}
+
+fn run_test(test: &CollectedTest) {
+  let file_text = test.read_to_string().unwrap();
+
+  let cst_marker = "\n=== cst ===\n";
+
+  let (message, expected_cst) = file_text
+    .split_once(cst_marker)
+    .unwrap_or((&*file_text, ""));
+
+  let (cst, _diagnostics, _info) = parse_cst(message, ParseOptions::default());
+  assert_eq!(cst.to_string(), message, "the CST is not lossless");
+  let actual_cst = format!("{cst:?}");
+
+  if std::env::var("UPDATE").is_ok() || expected_cst.is_empty() {
+    std::fs::write(&test.path, format!("{message}{cst_marker}{actual_cst}"))
+      .unwrap();
+  } else {
+    pretty_assertions::assert_eq!(actual_cst, expected_cst);
+  }
+}
@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+
+use mf2_parser::data_model::*;
+use mf2_parser::parse;
+
+fn lower(source: &str) -> Result<Message, ConversionError> {
+  let (message, diagnostics, _info) = parse(source);
+  assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+  Message::from_ast(&message)
+}
+
+fn literal(value: &str) -> Literal {
+  Literal {
+    value: value.to_string(),
+  }
+}
+
+fn variable(name: &str) -> VariableRef {
+  VariableRef {
+    name: name.to_string(),
+  }
+}
+
+#[test]
+fn lowers_pattern_message() {
+  let message = lower("Hi \\{{$name :string @u:id=|a\\|b| @x}{#b/}").unwrap();
+  pretty_assertions::assert_eq!(
+    message,
+    Message::Pattern(PatternMessage {
+      declarations: vec![],
+      pattern: vec![
+        PatternPart::Text("Hi {".to_string()),
+        PatternPart::Expression(Expression {
+          arg: Some(Value::Variable(variable("name"))),
+          function: Some(FunctionRef {
+            name: "string".to_string(),
+            options: BTreeMap::new(),
+          }),
+          attributes: BTreeMap::from([
+            ("u:id".to_string(), Some(literal("a|b"))),
+            ("x".to_string(), None),
+          ]),
+        }),
+        PatternPart::Markup(Markup {
+          kind: MarkupKind::Standalone,
+          name: "b".to_string(),
+          options: BTreeMap::new(),
+          attributes: BTreeMap::new(),
+        }),
+      ],
+    })
+  );
+}
+
+#[test]
+fn lowers_select_message() {
+  let message = lower(
+    ".input {$n :number}\n.local $x = {-1.5e3 :ns:fn opt=$n}\n.match $n\n1 {{one}}\n* {{{$x}}}",
+  )
+  .unwrap();
+  pretty_assertions::assert_eq!(
+    message,
+    Message::Select(SelectMessage {
+      declarations: vec![
+        Declaration::Input(InputDeclaration {
+          name: "n".to_string(),
+          value: Expression {
+            arg: Some(Value::Variable(variable("n"))),
+            function: Some(FunctionRef {
+              name: "number".to_string(),
+              options: BTreeMap::new(),
+            }),
+            attributes: BTreeMap::new(),
+          },
+        }),
+        Declaration::Local(LocalDeclaration {
+          name: "x".to_string(),
+          value: Expression {
+            arg: Some(Value::Literal(literal("-1.5e3"))),
+            function: Some(FunctionRef {
+              name: "ns:fn".to_string(),
+              options: BTreeMap::from([(
+                "opt".to_string(),
+                Value::Variable(variable("n")),
+              )]),
+            }),
+            attributes: BTreeMap::new(),
+          },
+        }),
+      ],
+      selectors: vec![variable("n")],
+      variants: vec![
+        Variant {
+          keys: vec![Key::Literal(literal("1"))],
+          value: vec![PatternPart::Text("one".to_string())],
+        },
+        Variant {
+          keys: vec![Key::CatchAll],
+          value: vec![PatternPart::Expression(Expression {
+            arg: Some(Value::Variable(variable("x"))),
+            function: None,
+            attributes: BTreeMap::new(),
+          })],
+        },
+      ],
+    })
+  );
+}
+
+#[test]
+fn rejects_unsupported_syntax() {
+  let (message, _diagnostics, _info) = parse("{:fn a=1 a=2}");
+  assert!(matches!(
+    Message::from_ast(&message),
+    Err(ConversionError::DuplicateOptionName { name, .. }) if name == "a"
+  ));
+  let (message, _diagnostics, _info) = parse("{$x @a @a}");
+  assert!(matches!(
+    Message::from_ast(&message),
+    Err(ConversionError::DuplicateAttribute { name, .. }) if name == "a"
+  ));
+}
+
+#[test]
+fn generates_source() {
+  let message = Message::Select(SelectMessage {
+    declarations: vec![Declaration::Input(InputDeclaration {
+      name: "n".to_string(),
+      value: Expression {
+        arg: Some(Value::Variable(variable("n"))),
+        function: Some(FunctionRef {
+          name: "number".to_string(),
+          options: BTreeMap::from([(
+            "style".to_string(),
+            Value::Literal(literal("percent")),
+          )]),
+        }),
+        attributes: BTreeMap::new(),
+      },
+    })],
+    selectors: vec![variable("n")],
+    variants: vec![
+      Variant {
+        keys: vec![Key::Literal(literal("a b"))],
+        value: vec![PatternPart::Text("{braces}".to_string())],
+      },
+      Variant {
+        keys: vec![Key::CatchAll],
+        value: vec![PatternPart::Expression(Expression {
+          arg: Some(Value::Literal(literal("1.0"))),
+          function: None,
+          attributes: BTreeMap::from([("x".to_string(), None)]),
+        })],
+      },
+    ],
+  });
+  pretty_assertions::assert_eq!(
+    message.to_source().unwrap(),
+    ".input {$n :number style=percent}\n.match $n\n|a b| {{\\{braces\\}}}\n* {{{1.0 @x}}}"
+  );
+}
+
+#[test]
+fn quotes_literals_with_bidi_marks() {
+  let message = Message::Pattern(PatternMessage {
+    declarations: vec![],
+    pattern: vec![PatternPart::Expression(Expression {
+      arg: Some(Value::Literal(literal("\u{200E}abc"))),
+      function: None,
+      attributes: BTreeMap::new(),
+    })],
+  });
+  let source = message.to_source().unwrap();
+  assert_eq!(source, "{|\u{200E}abc|}");
+  pretty_assertions::assert_eq!(lower(&source).unwrap(), message);
+}
+
+#[test]
+fn rejects_invalid_names() {
+  let expression = |arg: Value, function: &str, option: &str| {
+    Message::Pattern(PatternMessage {
+      declarations: vec![],
+      pattern: vec![PatternPart::Expression(Expression {
+        arg: Some(arg),
+        function: Some(FunctionRef {
+          name: function.to_string(),
+          options: BTreeMap::from([(
+            option.to_string(),
+            Value::Literal(literal("1")),
+          )]),
+        }),
+        attributes: BTreeMap::new(),
+      })],
+    })
+  };
+  let invalid_name = |name: &str| {
+    Err(GenerationError::InvalidName {
+      name: name.to_string(),
+    })
+  };
+  assert_eq!(
+    expression(Value::Variable(variable("a b")), "fn", "o").to_source(),
+    invalid_name("a b")
+  );
+  assert_eq!(
+    expression(Value::Variable(variable("")), "fn", "o").to_source(),
+    invalid_name("")
+  );
+  assert_eq!(
+    expression(Value::Literal(literal("x")), "u:fn:x", "o").to_source(),
+    invalid_name("u:fn:x")
+  );
+  assert_eq!(
+    expression(Value::Literal(literal("x")), "fn", "1o").to_source(),
+    invalid_name("1o")
+  );
+  assert_eq!(
+    expression(Value::Literal(literal("x")), "u:fn", "o").to_source(),
+    Ok("{x :u:fn o=1}".to_string())
+  );
+
+  let message = Message::Pattern(PatternMessage {
+    declarations: vec![Declaration::Local(LocalDeclaration {
+      name: "\u{200E}x".to_string(),
+      value: Expression {
+        arg: Some(Value::Literal(literal("x"))),
+        function: None,
+        attributes: BTreeMap::new(),
+      },
+    })],
+    pattern: vec![PatternPart::Markup(Markup {
+      kind: MarkupKind::Standalone,
+      name: "b".to_string(),
+      options: BTreeMap::new(),
+      attributes: BTreeMap::from([("a=".to_string(), None)]),
+    })],
+  });
+  assert!(message.to_ast().is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serializes_to_json() {
+  let message = lower(
+    ".local $x = {|a| :fn opt=1 @t @u=v}\n.match $x\na {{{#b}}}\n* {{{$x}}}",
+  )
+  .unwrap();
+  let json = serde_json::json!({
+    "type": "select",
+    "declarations": [{
+      "type": "local",
+      "name": "x",
+      "value": {
+        "type": "expression",
+        "arg": { "type": "literal", "value": "a" },
+        "function": {
+          "type": "function",
+          "name": "fn",
+          "options": { "opt": { "type": "literal", "value": "1" } },
+        },
+        "attributes": {
+          "t": true,
+          "u": { "type": "literal", "value": "v" },
+        },
+      },
+    }],
+    "selectors": [{ "type": "variable", "name": "x" }],
+    "variants": [
+      {
+        "keys": [{ "type": "literal", "value": "a" }],
+        "value": [{ "type": "markup", "kind": "open", "name": "b" }],
+      },
+      {
+        "keys": [{ "type": "*" }],
+        "value": [{
+          "type": "expression",
+          "arg": { "type": "variable", "name": "x" },
+        }],
+      },
+    ],
+  });
+  pretty_assertions::assert_eq!(serde_json::to_value(&message).unwrap(), json);
+  let deserialized: Message = serde_json::from_value(json).unwrap();
+  pretty_assertions::assert_eq!(deserialized, message);
+}
+
+#[test]
+fn rejects_empty_expressions() {
+  let message = Message::Pattern(PatternMessage {
+    declarations: vec![],
+    pattern: vec![PatternPart::Expression(Expression::default())],
+  });
+  assert_eq!(message.to_source(), Err(GenerationError::EmptyExpression));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn checks_types_when_deserializing() {
+  let pattern = |pattern: serde_json::Value| {
+    serde_json::from_value::<Message>(serde_json::json!({
+      "type": "message",
+      "declarations": [],
+      "pattern": pattern,
+    }))
+  };
+  // Markup without a kind is not an expression.
+  assert!(pattern(serde_json::json!([
+    ".foo",
+    { "type": "markup", "name": "b" },
+  ]))
+  .is_err());
+  assert!(pattern(serde_json::json!([
+    { "type": "markup", "kind": "middle", "name": "b" },
+  ]))
+  .is_err());
+  assert!(pattern(serde_json::json!([{ "name": "b" }])).is_err());
+  assert!(pattern(serde_json::json!([{
+    "type": "expression",
+    "arg": { "type": "variable", "value": "x" },
+  }]))
+  .is_err());
+
+  let message = pattern(serde_json::json!([{ "type": "expression" }])).unwrap();
+  assert_eq!(message.to_source(), Err(GenerationError::EmptyExpression));
+  let message = pattern(serde_json::json!([
+    "a",
+    { "type": "markup", "kind": "standalone", "name": "b" },
+    { "type": "expression", "arg": { "type": "variable", "name": "x" } },
+  ]))
+  .unwrap();
+  assert_eq!(message.to_source().unwrap(), "a{#b /}{$x}");
+}
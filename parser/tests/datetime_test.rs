@@ -0,0 +1,157 @@
+#![cfg(feature = "datetime")]
+
+use mf2_parser::format::format_message;
+use mf2_parser::format::Arguments;
+use mf2_parser::format::FormatError;
+use mf2_parser::format::Value;
+use mf2_parser::functions::FunctionRegistry;
+use mf2_parser::parse;
+use mf2_parser::validate_with_functions;
+
+fn format(
+  locale: &str,
+  source: &str,
+  arguments: &[(&str, Value)],
+) -> (String, Vec<FormatError>) {
+  let (message, diagnostics, _info) = parse(source);
+  assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+  let arguments = arguments
+    .iter()
+    .map(|(name, value)| (name.to_string(), value.clone()))
+    .collect::<Arguments>();
+  format_message(&message, locale, &arguments)
+}
+
+fn assert_formats(locale: &str, source: &str, expected: &str) {
+  let (out, errors) =
+    format(locale, source, &[("d", "2024-05-01T14:30:05.123Z".into())]);
+  assert_eq!(out, expected, "{source}");
+  assert!(errors.is_empty(), "{errors:#?}");
+}
+
+#[test]
+fn formats_date_and_time_styles() {
+  assert_formats("en-US", "{$d :date}", "May 1, 2024");
+  assert_formats("en-US", "{$d :date style=full}", "Wednesday, May 1, 2024");
+  assert_formats("en-US", "{$d :time}", "2:30\u{202F}PM");
+  assert_formats(
+    "en-US",
+    "{$d :datetime dateStyle=long timeStyle=long}",
+    "May 1, 2024 at 2:30:05\u{202F}PM GMT",
+  );
+  assert_formats("de-DE", "{$d :date style=long}", "1. Mai 2024");
+  assert_formats(
+    "fr",
+    "{$d :datetime dateStyle=medium timeStyle=short}",
+    "1 mai 2024 14:30",
+  );
+  assert_formats(
+    "es",
+    "{$d :date style=full}",
+    "miércoles, 1 de mayo de 2024",
+  );
+}
+
+#[test]
+fn formats_other_languages_locale_neutrally() {
+  assert_formats("ja", "{$d :date style=short}", "2024-05-01");
+  assert_formats(
+    "und",
+    "{$d :datetime dateStyle=medium timeStyle=medium}",
+    "2024 M05 1 14:30:05",
+  );
+  assert_formats("sv-SE", "{$d :time}", "14:30");
+}
+
+#[test]
+fn formats_field_options() {
+  assert_formats("en-US", "{$d :datetime}", "5/1/2024, 2:30\u{202F}PM");
+  assert_formats("en-US", "{$d :datetime month=long day=numeric}", "May 1");
+  assert_formats(
+    "en-US",
+    "{$d :datetime weekday=short year=numeric month=|2-digit| day=|2-digit|}",
+    "Wed, 05/01/2024",
+  );
+  assert_formats(
+    "en-US",
+    "{$d :datetime hour=|2-digit| minute=numeric second=numeric fractionalSecondDigits=2 hourCycle=h23}",
+    "14:30:05.12",
+  );
+  assert_formats("de", "{$d :datetime month=long year=numeric}", "Mai 2024");
+  assert_formats("es", "{$d :datetime month=long day=numeric}", "1 de mayo");
+}
+
+#[test]
+fn converts_time_zones() {
+  assert_formats(
+    "en-US",
+    "{$d :datetime hour=numeric minute=numeric timeZone=|-05:00| timeZoneName=short}",
+    "9:30\u{202F}AM GMT-5",
+  );
+  assert_formats(
+    "de",
+    "{$d :datetime dateStyle=short timeStyle=short timeZone=|+10:00|}",
+    "02.05.24, 00:30",
+  );
+  assert_formats(
+    "en-US",
+    "{$d :time style=long timeZone=|Europe/Berlin|}",
+    "4:30:05\u{202F}PM GMT+2",
+  );
+  assert_formats(
+    "en-US",
+    "{$d :datetime dateStyle=short timeStyle=short timeZone=|America/Los_Angeles|}",
+    "5/1/24, 7:30\u{202F}AM",
+  );
+
+  // Local times are not converted, and have no time zone name.
+  let (out, errors) = format(
+    "en-US",
+    "{$d :time style=full} {$d :time timeZone=UTC style=long}",
+    &[("d", "2024-05-01T08:00".into())],
+  );
+  assert_eq!(out, "8:00:00\u{202F}AM 8:00:00\u{202F}AM GMT");
+  assert!(errors.is_empty(), "{errors:#?}");
+
+  // The offset of an IANA time zone depends on the date.
+  let (out, errors) = format(
+    "en-US",
+    "{$w :time style=long timeZone=|Europe/Berlin|} {$l :time style=long timeZone=|Europe/Berlin|}",
+    &[
+      ("w", "2024-01-15T12:00Z".into()),
+      ("l", "2024-07-15T08:00".into()),
+    ],
+  );
+  assert_eq!(out, "1:00:00\u{202F}PM GMT+1 8:00:00\u{202F}AM GMT+2");
+  assert!(errors.is_empty(), "{errors:#?}");
+}
+
+#[test]
+fn falls_back_for_bad_dates() {
+  let (out, errors) = format(
+    "en-US",
+    "{|2023-02-29| :date} {$n :datetime} {|2024-01-01| :date timeZone=|Mars/Olympus_Mons|} {|2024-01-01| :date style=tiny}",
+    &[("n", 1.into())],
+  );
+  assert_eq!(out, "{|2023-02-29|} {$n} {|2024-01-01|} {|2024-01-01|}");
+  assert!(matches!(
+    errors.as_slice(),
+    [
+      FormatError::BadOperand { .. },
+      FormatError::BadOperand { .. },
+      FormatError::BadOption { name: a, .. },
+      FormatError::BadOption { name: b, .. },
+    ] if a == "timeZone" && b == "style"
+  ));
+}
+
+#[test]
+fn validates_date_time_options() {
+  let (message, _diagnostics, _info) =
+    parse("{$d :date style=short hourCycle=h23} {$d :datetime era=short}");
+  let diagnostics = validate_with_functions(
+    &message,
+    &FunctionRegistry::with_default_functions(),
+  );
+  assert_eq!(diagnostics.len(), 2, "{diagnostics:#?}");
+}
@@ -0,0 +1,506 @@
+use std::collections::BTreeMap;
+
+use mf2_parser::ast::Identifier;
+use mf2_parser::ast::MarkupKind;
+use mf2_parser::format::format_message;
+use mf2_parser::format::format_message_with_functions;
+use mf2_parser::format::format_to_parts;
+use mf2_parser::format::Arguments;
+use mf2_parser::format::FormatError;
+use mf2_parser::format::FormattedPart;
+use mf2_parser::format::Value;
+use mf2_parser::functions::Function;
+use mf2_parser::functions::FunctionError;
+use mf2_parser::functions::FunctionOptions;
+use mf2_parser::functions::FunctionRegistry;
+use mf2_parser::parse;
+use mf2_parser::validate_with_functions;
+use mf2_parser::Diagnostic;
+
+fn format(
+  source: &str,
+  arguments: &[(&str, Value)],
+) -> (String, Vec<FormatError>) {
+  let (message, diagnostics, _info) = parse(source);
+  assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+  let arguments = arguments
+    .iter()
+    .map(|(name, value)| (name.to_string(), value.clone()))
+    .collect::<Arguments>();
+  format_message(&message, "en-US", &arguments)
+}
+
+#[test]
+fn formats_text_and_escapes() {
+  let (out, errors) = format("Hello \\{world\\} \\\\", &[]);
+  assert_eq!(out, "Hello {world} \\");
+  assert!(errors.is_empty());
+}
+
+#[test]
+fn formats_literals_and_arguments() {
+  let (out, errors) = format(
+    "{|a\\|b|} {42} {$name} {$count}",
+    &[("name", "Ana".into()), ("count", 3.5.into())],
+  );
+  assert_eq!(out, "a|b 42 Ana 3.5");
+  assert!(errors.is_empty());
+}
+
+#[test]
+fn resolves_declarations() {
+  let (out, errors) = format(
+    ".input {$x}\n.local $y = {$x}\n.local $z = {|lit|}\n{{{$y} {$z}}}",
+    &[("x", 1.into())],
+  );
+  assert_eq!(out, "1 lit");
+  assert!(errors.is_empty());
+}
+
+#[test]
+fn formats_markup_as_nothing() {
+  let (out, errors) = format("{#b}bold{/b}{#br/}", &[]);
+  assert_eq!(out, "bold");
+  assert!(errors.is_empty());
+}
+
+#[test]
+fn falls_back_for_unresolved_variables() {
+  let (out, errors) = format(".local $y = {$x}\n{{{$x} {$y}}}", &[]);
+  assert_eq!(out, "{$x} {$x}");
+  assert!(matches!(
+    errors.as_slice(),
+    [
+      FormatError::UnresolvedVariable { name: a, .. },
+      FormatError::UnresolvedVariable { name: b, .. },
+    ] if a == "x" && b == "x"
+  ));
+}
+
+#[test]
+fn falls_back_to_the_expressions_of_local_declarations() {
+  let (out, errors) = format(
+    ".local $a = {|x| :nope}\n.local $b = {:nope}\n.local $c = {$a}\n\
+     {{{$a} {$b} {$c} {$c :string}}}",
+    &[],
+  );
+  assert_eq!(out, "{|x|} {:nope} {|x|} {|x|}");
+  assert!(errors
+    .iter()
+    .all(|error| matches!(error, FormatError::UnknownFunction { .. })));
+}
+
+#[test]
+fn falls_back_for_unknown_functions() {
+  let (out, errors) = format(
+    "{$x :nope} {|a\\|b| :nope} {:ns:nope}",
+    &[("x", "value".into())],
+  );
+  assert_eq!(out, "{$x} {|a\\|b|} {:ns:nope}");
+  let names = errors
+    .iter()
+    .map(|error| match error {
+      FormatError::UnknownFunction { name, .. } => name.as_str(),
+      error => panic!("unexpected error {error:?}"),
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(names, ["nope", "nope", "ns:nope"]);
+}
+
+#[test]
+fn selects_the_catch_all_variant() {
+  let (out, errors) = format(
+    ".input {$x}\n.match $x\n1 {{one}}\n* {{other {$x}}}",
+    &[("x", 1.into())],
+  );
+  assert_eq!(out, "other 1");
+  assert!(matches!(
+    errors.as_slice(),
+    [FormatError::BadSelector { .. }]
+  ));
+}
+
+#[test]
+fn formats_to_parts() {
+  let (message, _diagnostics, _info) =
+    parse("Hi \\{{#link href=$url u:x=|a|}{$name}{/link}{$missing}{#br/}");
+  let arguments = Arguments::from([
+    ("name".to_string(), "Ana".into()),
+    ("url".to_string(), "/ana".into()),
+  ]);
+  let (parts, errors) = format_to_parts(&message, "en-US", &arguments);
+  assert_eq!(
+    parts,
+    [
+      FormattedPart::Text {
+        value: "Hi {".to_string()
+      },
+      FormattedPart::Markup {
+        kind: MarkupKind::Open,
+        name: "link".to_string(),
+        options: BTreeMap::from([
+          ("href".to_string(), "/ana".into()),
+          ("u:x".to_string(), "a".into()),
+        ]),
+      },
+      FormattedPart::Expression {
+        source: "$name".to_string(),
+        value: "Ana".to_string(),
+      },
+      FormattedPart::Markup {
+        kind: MarkupKind::Close,
+        name: "link".to_string(),
+        options: BTreeMap::new(),
+      },
+      FormattedPart::Fallback {
+        source: "$missing".to_string()
+      },
+      FormattedPart::Markup {
+        kind: MarkupKind::Standalone,
+        name: "br".to_string(),
+        options: BTreeMap::new(),
+      },
+    ]
+  );
+  assert!(matches!(
+    errors.as_slice(),
+    [FormatError::UnresolvedVariable { .. }]
+  ));
+}
+
+#[test]
+fn selects_the_fallback_variant_for_bad_selectors() {
+  let (out, errors) = format(
+    ".input {$x}\n.input {$y}\n.match $x $y\n1 1 {{one one}}\n1 * {{one other}}\n* * {{other other}}",
+    &[("x", 1.into()), ("y", 1.into())],
+  );
+  assert_eq!(out, "other other");
+  assert!(matches!(
+    errors.as_slice(),
+    [
+      FormatError::BadSelector { .. },
+      FormatError::BadSelector { .. }
+    ]
+  ));
+}
+
+#[test]
+fn reports_missing_fallback_variants() {
+  let (out, errors) =
+    format(".input {$x}\n.match $x\n1 {{one}}", &[("x", 1.into())]);
+  assert_eq!(out, "");
+  assert!(matches!(
+    errors.as_slice(),
+    [
+      FormatError::BadSelector { .. },
+      FormatError::NoFallback { .. }
+    ]
+  ));
+}
+
+/// Formats a number as an amount of money in the currency of its `currency`
+/// option, with `decimals` fraction digits.
+struct Money;
+
+impl Function for Money {
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    options: &FunctionOptions,
+    _locale: &str,
+  ) -> Result<String, FunctionError> {
+    let Some(Value::Number(amount)) = operand else {
+      return Err(FunctionError::BadOperand);
+    };
+    let currency = options.get_string("currency")?.unwrap_or("USD");
+    let decimals = options.get_integer("decimals")?.unwrap_or(2) as usize;
+    Ok(format!("{currency} {amount:.decimals$}"))
+  }
+
+  fn option_names(&self) -> Option<&[&str]> {
+    Some(&["currency", "decimals"])
+  }
+}
+
+/// Selects the key that is equal to its operand.
+struct Gender;
+
+impl Function for Gender {
+  fn format(
+    &self,
+    operand: Option<&Value>,
+    _options: &FunctionOptions,
+    _locale: &str,
+  ) -> Result<String, FunctionError> {
+    operand
+      .map(Value::to_string)
+      .ok_or(FunctionError::BadOperand)
+  }
+
+  fn select(
+    &self,
+    operand: Option<&Value>,
+    _options: &FunctionOptions,
+    _locale: &str,
+    keys: &[&str],
+  ) -> Result<Vec<usize>, FunctionError> {
+    let operand = operand.ok_or(FunctionError::BadOperand)?.to_string();
+    Ok(
+      keys
+        .iter()
+        .position(|key| *key == operand)
+        .into_iter()
+        .collect(),
+    )
+  }
+}
+
+fn registry() -> FunctionRegistry {
+  let mut functions = FunctionRegistry::new();
+  functions.register(&Identifier::new(None, "money"), Money);
+  functions.register(&Identifier::new(Some("x"), "gender"), Gender);
+  functions
+}
+
+fn format_with_functions(
+  source: &str,
+  arguments: &[(&str, Value)],
+) -> (String, Vec<FormatError>) {
+  let (message, diagnostics, _info) = parse(source);
+  assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+  let arguments = arguments
+    .iter()
+    .map(|(name, value)| (name.to_string(), value.clone()))
+    .collect::<Arguments>();
+  format_message_with_functions(&message, "en-US", &arguments, &registry())
+}
+
+#[test]
+fn formats_with_registered_functions() {
+  let (out, errors) = format_with_functions(
+    "{$a :money} {$a :money currency=EUR decimals=0} {$a :x:gender}",
+    &[("a", 12.5.into())],
+  );
+  assert_eq!(out, "USD 12.50 EUR 12 12.5");
+  assert!(errors.is_empty(), "{errors:#?}");
+}
+
+#[test]
+fn keeps_the_options_of_annotated_variables() {
+  let (out, errors) = format_with_functions(
+    ".input {$a :money currency=EUR}\n.local $b = {$a :money decimals=1}\n.local $c = {$b :x:gender}\n{{{$a} {$b} {$c :money}}}",
+    &[("a", 3.into())],
+  );
+  assert_eq!(out, "EUR 3.00 EUR 3.0 USD 3.00");
+  assert!(errors.is_empty(), "{errors:#?}");
+}
+
+#[test]
+fn falls_back_for_function_errors() {
+  let (out, errors) = format_with_functions(
+    "{|text| :money} {$a :money decimals=-1} {$a :x:nope}",
+    &[("a", 1.into())],
+  );
+  assert_eq!(out, "{|text|} {$a} {$a}");
+  assert!(matches!(
+    errors.as_slice(),
+    [
+      FormatError::BadOperand { .. },
+      FormatError::BadOption { name, .. },
+      FormatError::UnknownFunction { .. },
+    ] if name == "decimals"
+  ));
+}
+
+#[test]
+fn selects_with_registered_functions() {
+  let source = ".input {$g :x:gender}\n.match $g\nfemale {{her}}\nmale {{his}}\n* {{their}}";
+  for (gender, expected) in
+    [("female", "her"), ("male", "his"), ("other", "their")]
+  {
+    let (out, errors) = format_with_functions(source, &[("g", gender.into())]);
+    assert_eq!(out, expected);
+    assert!(errors.is_empty(), "{errors:#?}");
+  }
+
+  let (out, errors) = format_with_functions(
+    ".input {$a :money}\n.match $a\n1 {{one}}\n* {{other}}",
+    &[("a", 1.into())],
+  );
+  assert_eq!(out, "other");
+  assert!(matches!(
+    errors.as_slice(),
+    [FormatError::BadSelector { .. }]
+  ));
+}
+
+#[test]
+fn validates_option_names() {
+  let (message, _diagnostics, _info) =
+    parse("{$a :money currency=EUR rounding=up u:id=x} {$a :nope rounding=up}");
+  let diagnostics = validate_with_functions(&message, &registry());
+  assert!(matches!(
+    diagnostics.as_slice(),
+    [Diagnostic::UnsupportedOption { .. }]
+  ));
+}
+
+fn format_in(
+  locale: &str,
+  source: &str,
+  arguments: &[(&str, Value)],
+) -> (String, Vec<FormatError>) {
+  let (message, diagnostics, _info) = parse(source);
+  assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+  let arguments = arguments
+    .iter()
+    .map(|(name, value)| (name.to_string(), value.clone()))
+    .collect::<Arguments>();
+  format_message(&message, locale, &arguments)
+}
+
+#[test]
+fn formats_numbers() {
+  let (out, errors) = format(
+    "{$a :number} {1234567.891 :number} {|0.5| :number minimumFractionDigits=2} {2.5 :number maximumFractionDigits=0} {$a :integer}",
+    &[("a", (-1234.5678).into())],
+  );
+  assert_eq!(out, "-1,234.568 1,234,567.891 0.50 3 -1,234");
+  assert!(errors.is_empty(), "{errors:#?}");
+
+  let (out, errors) = format(
+    "{1 :number signDisplay=always} {0 :number signDisplay=exceptZero} {-1 :number signDisplay=never} {1234 :number useGrouping=never} {5 :integer minimumIntegerDigits=3}",
+    &[],
+  );
+  assert_eq!(out, "+1 0 1 1234 005");
+  assert!(errors.is_empty(), "{errors:#?}");
+}
+
+#[test]
+fn formats_numbers_for_the_locale() {
+  let source = "{1234.5 :number} {12345 :number}";
+  assert_eq!(format_in("de-DE", source, &[]).0, "1.234,5 12.345");
+  assert_eq!(format_in("es", source, &[]).0, "1234,5 12.345");
+  assert_eq!(
+    format_in("fr", source, &[]).0,
+    "1\u{202F}234,5 12\u{202F}345"
+  );
+}
+
+#[test]
+fn falls_back_for_bad_numbers() {
+  let (out, errors) = format(
+    "{|abc| :number} {$a :integer} {1e1000 :number} {1 :number minimumFractionDigits=4 maximumFractionDigits=2} {1 :number signDisplay=sometimes}",
+    &[("a", "1.5x".into())],
+  );
+  assert_eq!(out, "{|abc|} {$a} {|1e1000|} {|1|} {|1|}");
+  assert!(matches!(
+    errors.as_slice(),
+    [
+      FormatError::BadOperand { .. },
+      FormatError::BadOperand { .. },
+      FormatError::BadOperand { .. },
+      FormatError::BadOption { name: a, .. },
+      FormatError::BadOption { name: b, .. },
+    ] if a == "maximumFractionDigits" && b == "signDisplay"
+  ));
+}
+
+#[test]
+fn selects_plural_categories() {
+  let source = ".input {$n :number}\n.match $n\n0 {{zero}}\none {{one}}\nfew {{few}}\nmany {{many}}\n* {{other}}";
+  let cases: &[(&str, Value, &str)] = &[
+    ("en", 0.into(), "zero"),
+    ("en", 1.into(), "one"),
+    ("en", 1.5.into(), "other"),
+    ("en", "1.0".into(), "one"),
+    ("fr", 1.5.into(), "one"),
+    ("ru", 21.into(), "one"),
+    ("ru", 3.into(), "few"),
+    ("ru", 11.into(), "many"),
+    ("pl", 22.into(), "few"),
+    ("ja", 1.into(), "other"),
+  ];
+  for (locale, n, expected) in cases {
+    let (out, errors) = format_in(locale, source, &[("n", n.clone())]);
+    assert_eq!(out, *expected, "{locale} {n:?}");
+    assert!(errors.is_empty(), "{errors:#?}");
+  }
+}
+
+#[test]
+fn selects_exact_numbers() {
+  let source = ".input {$n :number select=exact}\n.match $n\n1 {{exact}}\none {{one}}\n* {{other}}";
+  assert_eq!(format(source, &[("n", 1.into())]).0, "exact");
+  assert_eq!(format(source, &[("n", "1.0".into())]).0, "exact");
+  assert_eq!(format(source, &[("n", 2.into())]).0, "other");
+
+  let source = ".input {$n :number maximumFractionDigits=1}\n.match $n\n1.5 {{exact}}\none {{one}}\n* {{other}}";
+  assert_eq!(format(source, &[("n", 1.54.into())]).0, "exact");
+
+  let source = ".input {$n :integer select=ordinal}\n.match $n\none {{st}}\ntwo {{nd}}\nfew {{rd}}\n* {{th}}";
+  assert_eq!(format(source, &[("n", 22.7.into())]).0, "nd");
+  assert_eq!(format(source, &[("n", 13.into())]).0, "th");
+}
+
+#[test]
+fn validates_number_options() {
+  let (message, _diagnostics, _info) =
+    parse("{1 :number minimumFractionDigits=1 style=percent} {1 :integer maximumFractionDigits=2}");
+  let diagnostics = validate_with_functions(
+    &message,
+    &FunctionRegistry::with_default_functions(),
+  );
+  assert_eq!(diagnostics.len(), 2, "{diagnostics:#?}");
+}
+
+#[test]
+fn formats_strings() {
+  let (out, errors) = format(
+    "{$a :string} {|x| :string} {$b :string} {$b}",
+    &[("a", "Ana".into()), ("b", 1.5.into())],
+  );
+  assert_eq!(out, "Ana x 1.5 1.5");
+  assert!(errors.is_empty(), "{errors:#?}");
+}
+
+#[test]
+fn selects_strings_after_normalization() {
+  let source = ".input {$x :string}\n.match $x\ne\u{301} {{unquoted}}\n|\\|\u{e5}\\\\| {{quoted}}\n* {{other}}";
+  for (x, expected) in [
+    ("\u{e9}", "unquoted"),
+    ("e\u{301}", "unquoted"),
+    ("|a\u{30a}\\", "quoted"),
+    ("e", "other"),
+  ] {
+    let (out, errors) = format(source, &[("x", x.into())]);
+    assert_eq!(out, expected, "{x:?}");
+    assert!(errors.is_empty(), "{errors:#?}");
+  }
+}
+
+#[test]
+fn formats_unannotated_placeholders_with_the_string_function() {
+  struct Upper;
+
+  impl Function for Upper {
+    fn format(
+      &self,
+      operand: Option<&Value>,
+      _options: &FunctionOptions,
+      _locale: &str,
+    ) -> Result<String, FunctionError> {
+      let operand = operand.ok_or(FunctionError::BadOperand)?;
+      Ok(operand.to_string().to_uppercase())
+    }
+  }
+
+  let (message, _diagnostics, _info) = parse("{$a} {|b|} {$a :string}");
+  let mut functions = FunctionRegistry::new();
+  functions.register(&Identifier::new(None, "string"), Upper);
+  let arguments = Arguments::from([("a".to_string(), "a".into())]);
+  let (out, errors) =
+    format_message_with_functions(&message, "en-US", &arguments, &functions);
+  assert_eq!(out, "A B A");
+  assert!(errors.is_empty(), "{errors:#?}");
+}
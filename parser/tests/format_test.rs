@@ -0,0 +1,84 @@
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+use file_test_runner::collect_and_run_tests;
+use file_test_runner::collection::strategies::TestPerFileCollectionStrategy;
+use file_test_runner::collection::CollectOptions;
+use file_test_runner::collection::CollectedTest;
+use file_test_runner::RunOptions;
+use file_test_runner::TestResult;
+use mf2_parser::format_source;
+use mf2_parser::parse;
+use mf2_parser::FormatOptions;
+
+fn main() {
+  collect_and_run_tests(
+    CollectOptions {
+      base: Path::new("tests").join("format"),
+      strategy: Box::new(TestPerFileCollectionStrategy { file_pattern: None }),
+      filter_override: None,
+    },
+    RunOptions { parallel: true },
+    |test| {
+      TestResult::from_maybe_panic(AssertUnwindSafe(|| {
+        run_test(test);
+      }))
+    },
+  )
+}
+
+fn run_test(test: &CollectedTest) {
+  let file_text = test.read_to_string().unwrap();
+
+  let output_marker = "\n=== output ===\n";
+
+  let (message, expected_output) = file_text
+    .split_once(output_marker)
+    .unwrap_or((&*file_text, ""));
+
+  let options = format_options_for_test(&test.path);
+
+  let (ast, diagnostics, _info) = parse(message);
+  assert!(
+    diagnostics.is_empty(),
+    "input has diagnostics: {diagnostics:?}"
+  );
+  let actual_output = format_source(&ast, &options);
+
+  // Formatting must be idempotent.
+  let (reformatted_ast, diagnostics, _info) = parse(&actual_output);
+  assert!(
+    diagnostics.is_empty(),
+    "output has diagnostics: {diagnostics:?}"
+  );
+  pretty_assertions::assert_eq!(
+    format_source(&reformatted_ast, &options),
+    actual_output,
+    "formatting is not idempotent"
+  );
+
+  if std::env::var("UPDATE").is_ok() || expected_output.is_empty() {
+    std::fs::write(
+      &test.path,
+      format!("{message}{output_marker}{actual_output}"),
+    )
+    .unwrap();
+  } else {
+    pretty_assertions::assert_eq!(actual_output, expected_output);
+  }
+}
+
+/// Tests inside of an `unaligned` directory are formatted without key
+/// alignment, and tests inside of an `indent_4` directory with an indent width
+/// of 4. All other tests use the default options.
+fn format_options_for_test(path: &Path) -> FormatOptions {
+  let mut options = FormatOptions::default();
+  for component in path.components() {
+    match component.as_os_str().to_str() {
+      Some("unaligned") => options.align_keys = false,
+      Some("indent_4") => options.indent_width = 4,
+      _ => {}
+    }
+  }
+  options
+}
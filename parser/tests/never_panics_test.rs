@@ -0,0 +1,163 @@
+//! Property test for the guarantee that the parser never panics, whatever the
+//! input. Inputs are generated from a fixed seed, biased towards fragments of
+//! MessageFormat 2 syntax so that most of them reach deep into the parser.
+
+use std::path::Path;
+
+use mf2_parser::parse_cst;
+use mf2_parser::parse_with_options;
+use mf2_parser::validate;
+use mf2_parser::ParseOptions;
+use mf2_parser::SpecVersion;
+
+const SPEC_VERSIONS: [SpecVersion; 3] =
+  [SpecVersion::Draft, SpecVersion::Ldml46, SpecVersion::Ldml47];
+
+const FRAGMENTS: &[&str] = &[
+  "{",
+  "}",
+  "{{",
+  "}}",
+  "|",
+  "\\",
+  "$",
+  ":",
+  "@",
+  "#",
+  "/",
+  "=",
+  "*",
+  ".",
+  "-",
+  "+",
+  "e",
+  "0",
+  "1",
+  ".5",
+  "1e3",
+  "-1.0e-2",
+  "a",
+  "foo",
+  "ns:fn",
+  " ",
+  "\t",
+  "\n",
+  "\r",
+  "\u{3000}",
+  "\u{61C}",
+  "\u{200E}",
+  "\u{200F}",
+  "\u{2066}",
+  "\u{2067}",
+  "\u{2068}",
+  "\u{2069}",
+  "\u{FFFD}",
+  "\u{10FFFF}",
+  "\u{1F600}",
+  "\u{300}",
+  "é",
+  ".input",
+  ".local",
+  ".match",
+  ".foo",
+  "!",
+  "%",
+  "^",
+  "&",
+  "?",
+  "~",
+  "<",
+  ">",
+  "|quoted|",
+  "\\{",
+  "\\|",
+  "\\\\",
+  "$var",
+  ":number",
+  "@attr",
+  "#open",
+  "/close",
+  "{$x}",
+  "{{text}}",
+];
+
+/// A small xorshift generator, so that failures are reproducible from the
+/// fixed seed without pulling in a dependency.
+struct Rng(u64);
+
+impl Rng {
+  fn next(&mut self) -> u64 {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    self.0
+  }
+
+  fn below(&mut self, n: usize) -> usize {
+    (self.next() % n as u64) as usize
+  }
+
+  fn message(&mut self) -> String {
+    let mut message = String::new();
+    for _ in 0..self.below(40) {
+      if self.below(8) == 0 {
+        let c = char::from_u32(self.next() as u32 % 0x11_0000);
+        message.extend(c);
+      } else {
+        message.push_str(FRAGMENTS[self.below(FRAGMENTS.len())]);
+      }
+    }
+    message
+  }
+}
+
+fn assert_no_panic(message: &str) {
+  for spec_version in SPEC_VERSIONS {
+    let options = ParseOptions::default().with_spec_version(spec_version);
+    let result = std::panic::catch_unwind(|| {
+      let (ast, _, _) = parse_with_options(message, options.clone());
+      validate(&ast);
+    });
+    assert!(
+      result.is_ok(),
+      "parser panicked on {message:?} with {spec_version:?}"
+    );
+    let (cst, _, _) = parse_cst(message, options);
+    assert_eq!(cst.to_string(), message, "the CST is not lossless");
+  }
+}
+
+#[test]
+fn random_messages() {
+  let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+  for _ in 0..20_000 {
+    assert_no_panic(&rng.message());
+  }
+}
+
+#[test]
+fn snapshot_prefixes() {
+  fn visit(dir: &Path) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+      let path = entry.unwrap().path();
+      if path.is_dir() {
+        visit(&path);
+        continue;
+      }
+      let file = std::fs::read_to_string(&path).unwrap();
+      let message = file.split("\n=== spans ===\n").next().unwrap();
+      for (i, _) in message.char_indices() {
+        assert_no_panic(&message[..i]);
+        assert_no_panic(&message[i..]);
+      }
+    }
+  }
+  visit(&Path::new("tests").join("parser"));
+}
+
+#[test]
+fn long_numbers() {
+  assert_no_panic(&format!("{{{}}}", "1".repeat(70_000)));
+  assert_no_panic(&format!("{{|{}|}}", "a".repeat(70_000)));
+  assert_no_panic(&"{".repeat(10_000));
+}
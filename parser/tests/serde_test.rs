@@ -0,0 +1,59 @@
+#![cfg(feature = "serde")]
+
+use mf2_parser::ast::Message;
+use mf2_parser::parse;
+
+#[test]
+fn message_round_trips() {
+  let source =
+    ".local $x = {|a\\|b| :fn opt=1e3 @attr}\n.match $x\n* {{Hi {#b}{$x}{/b}}}";
+  let (message, diagnostics, _info) = parse(source);
+  assert!(diagnostics.is_empty());
+
+  let json = serde_json::to_string(&message).unwrap();
+  let deserialized: Message = serde_json::from_str(&json).unwrap();
+  assert_eq!(format!("{deserialized:#?}"), format!("{message:#?}"));
+}
+
+#[test]
+fn message_json() {
+  let (message, _diagnostics, _info) = parse("Hi {$name}");
+  pretty_assertions::assert_eq!(
+    serde_json::to_value(message).unwrap(),
+    serde_json::json!({
+      "Simple": {
+        "parts": [
+          { "Text": { "start": 0, "content": "Hi " } },
+          {
+            "Expression": {
+              "VariableExpression": {
+                "span": { "start": 3, "end": 10 },
+                "variable": {
+                  "span": { "start": 4, "end": 9 },
+                  "name": "name",
+                },
+                "annotation": null,
+                "attributes": [],
+              },
+            },
+          },
+        ],
+      },
+    }),
+  );
+}
+
+#[test]
+fn diagnostic_json() {
+  let (_message, diagnostics, _info) = parse("Hi {$}");
+  pretty_assertions::assert_eq!(
+    serde_json::to_value(&diagnostics).unwrap(),
+    serde_json::json!([
+      {
+        "kind": "VariableMissingName",
+        "span": { "start": 4, "end": 5 },
+        "message": diagnostics[0].to_string(),
+      },
+    ]),
+  );
+}
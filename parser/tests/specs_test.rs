@@ -1,6 +1,5 @@
 use std::fmt::Write;
 use std::iter;
-use std::panic;
 use std::panic::AssertUnwindSafe;
 use std::path::Path;
 
@@ -12,10 +11,17 @@ use file_test_runner::RunOptions;
 use file_test_runner::TestResult;
 use mf2_parser::ast;
 use mf2_parser::ast::Message;
-use mf2_parser::parse;
+use mf2_parser::format_source;
+use mf2_parser::parse_cst;
+use mf2_parser::parse_with_options;
+use mf2_parser::print;
+use mf2_parser::tokenize_with_options;
 use mf2_parser::Diagnostic;
+use mf2_parser::FormatOptions;
+use mf2_parser::ParseOptions;
 use mf2_parser::Span;
 use mf2_parser::Spanned;
+use mf2_parser::SpecVersion;
 use mf2_parser::Visit;
 use mf2_parser::Visitable;
 use unicode_width::UnicodeWidthStr;
@@ -53,19 +59,8 @@ fn run_test(test: &CollectedTest) {
     rest_str.split_once(ast_marker).unwrap_or((rest_str, ""));
   let expected_ast_dbg = rest_str;
 
-  if test
-    .path
-    .file_name()
-    .and_then(|f| f.to_str())
-    .map(|s| s.ends_with(".panic"))
-    .unwrap_or(false)
-  {
-    let result = panic::catch_unwind(|| parse(message));
-    if result.is_ok() {
-      panic!("expected panic, but parsing didn't");
-    }
-    return;
-  }
+  let options = ParseOptions::default()
+    .with_spec_version(spec_version_for_test(&test.path));
 
   let normalized_message = message
     .chars()
@@ -76,7 +71,15 @@ fn run_test(test: &CollectedTest) {
     })
     .collect::<String>();
 
-  let (actual_ast, diagnostics, _info) = parse(message);
+  let (actual_ast, diagnostics, _info) =
+    parse_with_options(message, options.clone());
+
+  let (cst, _, _) = parse_cst(message, options.clone());
+  assert_eq!(cst.to_string(), message, "the CST is not lossless");
+  let tokens = tokenize_with_options(message, options.clone())
+    .map(|token| token.text)
+    .collect::<String>();
+  assert_eq!(tokens, message, "the tokens do not cover the message");
 
   let actual_ast_dbg = generated_actual_ast_dbg(&actual_ast);
   let actual_spans =
@@ -84,6 +87,19 @@ fn run_test(test: &CollectedTest) {
   let actual_diags =
     generate_actual_diagnostics(&diagnostics, message, &normalized_message);
 
+  assert_into_owned(
+    message,
+    options.clone(),
+    &actual_ast_dbg,
+    &actual_diags,
+    &normalized_message,
+  );
+
+  if diagnostics.is_empty() {
+    assert_round_trips(&actual_ast, options.clone());
+    assert_formats_idempotently(&actual_ast, options);
+  }
+
   let mut need_update = std::env::var("UPDATE").is_ok();
   if !need_update {
     if expected_diagnostics.is_empty() {
@@ -114,6 +130,93 @@ fn run_test(test: &CollectedTest) {
   }
 }
 
+/// Owned messages and diagnostics must be the same as the borrowed ones, and
+/// must outlive the source text they were parsed from.
+fn assert_into_owned(
+  message: &str,
+  options: ParseOptions,
+  ast_dbg: &str,
+  diagnostics: &str,
+  normalized_message: &str,
+) {
+  let (owned_ast, owned_diagnostics) = {
+    let source = message.to_string();
+    let (ast, diagnostics, _info) = parse_with_options(&source, options);
+    let diagnostics = diagnostics
+      .into_iter()
+      .map(Diagnostic::into_owned)
+      .collect::<Vec<_>>();
+    (ast.into_owned(), diagnostics)
+  };
+  assert_eq!(generated_actual_ast_dbg(&owned_ast), ast_dbg);
+  assert_eq!(
+    generate_actual_diagnostics(
+      &owned_diagnostics,
+      message,
+      normalized_message
+    ),
+    diagnostics
+  );
+}
+
+/// Printing a message and parsing it again must give the same message, apart
+/// from the positions of the nodes.
+fn assert_round_trips(message: &Message, options: ParseOptions) {
+  let printed = print(message);
+  let (reparsed, diagnostics, _info) = parse_with_options(&printed, options);
+  assert!(
+    diagnostics.is_empty(),
+    "printed message {printed:?} has diagnostics: {diagnostics:?}"
+  );
+  pretty_assertions::assert_eq!(
+    strip_locations(&generated_actual_ast_dbg(&reparsed)),
+    strip_locations(&generated_actual_ast_dbg(message)),
+    "printed message {printed:?} does not round trip"
+  );
+}
+
+/// Formatting a message must give a valid message, which formats to itself.
+fn assert_formats_idempotently(message: &Message, options: ParseOptions) {
+  let format_options = FormatOptions::default();
+  let formatted = format_source(message, &format_options);
+  let (reparsed, diagnostics, _info) = parse_with_options(&formatted, options);
+  assert!(
+    diagnostics.is_empty(),
+    "formatted message {formatted:?} has diagnostics: {diagnostics:?}"
+  );
+  pretty_assertions::assert_eq!(
+    format_source(&reparsed, &format_options),
+    formatted,
+    "formatting is not idempotent"
+  );
+}
+
+/// Removes all locations (`@1`) and spans (`@1..2`) from a debug printed AST.
+fn strip_locations(ast_dbg: &str) -> String {
+  let mut out = String::new();
+  let mut chars = ast_dbg.chars().peekable();
+  while let Some(c) = chars.next() {
+    out.push(c);
+    if c == '@' {
+      while chars.next_if(|c| c.is_ascii_digit() || *c == '.').is_some() {}
+    }
+  }
+  out
+}
+
+/// Tests inside of a `draft` or `ldml46` directory are parsed against that
+/// version of the spec. All other tests use the default version.
+fn spec_version_for_test(path: &Path) -> SpecVersion {
+  path
+    .components()
+    .find_map(|component| match component.as_os_str().to_str() {
+      Some("draft") => Some(SpecVersion::Draft),
+      Some("ldml46") => Some(SpecVersion::Ldml46),
+      _ => None,
+    })
+    .unwrap_or_default()
+}
+
 fn generated_actual_ast_dbg(actual_ast: &Message) -> String {
   format!("{actual_ast:#?}")
 }
@@ -0,0 +1,92 @@
+use mf2_parser::tokenize;
+use mf2_parser::TokenKind;
+use mf2_parser::TokenKind::*;
+
+fn assert_tokens(message: &str, expected: &[(TokenKind, &str)]) {
+  let actual = tokenize(message)
+    .map(|token| (token.kind, token.text))
+    .collect::<Vec<_>>();
+  pretty_assertions::assert_eq!(actual, expected);
+}
+
+#[test]
+fn simple_message() {
+  assert_tokens(
+    "Hello {$name :string} \\{!",
+    &[
+      (Text, "Hello "),
+      (OpenBrace, "{"),
+      (Variable, "$name"),
+      (Whitespace, " "),
+      (FunctionSigil, ":"),
+      (Name, "string"),
+      (CloseBrace, "}"),
+      (Text, " "),
+      (Escape, "\\{"),
+      (Text, "!"),
+    ],
+  );
+}
+
+#[test]
+fn complex_message() {
+  assert_tokens(
+    ".local $x = {|a b| :ns:fn opt=1.5}\n.match $x\n* {{{#b/}}}",
+    &[
+      (Keyword, ".local"),
+      (Whitespace, " "),
+      (Variable, "$x"),
+      (Whitespace, " "),
+      (Equals, "="),
+      (Whitespace, " "),
+      (OpenBrace, "{"),
+      (Quoted, "|a b|"),
+      (Whitespace, " "),
+      (FunctionSigil, ":"),
+      (Name, "ns"),
+      (Colon, ":"),
+      (Name, "fn"),
+      (Whitespace, " "),
+      (Name, "opt"),
+      (Equals, "="),
+      (Number, "1.5"),
+      (CloseBrace, "}"),
+      (Whitespace, "\n"),
+      (Keyword, ".match"),
+      (Whitespace, " "),
+      (Variable, "$x"),
+      (Whitespace, "\n"),
+      (Star, "*"),
+      (Whitespace, " "),
+      (OpenQuotedPattern, "{{"),
+      (OpenBrace, "{"),
+      (MarkupSigil, "#"),
+      (Name, "b"),
+      (SelfClose, "/"),
+      (CloseBrace, "}"),
+      (CloseQuotedPattern, "}}"),
+    ],
+  );
+}
+
+#[test]
+fn invalid_contents() {
+  assert_tokens(
+    "{$x ;} {/a @b}",
+    &[
+      (OpenBrace, "{"),
+      (Variable, "$x"),
+      (Whitespace, " "),
+      (Error, ";"),
+      (CloseBrace, "}"),
+      (Text, " "),
+      (OpenBrace, "{"),
+      (MarkupSigil, "/"),
+      (Name, "a"),
+      (Whitespace, " "),
+      (At, "@"),
+      (Name, "b"),
+      (CloseBrace, "}"),
+    ],
+  );
+}
@@ -0,0 +1,78 @@
+use std::fmt::Write;
+use std::iter;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+use file_test_runner::collect_and_run_tests;
+use file_test_runner::collection::strategies::TestPerFileCollectionStrategy;
+use file_test_runner::collection::CollectOptions;
+use file_test_runner::collection::CollectedTest;
+use file_test_runner::RunOptions;
+use file_test_runner::TestResult;
+use mf2_parser::parse;
+use mf2_parser::validate;
+use unicode_width::UnicodeWidthStr;
+
+fn main() {
+  collect_and_run_tests(
+    CollectOptions {
+      base: Path::new("tests").join("validate"),
+      strategy: Box::new(TestPerFileCollectionStrategy { file_pattern: None }),
+      filter_override: None,
+    },
+    RunOptions { parallel: true },
+    |test| {
+      TestResult::from_maybe_panic(AssertUnwindSafe(|| {
+        run_test(test);
+      }))
+    },
+  )
+}
+
+fn run_test(test: &CollectedTest) {
+  let file_text = test.read_to_string().unwrap();
+
+  let diagnostics_marker = "\n=== diagnostics ===\n";
+
+  // Messages without data model errors have an empty diagnostics section, so
+  // only a missing section means that the expectation needs to be generated.
+  let (message, expected_diagnostics) =
+    match file_text.split_once(diagnostics_marker) {
+      Some((message, expected)) => (message, Some(expected)),
+      None => (&*file_text, None),
+    };
+
+  let (ast, diagnostics, _info) = parse(message);
+  assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+
+  let normalized_message = message.replace('\n', "↵");
+  let mut actual_diagnostics = String::new();
+  for (i, diagnostic) in validate(&ast).iter().enumerate() {
+    let span = diagnostic.span();
+    let start = span.start.inner_byte_index_for_test() as usize;
+    let end = span.end.inner_byte_index_for_test() as usize;
+
+    if i != 0 {
+      actual_diagnostics.push('\n');
+    }
+    writeln!(actual_diagnostics, "{diagnostic}").unwrap();
+    writeln!(actual_diagnostics, "  {normalized_message}").unwrap();
+    iter::repeat(' ')
+      .take(message[..start].width_cjk() + 2)
+      .chain(iter::repeat('^').take(message[start..end].width_cjk()))
+      .for_each(|c| actual_diagnostics.push(c));
+  }
+
+  if std::env::var("UPDATE").is_ok() || expected_diagnostics.is_none() {
+    std::fs::write(
+      &test.path,
+      format!("{message}{diagnostics_marker}{actual_diagnostics}"),
+    )
+    .unwrap();
+  } else {
+    pretty_assertions::assert_eq!(
+      Some(&*actual_diagnostics),
+      expected_diagnostics
+    );
+  }
+}
@@ -0,0 +1,120 @@
+#!/bin/sh
+# Generates `src/functions/number_symbols_data.rs` from the CLDR number
+# symbols of an ICU installation, which requires a C++ compiler and the ICU
+# development files (like the `libicu-dev` package). Only the symbols of the
+# Latin digits are kept, since numbers are always formatted with them.
+#
+# Usage: tools/generate_number_symbols.sh
+
+set -eu
+
+out="$(cd "$(dirname "$0")/.." && pwd)/src/functions/number_symbols_data.rs"
+tmp="$(mktemp -d)"
+trap 'rm -rf "$tmp"' EXIT
+
+cat >"$tmp/dump.cpp" <<'EOF'
+#include <unicode/dcfmtsym.h>
+#include <unicode/locid.h>
+#include <unicode/numberformatter.h>
+#include <unicode/ulocdata.h>
+
+#include <cstdio>
+#include <iostream>
+#include <string>
+
+using namespace icu;
+
+// Escapes a symbol as the contents of a Rust string literal.
+std::string escape(const UnicodeString& symbol) {
+  std::string out;
+  for (int32_t i = 0; i < symbol.length(); i = symbol.moveIndex32(i, 1)) {
+    UChar32 c = symbol.char32At(i);
+    if (c < 0x80 && c != '"' && c != '\\') {
+      out += (char)c;
+    } else {
+      char buffer[16];
+      snprintf(buffer, sizeof buffer, "\\u{%X}", c);
+      out += buffer;
+    }
+  }
+  return out;
+}
+
+// Prints the CLDR version, and then a line with the locale, the decimal
+// separator, the grouping separator, and the minimum grouping digits for every
+// locale without a script or variant.
+int main() {
+  UErrorCode status = U_ZERO_ERROR;
+  UVersionInfo version;
+  ulocdata_getCLDRVersion(version, &status);
+  std::cout << (int)version[0] << "\n";
+  int32_t count;
+  const Locale* locales = Locale::getAvailableLocales(count);
+  for (int32_t i = 0; i < count && U_SUCCESS(status); i++) {
+    if (*locales[i].getScript() || *locales[i].getVariant()) continue;
+    std::string id = locales[i].getBaseName();
+    Locale locale((id + "@numbers=latn").c_str());
+    DecimalFormatSymbols symbols(locale, status);
+    // The minimum grouping digits are not exposed, so they are found by
+    // formatting numbers with more and more digits until they are grouped.
+    int minimum_grouping = 1;
+    for (double number = 1000; minimum_grouping < 4; number *= 10) {
+      UnicodeString formatted = number::NumberFormatter::withLocale(locale)
+                                    .formatDouble(number, status)
+                                    .toString(status);
+      if (formatted.length() > (int32_t)std::to_string((long)number).size()) {
+        break;
+      }
+      minimum_grouping++;
+    }
+    std::cout << id << "\t"
+              << escape(symbols.getSymbol(
+                     DecimalFormatSymbols::kDecimalSeparatorSymbol))
+              << "\t"
+              << escape(symbols.getSymbol(
+                     DecimalFormatSymbols::kGroupingSeparatorSymbol))
+              << "\t" << minimum_grouping << "\n";
+  }
+  if (U_FAILURE(status)) {
+    std::cerr << u_errorName(status) << "\n";
+    return 1;
+  }
+}
+EOF
+# shellcheck disable=SC2046
+c++ -o "$tmp/dump" "$tmp/dump.cpp" $(pkg-config --cflags --libs icu-i18n)
+"$tmp/dump" >"$tmp/symbols.tsv"
+version=$(head -n 1 "$tmp/symbols.tsv")
+
+# Locales are only listed if their symbols differ from the ones of their
+# language, or for languages, from the default symbols.
+symbols() {
+  tail -n +2 "$tmp/symbols.tsv" | LC_ALL=C sort | awk -F '\t' '
+    {
+      split($1, subtags, "_")
+      symbols = $2 "\t" $3 "\t" $4
+      if (!(2 in subtags)) language[$1] = symbols
+      parent = (2 in subtags) && (subtags[1] in language) \
+        ? language[subtags[1]] : ".\t,\t1"
+      if (symbols != parent) {
+        locale = $1
+        gsub(/_/, "-", locale)
+        printf "  (\"%s\", \"%s\", \"%s\", %s),\n", locale, $2, $3, $4
+      }
+    }
+  ' | LC_ALL=C sort
+}
+
+{
+  echo "//! The CLDR decimal and grouping separators, and minimum grouping digits,"
+  echo "//! by locale. Locales that are not listed use the symbols of their"
+  echo "//! language, or \`.\`, \`,\`, and 1."
+  echo "//!"
+  echo "//! Generated by \`tools/generate_number_symbols.sh\` from CLDR $version. Do"
+  echo "//! not edit."
+  echo
+  echo "pub(super) const NUMBER_SYMBOLS: &[(&str, &str, &str, usize)] = &["
+  symbols
+  echo "];"
+} >"$out"
+rustfmt --edition 2021 "$out"
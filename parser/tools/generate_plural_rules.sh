@@ -0,0 +1,110 @@
+#!/bin/sh
+# Generates `src/functions/plural_rules_data.rs` from the CLDR plural rules of
+# an ICU installation, which requires a C++ compiler and the ICU development
+# files (like the `libicu-dev` package). The rules are written in the syntax of
+# the CLDR `plurals.json` and `ordinals.json` files, without the samples and
+# without the `other` category, which applies if no other category does.
+#
+# Usage: tools/generate_plural_rules.sh
+
+set -eu
+
+out="$(cd "$(dirname "$0")/.." && pwd)/src/functions/plural_rules_data.rs"
+tmp="$(mktemp -d)"
+trap 'rm -rf "$tmp"' EXIT
+
+cat >"$tmp/dump.cpp" <<'EOF'
+#include <unicode/plurrule.h>
+#include <unicode/strenum.h>
+#include <unicode/ulocdata.h>
+
+#include <iostream>
+#include <memory>
+#include <string>
+
+using namespace icu;
+
+// Prints the CLDR version, and then a line with the type, the locale, and the
+// rules for every locale with plural rules.
+int main() {
+  UErrorCode status = U_ZERO_ERROR;
+  UVersionInfo version;
+  ulocdata_getCLDRVersion(version, &status);
+  std::cout << (int)version[0] << "\n";
+  std::unique_ptr<StringEnumeration> locales(
+      PluralRules::getAvailableLocales(status));
+  const char* id;
+  while (U_SUCCESS(status) && (id = locales->next(nullptr, status))) {
+    for (auto type : {UPLURAL_TYPE_CARDINAL, UPLURAL_TYPE_ORDINAL}) {
+      std::unique_ptr<PluralRules> rules(
+          PluralRules::forLocale(Locale(id), type, status));
+      if (U_FAILURE(status)) break;
+      std::string text;
+      rules->getRules().toUTF8String(text);
+      std::cout << (type == UPLURAL_TYPE_CARDINAL ? "CARDINAL" : "ORDINAL")
+                << "\t" << id << "\t" << text << "\n";
+    }
+  }
+  if (U_FAILURE(status)) {
+    std::cerr << u_errorName(status) << "\n";
+    return 1;
+  }
+}
+EOF
+# shellcheck disable=SC2046
+c++ -o "$tmp/dump" "$tmp/dump.cpp" $(pkg-config --cflags --libs icu-i18n)
+"$tmp/dump" >"$tmp/rules.tsv"
+version=$(head -n 1 "$tmp/rules.tsv")
+
+# ICU prints the rules like `one: n  in 1..1; two: i mod 10 not in 2..4`,
+# which are converted to the CLDR syntax, like `one: n = 1; two: i % 10 !=
+# 2..4`, with the categories in the order of the CLDR files.
+rules() {
+  awk -F '\t' -v type="$1" '
+    $1 == type && $2 != "root" {
+      gsub(/  +/, " ", $3)
+      gsub(/ mod /, " % ", $3)
+      gsub(/ not in /, " != ", $3)
+      gsub(/ in /, " = ", $3)
+      n = split($3, rules, "; ")
+      text = ""
+      split("zero one two few many", order, " ")
+      for (o = 1; o <= 5; o++) {
+        for (r = 1; r <= n; r++) {
+          if (index(rules[r], order[o] ": ") == 1) {
+            text = text (text == "" ? "" : "; ") rules[r]
+          }
+        }
+      }
+      # Ranges of a single value, like `1..1`, are written as the value.
+      while (match(text, /[0-9]+\.\.[0-9]+/)) {
+        range = substr(text, RSTART, RLENGTH)
+        split(range, bounds, /\.\./)
+        if (bounds[1] == bounds[2]) range = bounds[1]
+        else range = bounds[1] "~" bounds[2]
+        text = substr(text, 1, RSTART - 1) range substr(text, RSTART + RLENGTH)
+      }
+      gsub(/~/, "..", text)
+      locale = $2
+      gsub(/_/, "-", locale)
+      printf "  (\"%s\", \"%s\"),\n", locale, text
+    }
+  ' "$tmp/rules.tsv" | LC_ALL=C sort
+}
+
+{
+  echo "//! The CLDR plural rules, by locale. Locales without rules here only use"
+  echo "//! the \`other\` category."
+  echo "//!"
+  echo "//! Generated by \`tools/generate_plural_rules.sh\` from CLDR $version. Do not"
+  echo "//! edit."
+  echo
+  echo "pub(super) const CARDINAL: &[(&str, &str)] = &["
+  rules CARDINAL
+  echo "];"
+  echo
+  echo "pub(super) const ORDINAL: &[(&str, &str)] = &["
+  rules ORDINAL
+  echo "];"
+} >"$out"
+rustfmt --edition 2021 "$out"
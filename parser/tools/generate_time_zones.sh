@@ -0,0 +1,34 @@
+#!/bin/sh
+# Generates `src/functions/time_zone_data.rs` from the TZif files of a tz
+# database installation (by default the one of the system). Only the POSIX TZ
+# rule at the end of each file is kept, which describes the current rules of
+# the time zone.
+#
+# Usage: tools/generate_time_zones.sh [path/to/zoneinfo]
+
+set -eu
+
+zoneinfo=${1:-/usr/share/zoneinfo}
+out="$(cd "$(dirname "$0")/.." && pwd)/src/functions/time_zone_data.rs"
+version=$(sed -n 's/^# version //p' "$zoneinfo/tzdata.zi")
+
+cd "$zoneinfo"
+{
+  echo "//! The current rules of the IANA time zones, as POSIX TZ strings, sorted by"
+  echo "//! identifier."
+  echo "//!"
+  echo "//! Generated by \`tools/generate_time_zones.sh\` from tzdata $version. Do not"
+  echo "//! edit."
+  echo
+  echo "pub(super) const TIME_ZONES: &[(&str, &str)] = &["
+  find . \( -type f -o -type l \) ! -path './posix/*' ! -path './right/*' \
+    | sed 's|^\./||' \
+    | grep -v -x -e localtime -e posixrules -e Factory \
+    | LC_ALL=C sort \
+    | while read -r id; do
+      [ "$(head -c 4 "$id")" = TZif ] || continue
+      printf '  ("%s", "%s"),\n' "$id" "$(tail -n 1 "$id")"
+    done
+  echo "];"
+} >"$out"
+rustfmt --edition 2021 "$out"